@@ -0,0 +1,18 @@
+extern crate tang;
+
+use std::io;
+use std::process;
+
+use tang::wu::lsp::Server;
+
+
+
+// a language server for editors, talking over stdin and stdout
+fn main() {
+  let stdin  = io::stdin();
+  let stdout = io::stdout();
+
+  let code = Server::new(stdin.lock(), stdout.lock()).run();
+
+  process::exit(code)
+}
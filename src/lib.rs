@@ -0,0 +1,15 @@
+// phases are `phase/phase.rs`, fail with `Err(())` once their diagnostics are reported,
+// and match the way the checker was first written
+#![allow(
+  clippy::module_inception,
+  clippy::result_unit_err,
+  clippy::too_many_arguments,
+  clippy::type_complexity,
+  clippy::should_implement_trait,
+  clippy::collapsible_match,
+  clippy::match_single_binding,
+)]
+
+extern crate colored;
+
+pub mod wu;
@@ -1,14 +1,17 @@
 extern crate colored;
 extern crate rustyline;
+extern crate tang;
 
 use colored::Colorize;
 
-mod wu;
-use wu::source::*;
-use wu::lexer::*;
-use wu::parser::*;
-use wu::visitor::*;
-use wu::compiler::*;
+use tang::wu::error::*;
+use tang::wu::source::*;
+use tang::wu::lexer::*;
+use tang::wu::parser::*;
+use tang::wu::visitor::*;
+use tang::wu::ir::*;
+use tang::wu::compiler::*;
+use tang::wu::formatter::*;
 
 
 use std::fs;
@@ -24,13 +27,13 @@ use rustyline::error::ReadlineError;
 
 
 
-const PROMPT:        &'static str = ">> ";
-const PROMPT_INDENT: &'static str = " | ";
+const PROMPT:        &str = ">> ";
+const PROMPT_INDENT: &str = " | ";
 
 
 
 fn repl() {
-  let mut repl = rustyline::Editor::<()>::new();
+  let mut repl = rustyline::DefaultEditor::new().unwrap();
 
   let mut is_indented = false;
 
@@ -41,11 +44,11 @@ fn repl() {
 
     match line {
       Ok(content) => {
-        if content.len() == 0 {
+        if content.is_empty() {
           continue
         }
 
-        is_indented = content.chars().last().unwrap() == '\\';
+        is_indented = content.ends_with('\\');
 
         if is_indented {
           program.push_str(&content[.. content.len() - 1]);
@@ -55,7 +58,7 @@ fn repl() {
 
           println!();
 
-          run(&program, "<repl>");
+          run(&program, "<repl>", ".", &Options { format: MessageFormat::Human, lints: LintLevels::default(), source_map: false, traceback: false, emit_ir: false, target: Target::default() });
 
           program.push('\n');
         }
@@ -81,19 +84,30 @@ fn repl() {
 
 
 
-fn compile_path(path: &str) {
+// `root` is what `require` paths are relative to, the directory given to `tang`, tells whether everything compiled
+fn compile_path(path: &str, root: &str, options: &Options) -> bool {
   let meta = match metadata(path) {
     Ok(m)    => m,
     Err(why) => panic!("{}", why),
   };
 
+  let mut fine = true;
+
   if meta.is_file() {
     let split: Vec<&str> = path.split('.').collect();
     let path_lua = format!("{}.lua", split[0 .. split.len() - 1].to_vec().join("."));
 
     if !Path::new(&path_lua).is_file() {
-      if let Some(n) = file_content(path) {
-        write(path, &n);
+      match file_content(path, root, options) {
+        Some((n, map, imports)) => {
+          write(path, &n, &map, options);
+
+          for import in imports {
+            fine &= compile_path(&import, root, options)
+          }
+        },
+
+        None => fine = false,
       }
     }
   } else {
@@ -104,7 +118,7 @@ fn compile_path(path: &str) {
       let split: Vec<&str> = path.split('.').collect();
 
       if Path::new(&path).is_dir() {
-        compile_path(&format!("{}", path))
+        fine &= compile_path(&path.to_string(), root, options)
       }
 
       match split.last() {
@@ -119,17 +133,19 @@ fn compile_path(path: &str) {
         _ => continue,
       }
 
-      compile_path(&format!("{}", path))
+      fine &= compile_path(&path.to_string(), root, options)
     }
   }
+
+  fine
 }
 
 
 
-fn file_content(path: &str) -> Option<String> {
+fn file_content(path: &str, root: &str, options: &Options) -> Option<(String, SourceMap, Vec<String>)> {
   let display = Path::new(path).display();
 
-  let mut file = match File::open(&path) {
+  let mut file = match File::open(path) {
       Err(why) => panic!("failed to open {}: {}", display, why),
       Ok(file) => file,
   };
@@ -138,16 +154,19 @@ fn file_content(path: &str) -> Option<String> {
 
   match file.read_to_string(&mut s) {
     Err(why) => panic!("failed to read {}: {}", display, why),
-    Ok(_)    => run(&s, path),
+    Ok(_)    => run(&s, path, root, options),
   }
 }
 
 
 
-fn write(path: &str, data: &str) {
+fn write(path: &str, data: &str, map: &SourceMap, options: &Options) {
   let path = Path::new(path);
 
-  println!("{} {}", "compiled".green().bold(), path.display().to_string().replace("./", ""));
+  // with json, stdout is left to diagnostics alone
+  if options.format == MessageFormat::Human {
+    println!("{} {}", "compiled".green().bold(), path.display().to_string().replace("./", ""));
+  }
 
   let split_name = path.file_name().unwrap().to_str().unwrap().split('.');
   let split: Vec<&str> = split_name.collect();
@@ -165,44 +184,190 @@ fn write(path: &str, data: &str) {
     Ok(_)    => (),
     Err(why) => println!("{}", why)
   }
+
+  if options.source_map {
+    if let Err(why) = fs::write(format!("{}.map", path_real), map.to_string()) {
+      println!("{}", why)
+    }
+  }
+}
+
+
+
+// `require` prefix of the directory holding `file`, relative to `root`, like `game.`
+fn module_prefix(file: &str, root: &str) -> String {
+  let root = Path::new(root).canonicalize().unwrap_or(Path::new(root).to_path_buf());
+  let file = Path::new(file).canonicalize().unwrap_or(Path::new(file).to_path_buf());
+
+  match file.parent().and_then(|directory| directory.strip_prefix(&root).ok()) {
+    Some(directory) => directory.components().map(|c| format!("{}.", c.as_os_str().to_string_lossy())).collect(),
+    None            => String::new(),
+  }
 }
 
 
 
-fn run(content: &str, file: &str) -> Option<String> {
+// compiles one file, handing back its Lua, where that came from and the files it imports
+fn run(content: &str, file: &str, root: &str, options: &Options) -> Option<(String, SourceMap, Vec<String>)> {
+  let result = compile(content, file, root, options);
+
+  let diagnostics = take_diagnostics();
+
+  if !diagnostics.is_empty() {
+    for diagnostic in &diagnostics {
+      match options.format {
+        MessageFormat::Human => println!("{}", diagnostic),
+        MessageFormat::Json  => println!("{}", diagnostic.to_json()),
+      }
+    }
+
+    let errors = diagnostics.iter().filter(|d| d.severity == Severity::Wrong).count();
+
+    if errors > 0 {
+      if options.format == MessageFormat::Human {
+        println!(
+          "\n{} {}, {} error{}",
+          "failed".red().bold(), file.replace("./", ""), errors, if errors == 1 { "" } else { "s" }
+        );
+      }
+
+      return None
+    }
+  }
+
+  result
+}
+
+
+
+// every phase reports what it finds, checking still runs on what parsed, the rest only when everything went fine
+fn compile(content: &str, file: &str, root: &str, options: &Options) -> Option<(String, SourceMap, Vec<String>)> {
   let source = Source::from(file, content.lines().map(|x| x.into()).collect::<Vec<String>>());
   let lexer  = Lexer::default(content.chars().collect(), &source);
 
   let mut tokens = Vec::new();
 
-  for token_result in lexer {
-    if let Ok(token) = token_result {
-      tokens.push(token)
-    } else {
-      return None
+  for token in lexer.flatten() {
+    tokens.push(token)
+  }
+
+  let unread = take_diagnostics();
+
+  let tokens_ref = tokens.iter().collect::<Vec<&Token>>();
+
+  let mut parser = Parser::new(tokens_ref, &source);
+
+  let ast = parser.parse();
+
+  // a tree with error nodes in it is still checked, those just don't lead to more errors
+  let mut visitor = Visitor::new(&source, &ast);
+
+  let checked = visitor.visit();
+
+  if !unread.is_empty() {
+    // past a weird character the rest of its line is off, past a token given up on the rest of the file
+    let garbled = |diagnostic: &Diagnostic| unread.iter().any(|lexed| lexed.file == diagnostic.file && match lexed.code {
+      Some("E0101") => lexed.line() == diagnostic.line(),
+      _             => lexed.line() <= diagnostic.line(),
+    });
+
+    let found = take_diagnostics().into_iter().filter(|diagnostic| !garbled(diagnostic)).collect::<Vec<_>>();
+
+    for diagnostic in unread.iter().cloned().chain(found) {
+      report(diagnostic)
     }
+
+    return None
   }
 
-  let tokens_ref = tokens.iter().map(|x| &*x).collect::<Vec<&Token>>();
+  if parser.failed() || checked.is_err() {
+    return None
+  }
+
+  Linter::new(&source, &ast, &options.lints).lint();
 
-  let mut parser  = Parser::new(tokens_ref, &source);
+  let program = Lowering::new(&visitor.types).lower(&ast);
 
-  match parser.parse() {
-    Ok(ast) => {
-      let mut visitor = Visitor::new(&source, &ast);
+  if options.emit_ir {
+    println!("{} {}\n{}", "lowered".cyan().bold(), file.replace("./", ""), program)
+  }
 
-      match visitor.visit() {
-        Ok(_) => {
-          let mut generator = Generator::new();
+  let mut generator = Generator::new();
 
-          Some(generator.generate(&ast))
-        },
+  generator.module_prefix = module_prefix(file, root);
+  generator.target        = options.target;
+
+  let imports = visitor.modules.borrow().keys().cloned().collect();
+
+  let lua = generator.generate(&program);
+
+  let map = SourceMap {
+    source: Path::new(file).file_name().map_or(file.to_string(), |name| name.to_string_lossy().into_owned()),
+    lines:  generator.lines,
+  };
+
+  Some((lua, map, imports))
+}
+
+
+
+// lays out `path` or every Wu file under it, telling whether all of them were fine
+fn format_path(path: &str, check: bool) -> bool {
+  let meta = match metadata(path) {
+    Ok(m)    => m,
+    Err(why) => fail(&format!("{}: {}", path, why)),
+  };
+
+  if meta.is_dir() {
+    let mut paths = fs::read_dir(path).unwrap().map(|entry| entry.unwrap().path()).collect::<Vec<_>>();
+
+    paths.sort();
+
+    let mut fine = true;
 
-        _ => None
+    for path in paths {
+      if path.is_dir() || path.extension().is_some_and(|extension| extension == "wu") {
+        fine &= format_path(&path.display().to_string(), check)
       }
+    }
+
+    return fine
+  }
+
+  let content = match fs::read_to_string(path) {
+    Ok(content) => content,
+    Err(why)    => fail(&format!("failed to read {}: {}", path, why)),
+  };
+
+  let formatted = format(&content, path);
+
+  for diagnostic in take_diagnostics() {
+    println!("{}", diagnostic)
+  }
+
+  let display = path.replace("./", "");
+
+  match formatted {
+    Some(ref formatted) if *formatted == content => true,
+
+    Some(formatted) => if check {
+      println!("{} {}", "unformatted".yellow().bold(), display);
+
+      false
+    } else {
+      match fs::write(path, formatted) {
+        Ok(_)    => println!("{} {}", "formatted".green().bold(), display),
+        Err(why) => fail(&format!("failed to write {}: {}", path, why)),
+      }
+
+      true
     },
 
-    _ => None,
+    None => {
+      println!("\n{} {}, only files that parse are formatted", "failed".red().bold(), display);
+
+      false
+    },
   }
 }
 
@@ -236,6 +401,8 @@ fn clean_path(path: &str) {
                 Err(why) => panic!("{}", why)
               }
             }
+
+            clean_map(&path)
           },
           _ => continue,
         }
@@ -252,22 +419,150 @@ fn clean_path(path: &str) {
         Err(why) => panic!("{}", why)
       }
     }
+
+    clean_map(&path)
+  }
+}
+
+// the source map written next to `path`, if there is one
+fn clean_map(path: &str) {
+  let path = format!("{}.map", path);
+
+  if Path::new(&path).is_file() {
+    match fs::remove_file(&path) {
+      Ok(_)    => println!("{} {}", "removed".red().bold(), path.replace("./", "")),
+      Err(why) => panic!("{}", why)
+    }
   }
 }
 
 
 
+// what the command line asks of every compiled file
+struct Options {
+  format:     MessageFormat,
+  lints:      LintLevels,
+  source_map: bool, // `.lua.map` next to every `.lua`
+  traceback:  bool, // and `traceback.lua` to read them with
+  emit_ir:    bool, // print what each file lowers to
+  target:     Target,
+}
+
+fn fail(message: &str) -> ! {
+  eprintln!("{} {}", "wrong:".magenta().bold(), message);
+  std::process::exit(1)
+}
+
+
+
 fn main() {
-  let args = env::args().collect::<Vec<String>>();
+  let mut options = Options {
+    format:     MessageFormat::Human,
+    lints:      LintLevels::default(),
+    source_map: false,
+    traceback:  false,
+    emit_ir:    false,
+    target:     Target::default(),
+  };
+
+  // flags may go anywhere, everything else is positional
+  let args = env::args().filter(|arg| {
+    if let Some(name) = arg.strip_prefix("--message-format=") {
+      match MessageFormat::from(name) {
+        Some(format) => options.format = format,
+        None         => fail(&format!("unknown message format `{}`, expected `human` or `json`", name)),
+      }
+
+      false
+    } else if let Some(name) = arg.strip_prefix("--target=") {
+      match Target::from(name) {
+        Some(target) => options.target = target,
+        None         => fail(&format!("unknown target `{}`, expected `5.1`, `5.3`, `5.4` or `luajit`", name)),
+      }
+
+      false
+    } else if let Some((level, lints)) = ["allow", "warn", "deny"].iter().filter_map(|level| arg.strip_prefix(&format!("--{}=", level)).map(|lints| (level, lints))).next() {
+      // `--deny=shadowing,unused_variables`
+      for lint in lints.split(',') {
+        if let Err(message) = options.lints.set(lint, Level::from(level).unwrap()) {
+          fail(&message)
+        }
+      }
+
+      false
+    } else if arg == "--source-map" {
+      options.source_map = true;
+
+      false
+    } else if arg == "--traceback" {
+      options.source_map = true;
+      options.traceback  = true;
+
+      false
+    } else if arg == "--emit-ir" {
+      options.emit_ir = true;
+
+      false
+    } else {
+      true
+    }
+  }).collect::<Vec<String>>();
 
   if args.len() > 1 {
     if args[1] == "clean" {
       if args.len() > 2 {
         clean_path(&args[2])
       }
+    } else if args[1] == "fmt" {
+      // `tang fmt --check src` only tells which files would change
+      let check = args.iter().any(|arg| arg == "--check");
+      let paths = args[2 ..].iter().filter(|arg| *arg != "--check").collect::<Vec<&String>>();
+
+      if paths.is_empty() {
+        fail("expected a file or directory to format")
+      }
+
+      let mut fine = true;
+
+      for path in paths {
+        fine &= format_path(path, check)
+      }
+
+      if !fine {
+        std::process::exit(1)
+      }
     } else {
-      compile_path(&args[1])    
-    }    
+      let root = if Path::new(&args[1]).is_dir() {
+        args[1].clone()
+      } else {
+        match Path::new(&args[1]).parent() {
+          Some(parent) if parent != Path::new("") => parent.display().to_string(),
+          _                                       => ".".to_string(),
+        }
+      };
+
+      let fine = compile_path(&args[1], &root, &options);
+
+      if options.traceback {
+        let path = Path::new(&root).join("traceback.lua");
+
+        // only one written by `--traceback` is replaced, the project may have a module of that name
+        let written = fs::read_to_string(&path).map_or(true, |existing| existing.lines().next() == TRACEBACK.lines().next());
+
+        if !written {
+          fail(&format!("won't overwrite {}, it wasn't written by `--traceback`", path.display()))
+        }
+
+        if let Err(why) = fs::write(&path, TRACEBACK) {
+          fail(&format!("failed to write {}: {}", path.display(), why))
+        }
+      }
+
+      // files with errors are reported and skipped, the rest are still written
+      if !fine {
+        std::process::exit(1)
+      }
+    }
   } else {
     repl()
   }
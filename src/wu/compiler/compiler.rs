@@ -1,353 +1,439 @@
 use super::*;
 
+use std::fmt;
 
 
-#[derive(Debug, Clone)]
-pub enum FlagImplicit {
-  Return,
-  Assign(String)
-}
 
+// where generated statements came from, marked in the output while it's built and
+// taken out once it's done, `\u{1}12\u{2}local a = ..` came from line 12
+const MARK:     char = '\u{1}';
+const MARK_END: char = '\u{2}';
 
+// a Lua error handler reading `.lua.map` files, rewrites `a.lua:3:` to `a.wu:1:`
+pub const TRACEBACK: &str = include_str!("traceback.lua");
 
-pub struct Generator {
-  flag: Option<FlagImplicit>
+
+
+// which Wu line each line of generated Lua came from, written next to it as `.lua.map`
+#[derive(Debug, Clone, Default)]
+pub struct SourceMap {
+  pub source: String,              // the Wu file, next to the Lua one
+  pub lines:  Vec<(usize, usize)>, // Lua line and Wu line
 }
 
-impl<'g> Generator {
-  pub fn new() -> Self {
-    Generator {
-      flag: None,
+impl fmt::Display for SourceMap {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    writeln!(f, "{}", self.source)?;
+
+    for &(lua, wu) in &self.lines {
+      writeln!(f, "{} {}", lua, wu)?
     }
+
+    Ok(())
   }
+}
 
 
 
-  pub fn generate(&mut self, ast: &'g Vec<Statement>) -> String {
-    let mut output = String::new();
+// the Lua the output is written for, where the dialects differ
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Target {
+  Lua51,
+  #[default]
+  Lua54, // and 5.3, what's new since 5.1 is the same
+  LuaJIT,
+}
 
-    for statement in ast.iter() {
-      output.push_str(&self.generate_statement(&statement));
-      output.push('\n')
+impl Target {
+  pub fn from(name: &str) -> Option<Self> {
+    match name {
+      "5.1"         => Some(Target::Lua51),
+      "5.3" | "5.4" => Some(Target::Lua54),
+      "luajit"      => Some(Target::LuaJIT),
+      _             => None,
     }
+  }
 
-    output
+  // `//`, flooring without going through floats
+  pub fn has_floor_division(&self) -> bool {
+    *self == Target::Lua54
   }
 
+  // moved into `table` by 5.2
+  pub fn unpack(&self) -> &'static str {
+    if *self == Target::Lua54 { "table.unpack" } else { "unpack" }
+  }
 
+  // `\x` escapes in strings
+  pub fn has_hex_escapes(&self) -> bool {
+    *self != Target::Lua51
+  }
+}
 
-  fn generate_statement<'b>(&mut self, statement: &'b Statement<'b>) -> String {
-    use self::StatementNode::*;
 
-    let result = match statement.node {
-      Expression(ref expression)       => self.generate_expression(expression),
-      Variable(_, ref left, ref right) => self.generate_local(left, right),
-      Assignment(ref left, ref right)  => self.generate_assignment(left, right),
 
-      Return(ref expr)  => if let Some(ref expr) = *expr {
-        format!("return {}\n", self.generate_expression(expr))
-      } else {
-        "return\n".to_string()
-      }, 
-    };
+pub struct Generator {
+  pub lines: Vec<(usize, usize)>, // Lua line and Wu line, filled in by `generate`
 
-    result
+  pub module_prefix: String, // `require` path of the compiled file's directory, like `game.`
+  pub target:        Target,
+}
+
+impl Default for Generator {
+  fn default() -> Self {
+    Self::new()
   }
+}
 
+impl Generator {
+  pub fn new() -> Self {
+    Generator {
+      lines: Vec::new(),
 
+      module_prefix: String::new(),
+      target:        Target::default(),
+    }
+  }
 
-  fn generate_expression<'b>(&mut self, expression: &'b Expression<'b>) -> String {
-    use self::ExpressionNode::*;
-    use std::string;
 
-    let result = match expression.node {
-      Binary(ref left, ref op, ref right) => {
-        let mut result = string::String::new();
 
-        result.push_str(
-          &format!(
-            "({} {} {})",
-            self.generate_expression(&left),
-            self.generate_operator(&op),
-            self.generate_expression(&right),
-          )
-        );
+  pub fn generate(&mut self, program: &Program) -> String {
+    let mut output = String::new();
+    let mut line   = None;
 
-        result
-      },
+    for instruction in &program.body {
+      // what's worked out ahead of a statement stays with it
+      if line.is_some() && instruction.line != line {
+        output.push('\n')
+      }
 
-      Call(ref called, ref args) => {
-        let flag_backup = self.flag.clone();
+      line = instruction.line;
 
-        self.flag = Some(FlagImplicit::Assign("none".to_string()));
+      output.push_str(&self.generate_instruction(instruction))
+    }
 
-        let mut result = format!("{}(", self.generate_expression(called));
+    if !output.is_empty() {
+      output.push('\n')
+    }
 
-        for (i, arg) in args.iter().enumerate() {
-          result.push_str(&self.generate_expression(arg));
+    // the exports don't come from any one line
+    let mut output = self.take_marks(&output);
 
-          if i < args.len() - 1 {
-            result.push_str(", ")
-          }
-        }
+    output.push_str(&self.generate_exports(&program.exports));
 
-        result.push(')');
+    output
+  }
 
-        self.flag = flag_backup;
-        
-        result
-      },
 
-      Block(ref content) => {
-        let flag_backup = self.flag.clone();
 
-        let flag = self.flag.clone();
+  // lines without a mark of their own belong to the last instruction marked before them
+  fn take_marks(&mut self, output: &str) -> String {
+    let mut result  = String::new();
+    let mut current = None;
 
-        let mut result = if let Some(ref f) = flag {
-          match *f {
-            FlagImplicit::Assign(_) => {
-              self.flag = Some(FlagImplicit::Return);
+    self.lines.clear();
 
-              "(function()\n"
-            },
+    for (index, line) in output.lines().enumerate() {
+      let mut rest = line;
+      let mut mark = None;
 
-            FlagImplicit::Return => ""
-          }
-        } else  {
-          "do\n"
-        }.to_string();
-
-        for (i, element) in content.iter().enumerate() {
-          if i == content.len() - 1 {
-            if self.flag.is_some() {
-              if let StatementNode::Expression(ref expression) = element.node {
-                match expression.node {
-                  Block(_) | If(..) => (),
-                  _ => match &self.flag.clone().unwrap() {
-                    &FlagImplicit::Return => {
-                      let line = format!("return {}\n", self.generate_expression(expression));
-
-                      result.push_str(&self.make_line(&line));
-
-                      break
-                    },
-
-                    _ => ()
-                  },
-                }
-              }
-            }
-          }
+      while let Some(start) = rest.find(MARK) {
+        result.push_str(&rest[.. start]);
 
-          let line = self.generate_statement(&element);
-          result.push_str(&self.make_line(&line));
-        }
+        let marked = &rest[start + MARK.len_utf8() ..];
+        let end    = marked.find(MARK_END).unwrap_or(0);
 
-        self.flag = flag_backup;
+        mark = mark.or(marked[.. end].parse::<usize>().ok());
+        rest = &marked[end + MARK_END.len_utf8() ..];
+      }
 
-        if let Some(ref f) = flag {
-          match *f {
-            FlagImplicit::Assign(_) => {
-              self.flag = Some(FlagImplicit::Return);
+      result.push_str(rest);
+      result.push('\n');
 
-              result.push_str("end)()")
-            },
+      current = mark.or(current);
 
-            FlagImplicit::Return => ()
-          }
-        } else {
-          result.push_str("end\n")
-        }
+      if let Some(wu) = current {
+        self.lines.push((index + 1, wu))
+      }
+    }
 
-        result
-      },
+    result
+  }
 
-      Function(ref params, _, ref body, _) => {
-        let mut result = format!("function(");
+  fn mark(line: Option<usize>) -> String {
+    match line {
+      Some(line) => format!("{}{}{}", MARK, line, MARK_END),
+      None       => String::new(),
+    }
+  }
 
-        for (i, param) in params.iter().enumerate() {
-          result.push_str(&param.0);
 
-          if i < params.len() - 1 {
-            result.push_str(", ")
-          }
-        }
 
-        result.push_str(")\n");
+  fn generate_exports(&mut self, names: &[String]) -> String {
+    if names.is_empty() {
+      return String::new()
+    }
 
-        let flag_backup = self.flag.clone();
+    let mut result = "return {\n".to_string();
 
-        self.flag = Some(FlagImplicit::Return);
+    for (i, name) in names.iter().enumerate() {
+      let mut line = format!("{} = {}", name, name);
 
-        let line = if let Block(..) = body.node {
-          self.generate_expression(body)
-        } else {
-          format!("return {}", self.generate_expression(body))
-        };
+      if i < names.len() - 1 {
+        line.push(',')
+      }
 
-        result.push_str(&self.make_line(&line));
+      result.push_str(&self.make_line(&line));
+    }
 
-        self.flag = flag_backup;
+    result.push_str("}\n");
 
-        result.push_str("end");
+    result
+  }
 
-        result
-      },
 
-      Array(ref content) => {        
-        let mut result = "({\n".to_string();
 
-        for (i, arg) in content.iter().enumerate() {
-          let value    = self.generate_expression(arg);
-          let mut line = format!("[{}] = {}", i, value);
+  fn generate_instruction(&mut self, instruction: &Instruction) -> String {
+    use self::InstructionNode::*;
 
-          if i < content.len() - 1 {
-            line.push(',')
-          }
+    let result = match instruction.node {
+      Local(ref name, _, ref value) => match *value {
+        Some(ref value) => format!("local {} = {}\n", name, self.generate_value(value)),
+        None            => format!("local {}\n", name),
+      },
 
-          result.push_str(&self.make_line(&line));
-        }
+      Assign(ref place, ref value) => format!("{} = {}\n", self.generate_value(place), self.generate_value(value)),
 
-        result.push_str("})");
+      // Lua only runs calls as statements
+      Eval(ref value) => match value.node {
+        ValueNode::Call(..) => format!("{}\n", self.generate_value(value)),
+        _                   => format!("local _ = {}\n", self.generate_value(value)),
+      },
+
+      If(ref condition, ref body, ref otherwise) => {
+        let mut result = format!("if {} then\n", self.generate_value(condition));
+
+        result.push_str(&self.generate_body(body));
+        result.push_str(&self.generate_else(otherwise));
+        result.push_str("end\n");
 
         result
       },
 
-      Index(ref source, ref index) => {
-        let source = self.generate_expression(source);
-        let index  = self.generate_expression(index);
+      While(ref condition, ref body) => format!("while {} do\n{}end\n", self.generate_value(condition), self.generate_body(body)),
 
-        format!("{}[{}]", source, index)
-      }
+      Loop(ref body)  => format!("while true do\n{}end\n", self.generate_body(body)),
+      Scope(ref body) => format!("do\n{}end\n", self.generate_body(body)),
 
-      If(ref condition, ref body, ref elses) => {
-        let flag_backup = self.flag.clone();
+      Break => "break\n".to_string(),
 
-        let mut result = if let Some(FlagImplicit::Assign(_)) = self.flag {
-          self.flag = Some(FlagImplicit::Return);
+      Return(ref value) => match *value {
+        Some(ref value) => format!("return {}\n", self.generate_value(value)),
+        None            => "return\n".to_string(),
+      },
 
-          "(function()\n"
-        } else {
-          ""
-        }.to_string();
+      Import(ref name, ref path) => format!("local {} = require(\"{}{}\")\n", name, self.module_prefix, path.join(".")),
+    };
 
-        result.push_str(&format!("if {} then\n", self.generate_expression(condition)));
+    format!("{}{}", Self::mark(instruction.line), result)
+  }
 
-        let body = self.generate_expression(body);
+  // an `if` alone in an `else` is an `elseif`
+  fn generate_else(&mut self, otherwise: &[Instruction]) -> String {
+    match otherwise {
+      [] => String::new(),
 
-        result.push_str(&self.make_line(&body));
+      [Instruction { node: InstructionNode::If(ref condition, ref body, ref rest), line }] => {
+        let condition = self.generate_value(condition);
+        let body      = self.generate_body(body);
+        let rest      = self.generate_else(rest);
 
-        if let &Some(ref elses) = elses {
-          for branch in elses {
+        format!("{}elseif {} then\n{}{}", Self::mark(*line), condition, body, rest)
+      },
 
-            if let Some(ref condition) = branch.0 {
-              result.push_str(&format!("elseif {} then\n", self.generate_expression(condition)));
-            } else {
-              result.push_str("else\n")
-            }
+      _ => format!("else\n{}", self.generate_body(otherwise)),
+    }
+  }
 
-            let body = self.generate_expression(&branch.1);
+  fn generate_body(&mut self, body: &[Instruction]) -> String {
+    let mut result = String::new();
 
-            result.push_str(&self.make_line(&body));
-          }
-        }
+    for instruction in body {
+      let line = self.generate_instruction(instruction);
 
-        self.flag = flag_backup;
+      result.push_str(&self.make_line(&line))
+    }
 
-        if let Some(FlagImplicit::Assign(_)) = self.flag {
-          result.push_str("end\nend)()\n")
-        } else {
-          result.push_str("end\n")
-        }
+    result
+  }
 
-        result
+
+
+  fn generate_value(&mut self, value: &Value) -> String {
+    use self::ValueNode::*;
+
+    match value.node {
+      // inlined constants can be negative, and `-2 ^ 2` is `-(2 ^ 2)` to Lua
+      Int(ref n)   => if *n < 0 {
+        format!("({})", n)
+      } else {
+        format!("{}", n)
       },
 
-      Int(ref n)        => format!("{}", n),
-      Float(ref n)      => format!("{}", n),
-      Bool(ref n)       => format!("{}", n),
-      Str(ref n)        => format!("\"{}\"", n),
-      Char(ref n)       => format!("\"{}\"", n),
-      Identifier(ref n) => format!("{}", n),
-      
-      _ => String::new()
-    };
+      Float(ref n) => if n.is_nan() {
+        "(0 / 0)".to_string()
+      } else if n.is_infinite() {
+        format!("({}1 / 0)", if *n < 0.0 { "-" } else { "" })
+      } else if n.is_sign_negative() {
+        format!("({:?})", n)
+      } else {
+        format!("{:?}", n) // `7.0`, which 5.3 and later keep apart from `7`
+      },
+      Bool(ref n)  => format!("{}", n),
+      Str(ref n)   => self.quote(n),
+      Char(ref n)  => self.quote(&n.to_string()),
+      Nil          => "nil".to_string(),
+      Name(ref n)  => n.clone(),
+
+      // `int` division and powers stay `int`, rounding down like `Parser::fold_expression`,
+      // Lua's `%` already does
+      Binary(ref left, ref op, ref right) if left.kind.node == TypeNode::Int && right.kind.node == TypeNode::Int && (*op == Operator::Div || *op == Operator::Pow) => {
+        let (left, right) = (self.generate_value(left), self.generate_value(right));
+
+        match *op {
+          Operator::Div if self.target.has_floor_division() => format!("({} // {})", left, right),
+          Operator::Div                                      => format!("math.floor({} / {})", left, right),
+          _                                                  => format!("math.floor({} ^ {})", left, right),
+        }
+      },
 
-    result
-  }
+      Binary(ref left, ref op, ref right) => format!("({} {} {})", self.generate_value(left), self.generate_operator(op), self.generate_value(right)),
 
+      Unary(ref op, ref operand) => format!("({} {})", self.generate_operator(op), self.generate_value(operand)),
 
+      Call(ref called, ref args) => {
+        let mut result = format!("{}(", self.generate_value(called));
 
-  fn generate_local<'b>(&mut self, name: &str, right: &'b Option<Expression<'b>>) -> String {
-    use self::ExpressionNode::*;
-    use std::string;
+        for (i, arg) in args.iter().enumerate() {
+          result.push_str(&self.generate_value(arg));
 
-    let flag_backup = self.flag.clone();
+          if i < args.len() - 1 {
+            result.push_str(", ")
+          }
+        }
 
-    let mut result = {
-      let output = format!("local {}", name);
+        result.push(')');
 
-      self.flag = Some(FlagImplicit::Assign(name.to_string()));
+        result
+      },
 
-      output
-    };
+      // counted when captured, so nils among the arguments aren't lost,
+      // the length that was checked is only that of calls in this file, not of those from others
+      Unpack(ref splat, _) => {
+        let splat = self.generate_value(splat);
 
-    if let &Some(ref right) = right {
-      let right_str = self.generate_expression(right);
+        format!("{}({}, 1, {}.n)", self.target.unpack(), splat, splat)
+      },
 
-      result.push_str(&format!(" = {}", right_str))
-    }
+      Index(ref source, ref index) => format!("{}[{}]", self.generate_value(source), self.generate_value(index)),
 
-    self.flag = flag_backup;
+      Field(ref source, ref field) => format!("{}.{}", self.generate_value(source), field),
 
-    format!("{}\n", result)
-  }
+      Table(ref entries) => {
+        let mut result = "({\n".to_string();
 
+        for (i, (key, entry)) in entries.iter().enumerate() {
+          let entry = self.generate_value(entry);
 
+          let mut line = match *key {
+            Key::Index(index)     => format!("[{}] = {}", index, entry),
+            Key::Field(ref field) => format!("{} = {}", field, entry),
+          };
 
-  fn generate_assignment<'b>(&mut self, left: &'b Expression, right: &'b Expression) -> String {
-    let left_string  = self.generate_expression(left);
+          if i < entries.len() - 1 {
+            line.push(',')
+          }
 
-    let flag_backup = self.flag.clone();
+          result.push_str(&self.make_line(&line));
+        }
 
-    self.flag = Some(FlagImplicit::Assign(left_string.clone()));
-    
-    let right_string = self.generate_expression(right);
+        result.push_str("})");
 
-    self.flag = flag_backup;
+        result
+      },
 
-    let result = format!("{} = {}\n", left_string, right_string);
+      Function(ref params, ref body) => {
+        let mut names = params.iter().map(|param| param.0.clone()).collect::<Vec<String>>();
+        let mut body  = self.generate_body(body);
 
-    result
+        // a splat takes the rest of the arguments, captured with their count
+        if let Some(&(ref name, Type { mode: TypeMode::Splat(_), .. })) = params.last() {
+          let capture = format!("local {} = {{ n = select(\"#\", ...), ... }}\n", name);
+
+          body = format!("{}{}", self.make_line(&capture), body);
+
+          *names.last_mut().unwrap() = "...".to_string()
+        }
+
+        format!("function({})\n{}end", names.join(", "), body)
+      },
+    }
   }
 
 
 
-  fn generate_operator<'b>(&mut self, op: &'b Operator) -> String {
+  fn generate_operator(&mut self, op: &Operator) -> String {
     use self::Operator::*;
 
     match *op {
       Concat => "..".to_string(),
+      NEq    => "~=".to_string(),
       _ => format!("{}", op)
     }
   }
 
 
 
+  // the lexer unescaped the string, so it's escaped again the way Lua reads it
+  fn quote(&self, content: &str) -> String {
+    let mut result = "\"".to_string();
+
+    for c in content.chars() {
+      match c {
+        '"'  => result.push_str("\\\""),
+        '\\' => result.push_str("\\\\"),
+        '\n' => result.push_str("\\n"),
+        '\r' => result.push_str("\\r"),
+        '\t' => result.push_str("\\t"),
+
+        // 5.1 only reads decimal escapes, which take up to three digits
+        c if c.is_control() && (c as u32) < 0x80 => if self.target.has_hex_escapes() {
+          result.push_str(&format!("\\x{:02x}", c as u32))
+        } else {
+          result.push_str(&format!("\\{:03}", c as u32))
+        },
+
+        c => result.push(c),
+      }
+    }
+
+    result.push('"');
+
+    result
+  }
+
+
+
   fn make_line(&mut self, value: &str) -> String {
     let mut output = String::new();
 
     for line in value.lines() {
       output.push_str("  ");
 
-      output.push_str(&line);
+      output.push_str(line);
       output.push('\n')
     }
 
     output
   }
-
-  fn push_line(&mut self, target: &mut String, value: &str) {
-    target.push_str(&self.make_line(&value))
-  }
-}
\ No newline at end of file
+}
@@ -1,5 +1,6 @@
 pub mod compiler;
 
+use super::ir::*;
 use super::parser::*;
 use super::visitor::*;
 
@@ -0,0 +1,51 @@
+-- rewrites `file.lua:12:` in errors and tracebacks to the Wu line that line came from,
+-- using the `.lua.map` files written by `tang --source-map`
+--
+--   local traceback = require("traceback")
+--   xpcall(main, traceback.handler)
+
+local maps = {}
+
+local function load(path)
+  if maps[path] == nil then
+    local map  = false
+    local file = io.open(path .. ".map")
+
+    if file then
+      map = { source = file:read("*l") }
+
+      for line in file:lines() do
+        local lua, wu = line:match("^(%d+) (%d+)$")
+
+        if lua then
+          map[tonumber(lua)] = tonumber(wu)
+        end
+      end
+
+      file:close()
+    end
+
+    maps[path] = map
+  end
+
+  return maps[path]
+end
+
+local function rewrite(message)
+  return (tostring(message):gsub("([^%s:]+)%.lua:(%d+):", function(path, line)
+    local map = load(path .. ".lua")
+    local wu  = map and map[tonumber(line)]
+
+    if wu then
+      return path:gsub("[^/\\]+$", "") .. map.source .. ":" .. wu .. ":"
+    end
+  end))
+end
+
+return {
+  rewrite = rewrite,
+
+  handler = function(message)
+    return rewrite(debug.traceback(tostring(message), 2))
+  end,
+}
@@ -1,6 +1,10 @@
 use std::fmt;
+use std::cell::RefCell;
 use colored::Colorize;
 
+use super::source::FilePath;
+use super::lexer::TokenElement;
+
 pub enum Response<T: fmt::Display> {
   Wrong(T),
   Weird(T),
@@ -9,29 +13,534 @@ pub enum Response<T: fmt::Display> {
 
 use self::Response::*;
 
+// codes are stable, tools may match on them:
+//
+//   E01xx  lexing
+//   E02xx  syntax
+//   E03xx  names, fields and modules
+//   E04xx  types
+//   E05xx  optionals and constants
+//   E09xx  compiler internals
+
+// builds a diagnostic without reporting it, for when labels or help are added before `report`
+#[macro_export]
+macro_rules! diagnostic {
+  ( $code:expr => $r:expr ) => {{
+    $crate::wu::error::Diagnostic::new($r, None, None).code($code)
+  }};
+
+  ( $code:expr => $r:expr, $file:expr ) => {{
+    $crate::wu::error::Diagnostic::new($r, Some(&$file), None).code($code)
+  }};
+
+  ( $code:expr => $r:expr, $file:expr, $pos:expr ) => {{
+    $crate::wu::error::Diagnostic::new($r, Some(&$file), Some(&$pos)).code($code)
+  }};
+
+  ( $r:expr ) => {{
+    $crate::wu::error::Diagnostic::new($r, None, None)
+  }};
+
+  ( $r:expr, $file:expr ) => {{
+    $crate::wu::error::Diagnostic::new($r, Some(&$file), None)
+  }};
+
+  ( $r:expr, $file:expr, $pos:expr ) => {{
+    $crate::wu::error::Diagnostic::new($r, Some(&$file), Some(&$pos))
+  }};
+}
+
+// every phase reports through here instead of printing, so one compile can collect all of its problems,
+// `return fail!(..)` stops where it is
 #[macro_export]
-macro_rules! response {
-  ( $( $r:expr ),+ ) => {{
-    $(
-        print!("{}", $r);
-    )*
-    println!("");
+macro_rules! fail {
+  ( $($arg:tt)* ) => {{
+    $crate::wu::error::reject(diagnostic!($($arg)*))
   }};
 }
 
-impl<T: fmt::Display> fmt::Display for Response<T> {
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+  Wrong,
+  Weird,
+  Note,
+}
+
+impl Severity {
+  pub fn name(&self) -> &'static str {
+    match *self {
+      Severity::Wrong => "wrong",
+      Severity::Weird => "weird",
+      Severity::Note  => "note",
+    }
+  }
+
+  fn color(&self) -> &'static str {
+    match *self {
+      Severity::Wrong => "magenta",
+      Severity::Weird => "yellow",
+      Severity::Note  => "white",
+    }
+  }
+}
+
+
+
+// from a column on one line to a column on the same or a later one, columns start at 1 and are inclusive
+#[derive(Debug, Clone, PartialEq)]
+pub struct Span {
+  pub file:  Option<String>,
+  pub start: (usize, usize), // line and column
+  pub end:   (usize, usize),
+  pub first: String,         // source of the first line
+  pub last:  String,         // source of the last line, the same as `first` for one line
+}
+
+impl Span {
+  pub fn new(file: Option<&FilePath>, pos: &TokenElement) -> Option<Self> {
+    let (first, start, last, end) = match *pos {
+      TokenElement::Pos(line, slice)                => (line, slice.0, line, slice.1),
+      TokenElement::Span(first, start, last, end)   => (first, start, last, end),
+      TokenElement::Ref(token)                      => (token.line, token.slice.0, token.line, token.slice.1),
+      TokenElement::Line(line)                      => (line, 1, line, line.1.chars().count()),
+      TokenElement::Row(row)                        => (row[0].line, row[0].slice.0, row[row.len() - 1].line, row[row.len() - 1].slice.1),
+      _                                             => return None,
+    };
+
+    Some(
+      Span {
+        file:  file.map(|file| file.0.clone()),
+        start: (first.0, start),
+        end:   (last.0, end),
+        first: first.1.to_string(),
+        last:  last.1.to_string(),
+      }
+    )
+  }
+
+  pub fn is_multiline(&self) -> bool {
+    self.end.0 > self.start.0
+  }
+
+  fn source(&self, line: usize) -> &str {
+    if line == self.start.0 {
+      &self.first
+    } else {
+      &self.last
+    }
+  }
+
+  // spans reaching past the end of a line, like an unexpected end of it, are cut short
+  pub fn clamped(&self) -> (usize, usize) {
+    (self.start.1.min(self.first.chars().count() + 1).max(1), self.end.1.min(self.last.chars().count()))
+  }
+}
+
+// a secondary span explaining part of the problem
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label {
+  pub span:    Span,
+  pub message: String,
+}
+
+
+
+// one reported problem, detached from the source it was found in
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+  pub severity: Severity,
+  pub code:     Option<&'static str>,
+  pub message:  String,
+  pub file:     Option<String>,
+  pub span:     Option<Span>,
+  pub labels:   Vec<Label>,
+  pub help:     Vec<String>,
+}
+
+impl Diagnostic {
+  pub fn new<T: fmt::Display>(response: Response<T>, file: Option<&FilePath>, pos: Option<&TokenElement>) -> Self {
+    let (severity, message) = match response {
+      Wrong(m) => (Severity::Wrong, m.to_string()),
+      Weird(m) => (Severity::Weird, m.to_string()),
+      Note(m)  => (Severity::Note,  m.to_string()),
+    };
+
+    Diagnostic {
+      severity,
+      message,
+
+      code:   None,
+      file:   file.map(|file| file.0.clone()),
+      span:   pos.and_then(|pos| Span::new(file, pos)),
+      labels: Vec::new(),
+      help:   Vec::new(),
+    }
+  }
+
+  pub fn code(mut self, code: &'static str) -> Self {
+    self.code = Some(code);
+    self
+  }
+
+  pub fn label<T: fmt::Display>(mut self, file: &FilePath, pos: &TokenElement, message: T) -> Self {
+    if let Some(span) = Span::new(Some(file), pos) {
+      self.labels.push(
+        Label {
+          span,
+          message: message.to_string(),
+        }
+      )
+    }
+
+    self
+  }
+
+  pub fn help<T: fmt::Display>(mut self, message: T) -> Self {
+    self.help.push(message.to_string());
+    self
+  }
+
+  pub fn line(&self) -> usize {
+    self.span.as_ref().map(|span| span.start.0).unwrap_or(0)
+  }
+
+  pub fn column(&self) -> usize {
+    self.span.as_ref().map(|span| span.start.1).unwrap_or(0)
+  }
+
+
+
+  // the primary span first, then the labels pointing into the same file
+  fn marks(&self) -> Vec<(&Span, Option<&str>, bool)> {
+    self.span.iter().map(|span| (span, None, true))
+      .chain(
+        self.labels.iter()
+          .filter(|label| label.span.file == self.file)
+          .map(|label| (&label.span, Some(label.message.as_str()), false))
+      )
+      .collect()
+  }
+
+  fn render_line(&self, f: &mut fmt::Formatter, line: usize, multiline: bool) -> fmt::Result {
+    let marks   = self.marks();
+    let linepad = format!("{:5} │", " ").blue().bold();
+
+    let color = |primary: bool| if primary { self.severity.color() } else { "blue" };
+
+    // a span over several lines runs down a gutter left of the source
+    let open = marks.iter().find(|&&(span, ..)| span.is_multiline() && span.start.0 < line && line <= span.end.0);
+
+    let gutter = match open {
+      Some(&(_, _, primary)) => format!("{}", "│ ".color(color(primary)).bold()),
+      None if multiline      => "  ".to_string(),
+      None                   => String::new(),
+    };
+
+    let source = marks.iter()
+      .find(|&&(span, ..)| span.start.0 == line || span.end.0 == line)
+      .map(|&(span, ..)| span.source(line))
+      .unwrap_or("");
+
+    write!(f, "\n{}{}", format!("{:5} │ ", line).blue().bold(), gutter)?;
+
+    let primary = marks.iter().find(|&&(span, _, primary)| primary && !span.is_multiline() && span.start.0 == line);
+
+    match primary.map(|&(span, ..)| span.clamped()) {
+      Some((start, end)) if start <= end + 1 => {
+        // columns count characters
+        let byte = |column: usize| source.char_indices().nth(column).map_or(source.len(), |(i, _)| i);
+
+        let (start, end) = (byte(start - 1), byte(end.max(start - 1)));
+
+        let mut mark = source[start .. end].to_string();
+
+        if mark.split_whitespace().count() == 0 {
+          mark = format!("{:─>count$}", ">".bold().color(color(true)), count = mark.chars().count());
+        } else {
+          mark = format!("{}", mark.bold().color(color(true)));
+        }
+
+        write!(f, "{}{}{}", &source[.. start], mark, &source[end ..])?
+      },
+
+      _ => write!(f, "{}", source)?,
+    }
+
+    // labels on this line go right under it, the ends of longer spans after them
+    for &(span, message, primary) in &marks {
+      let (start, end) = span.clamped();
+
+      if primary || span.is_multiline() || span.start.0 != line {
+        continue
+      }
+
+      let width = (end + 1).saturating_sub(start).max(1);
+
+      write!(f, "\n{} {}{:offset$}{} {}",
+        linepad, gutter, "",
+        format!("{:─<width$}", "", width = width).blue().bold(),
+        message.unwrap_or("").blue().bold(),
+        offset = start - 1,
+      )?
+    }
+
+    for &(span, message, primary) in &marks {
+      let (start, end) = span.clamped();
+
+      if !span.is_multiline() {
+        continue
+      }
+
+      if span.start.0 == line {
+        write!(f, "\n{} {}", linepad, format!("╭{:─<width$}^", "", width = start).color(color(primary)).bold())?
+      } else if span.end.0 == line {
+        write!(f, "\n{} {}", linepad, format!("╰{:─<width$}^", "", width = end.max(1)).color(color(primary)).bold())?;
+
+        if let Some(message) = message {
+          write!(f, " {}", message.color(color(primary)).bold())?
+        }
+      }
+    }
+
+    Ok(())
+  }
+}
+
+// the one place diagnostics are turned into text for humans
+impl fmt::Display for Diagnostic {
   fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
-    let (color, message_type, message) = match *self {
-      Wrong(ref m) => ("magenta", "wrong", m),
-      Weird(ref m) => ("yellow",  "weird", m),
-      Note(ref m)  => ("white",   "note",  m),
+    let header = match self.code {
+      Some(code) => format!("\n{}[{}]: ", self.severity.name(), code),
+      None       => format!("\n{}: ", self.severity.name()),
     };
 
-    let message_type = format!("\n{}: ", message_type).color(color).bold();
-    let message      = format!("{}", message);
+    write!(f, "{}{}", header.color(self.severity.color()).bold(), self.message)?;
+
+    if let Some(ref file) = self.file {
+      match self.span {
+        Some(ref span) => write!(f, "{}", FilePath(format!("{}:{}:{}", file, span.start.0, span.start.1)))?,
+        None           => write!(f, "{}", FilePath(file.clone()))?,
+      }
+    }
+
+    let marks = self.marks();
+
+    let multiline = marks.iter().any(|&(span, ..)| span.is_multiline());
+
+    let mut lines = marks.iter()
+      .flat_map(|&(span, ..)| vec![span.start.0, span.end.0])
+      .collect::<Vec<_>>();
+
+    lines.sort();
+    lines.dedup();
+
+    let linepad = format!("{:5} │", " ").blue().bold();
+
+    if !lines.is_empty() {
+      write!(f, "\n{}", linepad)?;
+
+      for (i, line) in lines.iter().enumerate() {
+        if i > 0 && lines[i - 1] + 1 < *line {
+          let through = marks.iter().any(|&(span, ..)| span.start.0 < lines[i - 1] + 1 && *line <= span.end.0);
+
+          write!(f, "\n{}", if through { format!("{:5} ┆ │", " ") } else { format!("{:5} ┆", " ") }.blue().bold())?
+        }
+
+        self.render_line(f, *line, multiline)?
+      }
+
+      write!(f, "\n{}", linepad)?
+    }
+
+    for help in &self.help {
+      write!(f, "\n{:5} {} {}", " ", "= help:".bold(), help)?
+    }
+
+    Ok(())
+  }
+}
+
+
 
-    let message      = format!("{}{}", message_type, message);
+// the candidate closest to a misspelled `name`, if any is close enough to be what was meant
+pub fn similar<'a, I: Iterator<Item = &'a String>>(name: &str, candidates: I) -> Option<String> {
+  let length = name.chars().count();
 
-    write!(f, "{}", message)
+  // one edit away from a name of two characters is nearly any other short name
+  if length <= 2 {
+    return None
   }
-}
\ No newline at end of file
+
+  let limit = (length / 3).max(1);
+
+  let mut best: Option<(usize, &String)> = None;
+
+  for candidate in candidates {
+    if candidate == name {
+      continue
+    }
+
+    let distance = edit_distance(name, candidate);
+
+    if distance <= limit && best.is_none_or(|(d, _)| distance < d) {
+      best = Some((distance, candidate))
+    }
+  }
+
+  best.map(|(_, candidate)| candidate.clone())
+}
+
+// edit distance counting a swap of two neighbouring characters as one edit, so `flaot` is close to `float`
+fn edit_distance(a: &str, b: &str) -> usize {
+  let a = a.chars().collect::<Vec<char>>();
+  let b = b.chars().collect::<Vec<char>>();
+
+  let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+
+  for (i, row) in distances.iter_mut().enumerate() {
+    row[0] = i
+  }
+
+  for (j, distance) in distances[0].iter_mut().enumerate() {
+    *distance = j
+  }
+
+  for i in 1 ..= a.len() {
+    for j in 1 ..= b.len() {
+      let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+
+      distances[i][j] = (distances[i - 1][j] + 1)
+        .min(distances[i][j - 1] + 1)
+        .min(distances[i - 1][j - 1] + cost);
+
+      if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+        distances[i][j] = distances[i][j].min(distances[i - 2][j - 2] + 1)
+      }
+    }
+  }
+
+  distances[a.len()][b.len()]
+}
+
+
+
+// how diagnostics are written out, `--message-format=json` is for editors and CI
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MessageFormat {
+  Human,
+  Json,
+}
+
+impl MessageFormat {
+  pub fn from(name: &str) -> Option<Self> {
+    match name {
+      "human" => Some(MessageFormat::Human),
+      "json"  => Some(MessageFormat::Json),
+      _       => None,
+    }
+  }
+}
+
+pub fn json_string(s: &str) -> String {
+  let mut escaped = String::from("\"");
+
+  for c in s.chars() {
+    match c {
+      '"'  => escaped.push_str("\\\""),
+      '\\' => escaped.push_str("\\\\"),
+      '\n' => escaped.push_str("\\n"),
+      '\r' => escaped.push_str("\\r"),
+      '\t' => escaped.push_str("\\t"),
+      c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+      c    => escaped.push(c),
+    }
+  }
+
+  escaped.push('"');
+  escaped
+}
+
+fn json_option(s: Option<&str>) -> String {
+  s.map(json_string).unwrap_or_else(|| "null".to_string())
+}
+
+impl Span {
+  // `"line": .., "column_start": .., "line_end": .., "column_end": ..`, columns are 1-based and inclusive
+  fn json_fields(&self) -> String {
+    let (start, end) = self.clamped();
+
+    let end = if self.is_multiline() { end.max(1) } else { end.max(start) };
+
+    format!("\"line\":{},\"column_start\":{},\"line_end\":{},\"column_end\":{}", self.start.0, start, self.end.0, end)
+  }
+}
+
+impl Diagnostic {
+  // one line of JSON, so a diagnostic per line can be read from the output
+  pub fn to_json(&self) -> String {
+    let position = match self.span {
+      Some(ref span) => span.json_fields(),
+      None           => "\"line\":null,\"column_start\":null,\"line_end\":null,\"column_end\":null".to_string(),
+    };
+
+    let labels = self.labels.iter().map(|label|
+      format!("{{\"file\":{},{},\"message\":{}}}", json_option(label.span.file.as_deref()), label.span.json_fields(), json_string(&label.message))
+    ).collect::<Vec<_>>();
+
+    let help = self.help.iter().map(|help| json_string(help)).collect::<Vec<_>>();
+
+    format!(
+      "{{\"file\":{},{},\"severity\":{},\"code\":{},\"message\":{},\"labels\":[{}],\"help\":[{}]}}",
+      json_option(self.file.as_deref()),
+      position,
+      json_string(self.severity.name()),
+      json_option(self.code),
+      json_string(&self.message),
+      labels.join(","),
+      help.join(","),
+    )
+  }
+}
+
+
+
+thread_local! {
+  static DIAGNOSTICS: RefCell<Vec<Diagnostic>> = const { RefCell::new(Vec::new()) };
+}
+
+// parts of the tree are checked more than once, the same problem is only kept once
+pub fn report(diagnostic: Diagnostic) {
+  DIAGNOSTICS.with(|diagnostics| {
+    let mut diagnostics = diagnostics.borrow_mut();
+
+    if !diagnostics.contains(&diagnostic) {
+      diagnostics.push(diagnostic)
+    }
+  })
+}
+
+// reports what a phase stopped at, for diagnostics built up with labels or help first
+pub fn reject<T>(diagnostic: Diagnostic) -> Result<T, ()> {
+  report(diagnostic);
+
+  Err(())
+}
+
+// everything reported so far, files in the order they had problems and each in source order
+pub fn take_diagnostics() -> Vec<Diagnostic> {
+  let mut diagnostics = DIAGNOSTICS.with(|diagnostics| diagnostics.replace(Vec::new()));
+
+  let mut files = Vec::new();
+
+  for diagnostic in &diagnostics {
+    if !files.contains(&diagnostic.file) {
+      files.push(diagnostic.file.clone())
+    }
+  }
+
+  diagnostics.sort_by_key(|d| (files.iter().position(|file| *file == d.file), d.line(), d.column()));
+
+  diagnostics
+}
@@ -0,0 +1,741 @@
+use super::*;
+
+
+
+// a file printed back in the one layout `tang fmt` knows, nothing if it doesn't parse
+pub fn format(content: &str, file: &str) -> Option<String> {
+  let source = Source::from(file, content.lines().map(|x| x.into()).collect::<Vec<String>>());
+
+  let mut tokens = Vec::new();
+
+  for token in Lexer::default(content.chars().collect(), &source) {
+    tokens.push(token.ok()?)
+  }
+
+  let mut parser = Parser::new(tokens.iter().collect(), &source);
+
+  let ast = parser.parse();
+
+  if parser.failed() {
+    return None
+  }
+
+  Some(Formatter::new(&source, &tokens).format(&ast))
+}
+
+
+
+// a comment found while lexing, kept until the statements around it are written
+struct Comment {
+  line:   usize, // where it starts
+  end:    usize, // where it ends, later for block comments
+  text:   String,
+  inline: bool,  // follows code on its line
+}
+
+
+
+// prints a tree back as Wu, the same way every time, with its comments where they were
+pub struct Formatter<'f> {
+  source:   &'f Source,
+  comments: Vec<Comment>,
+  next:     usize, // first comment not written yet
+
+  lengths: Vec<String>, // array lengths as written, the parser only keeps what they fold to
+  length:  usize,       // first length not written yet
+
+  output: String,
+  indent: usize,
+  last:   usize, // last source line written
+  fresh:  bool,  // nothing written in the current block yet
+}
+
+impl<'f> Formatter<'f> {
+  pub fn new(source: &'f Source, tokens: &[Token]) -> Self {
+    let comments = tokens.iter().filter_map(|token| {
+      let text = token.trivia.as_ref()?.trim_end().to_string();
+
+      let end  = token.line.0;
+      let line = end - text.matches('\n').count();
+
+      let inline = source.lines.get(line.saturating_sub(1)).is_some_and(|code| !code.trim_start().starts_with("--"));
+
+      Some(Comment { line, end, text, inline })
+    }).collect();
+
+    Formatter {
+      source,
+      comments,
+      next: 0,
+
+      lengths: Self::lengths(tokens),
+      length:  0,
+
+      output: String::new(),
+      indent: 0,
+      last:   0,
+      fresh:  true,
+    }
+  }
+
+
+
+  pub fn format(mut self, ast: &[Statement]) -> String {
+    self.statements(ast);
+    self.comments_before(usize::MAX);
+
+    let trimmed = self.output.trim_end().len();
+
+    self.output.truncate(trimmed);
+
+    if !self.output.is_empty() {
+      self.output.push('\n')
+    }
+
+    self.output
+  }
+
+
+
+  fn statements(&mut self, statements: &[Statement]) {
+    for statement in statements {
+      if let StatementNode::Expression(Expression { node: ExpressionNode::EOF, .. }) = statement.node {
+        continue
+      }
+
+      let (first, last) = self.lines(&statement.pos);
+
+      self.comments_before(first);
+      self.gap(first);
+
+      self.write_indent();
+      self.statement(statement);
+
+      // blocks inside may have ended later than the statement's own position says
+      let last = last.max(self.last);
+
+      self.trailing(last);
+      self.output.push('\n');
+
+      self.last = last
+    }
+  }
+
+  fn statement(&mut self, statement: &Statement) {
+    use self::StatementNode::*;
+
+    match statement.node {
+      Expression(ref expression) => self.expression(expression),
+
+      Variable(ref kind, ref name, ref right, _) => self.variable(kind, name, right.as_ref()),
+
+      Assignment(ref left, ref right) => {
+        self.expression(left);
+        self.output.push_str(" = ");
+        self.expression(right)
+      },
+
+      Return(ref value) => {
+        self.output.push_str("return");
+
+        if let Some(ref value) = *value {
+          self.output.push(' ');
+          self.expression(value)
+        }
+      },
+
+      Import(ref path) => {
+        self.output.push_str("import ");
+        self.output.push_str(&path.join("."))
+      },
+
+      Extern(ref path, ref kind, ref names) => {
+        self.output.push_str("extern ");
+        self.output.push_str(&path.join("."));
+        self.output.push_str(": ");
+
+        match (&kind.node, names) {
+          (TypeNode::Func(params, retty, generics, _), Some(names)) => {
+            let params = names.iter().cloned().zip(params.iter().cloned()).collect::<Vec<_>>();
+
+            self.output.push_str("def");
+            self.signature(generics, &params, retty)
+          },
+
+          _ => {
+            let kind = self.kind(kind);
+
+            self.output.push_str(&kind)
+          },
+        }
+      },
+
+      Error => (), // files that didn't parse aren't formatted
+    }
+  }
+
+  fn variable(&mut self, kind: &Type, name: &str, right: Option<&Expression>) {
+    self.output.push_str(name);
+
+    let right = match right {
+      Some(right) => right,
+      None        => {
+        let kind = self.kind(kind);
+
+        self.output.push_str(": ");
+        self.output.push_str(&kind);
+
+        return
+      },
+    };
+
+    let declared = kind.node != TypeNode::Nil;
+
+    if let TypeMode::Immutable = kind.mode {
+      if declared {
+        let kind = self.kind(kind);
+
+        self.output.push_str(&format!(": {} : ", kind))
+      } else {
+        self.output.push_str(" :: ")
+      }
+    } else if declared {
+      let kind = self.kind(kind);
+
+      self.output.push_str(&format!(": {} = ", kind))
+    } else if let ExpressionNode::Function(..) = right.node {
+      // `name: def ..` is the only way to declare a function
+      self.output.push_str(": ")
+    } else {
+      self.output.push_str(" := ")
+    }
+
+    self.expression(right)
+  }
+
+
+
+  fn expression(&mut self, expression: &Expression) {
+    use self::ExpressionNode::*;
+
+    match expression.node {
+      Int(n)        => self.output.push_str(&n.to_string()),
+      Float(n)      => self.output.push_str(&format!("{:?}", n)),
+      Bool(b)       => self.output.push_str(&b.to_string()),
+      Str(ref s)    => self.output.push_str(&format!("\"{}\"", Self::escape(s, '"'))),
+      Char(c)       => self.output.push_str(&format!("'{}'", Self::escape(&c.to_string(), '\''))),
+      Identifier(ref n) => self.output.push_str(n),
+      Nil           => self.output.push_str("nil"),
+      Empty         => self.output.push_str("()"),
+      EOF | Error   => (),
+
+      Block(ref statements) => self.block(statements, &expression.pos),
+
+      Unwrap(ref operand) => {
+        self.output.push('*');
+        self.wrapped(operand, matches!(operand.node, Binary(..)))
+      },
+
+      Unary(ref operator, ref operand) => {
+        self.output.push_str(operator.as_str());
+
+        if *operator == Operator::Not {
+          self.output.push(' ')
+        }
+
+        // `^` binds tighter than prefix operators, `- -a` would start a comment
+        let parens = match operand.node {
+          Binary(_, ref inner, _) => *inner != Operator::Pow,
+          Unary(Operator::Neg, _) => *operator == Operator::Neg,
+          _                       => false,
+        };
+
+        self.wrapped(operand, parens)
+      },
+
+      Binary(ref left, ref operator, ref right) => {
+        let precedence = Self::precedence(operator);
+
+        let left_parens = match left.node {
+          Binary(_, ref inner, _) => {
+            let inner = Self::precedence(inner);
+
+            inner < precedence || (inner == precedence && operator.is_right_associative())
+          },
+
+          // `-a ^ b` is `-(a ^ b)`
+          Unary(..) => *operator == Operator::Pow,
+          Else(..)  => true, // binds tighter, but reads as if it didn't
+          _         => false,
+        };
+
+        let right_parens = match right.node {
+          Binary(_, ref inner, _) => {
+            let inner = Self::precedence(inner);
+
+            inner < precedence || (inner == precedence && !operator.is_right_associative())
+          },
+
+          Else(..) => true,
+          _        => false,
+        };
+
+        self.wrapped(left, left_parens);
+        self.output.push_str(&format!(" {} ", operator));
+        self.wrapped(right, right_parens)
+      },
+
+      Cast(ref value, ref kind) => {
+        self.wrapped(value, matches!(value.node, Binary(..) | Unary(..) | Unwrap(..)));
+        let kind = self.kind(kind);
+
+        self.output.push_str(" as ");
+        self.output.push_str(&kind)
+      },
+
+      Else(ref value, ref default) => {
+        self.wrapped(value, matches!(value.node, Binary(..) | Unary(..) | Unwrap(..)));
+        self.output.push_str(" else ");
+        self.wrapped(default, matches!(default.node, Binary(..) | Else(..)))
+      },
+
+      Array(ref elements) => {
+        self.output.push('[');
+        self.list(elements);
+        self.output.push(']')
+      },
+
+      Index(ref value, ref index) => {
+        self.callee(value);
+        self.output.push('[');
+        self.expression(index);
+        self.output.push(']')
+      },
+
+      Access(ref value, ref field) => {
+        self.callee(value);
+        self.output.push('.');
+        self.output.push_str(field)
+      },
+
+      Call(ref called, ref args, ref types) => {
+        self.callee(called);
+
+        if !types.is_empty() {
+          let types = self.kinds(types);
+
+          self.output.push_str(&format!("<{}>", types))
+        }
+
+        self.output.push('(');
+        self.list(args);
+        self.output.push(')')
+      },
+
+      Construct(ref called, ref fields) => {
+        self.callee(called);
+
+        if fields.is_empty() {
+          self.output.push_str(" {}");
+
+          return
+        }
+
+        // fields that were given a line each keep it
+        let (first, last) = self.lines(&expression.pos);
+
+        if first < last {
+          self.output.push_str(" {\n");
+          self.indent += 1;
+        } else {
+          self.output.push_str(" { ")
+        }
+
+        for (index, (name, value)) in fields.iter().enumerate() {
+          if first < last {
+            self.write_indent()
+          } else if index > 0 {
+            self.output.push_str(", ")
+          }
+
+          if let Some(ref name) = *name {
+            self.output.push_str(name);
+            self.output.push_str(": ")
+          }
+
+          self.expression(value);
+
+          if first < last {
+            self.output.push('\n')
+          }
+        }
+
+        if first < last {
+          self.indent -= 1;
+          self.write_indent();
+          self.output.push('}')
+        } else {
+          self.output.push_str(" }")
+        }
+      },
+
+      Function(ref params, ref retty, ref body, ref generics) => {
+        let params = params.iter().map(|param| (param.0.clone(), param.1.clone())).collect::<Vec<_>>();
+
+        self.output.push_str("def");
+        self.signature(generics.as_ref().map_or(&[][..], |generics| &generics[..]), &params, retty);
+        self.output.push(' ');
+        self.expression(body)
+      },
+
+      If(ref condition, ref body, ref elses) => {
+        self.output.push_str("if ");
+        self.condition(condition);
+        self.output.push(' ');
+        self.expression(body);
+
+        if let Some(ref elses) = *elses {
+          for (condition, body, _) in elses {
+            match *condition {
+              Some(ref condition) => {
+                self.output.push_str(" elif ");
+                self.condition(condition)
+              },
+
+              None => self.output.push_str(" else"),
+            }
+
+            self.output.push(' ');
+            self.expression(body)
+          }
+        }
+      },
+
+      While(ref condition, ref body) => {
+        self.output.push_str("while ");
+        self.condition(condition);
+        self.output.push(' ');
+        self.expression(body)
+      },
+
+      Struct(_, ref fields) => self.fields(fields, &expression.pos),
+    }
+  }
+
+
+
+  fn block(&mut self, statements: &[Statement], pos: &TokenElement) {
+    let (first, end) = self.lines(pos);
+
+    let empty = statements.iter().all(|statement| matches!(statement.node, StatementNode::Expression(Expression { node: ExpressionNode::EOF, .. })));
+
+    if empty && self.comments.get(self.next).is_none_or(|comment| comment.line >= end) {
+      self.output.push_str("{}");
+
+      return
+    }
+
+    self.output.push('{');
+    self.trailing(first);
+    self.output.push('\n');
+
+    self.indent += 1;
+    self.fresh   = true;
+
+    self.statements(statements);
+    self.comments_before(end);
+
+    self.indent -= 1;
+
+    self.write_indent();
+    self.output.push('}');
+
+    self.last = self.last.max(end)
+  }
+
+  // a struct's fields, one to a line
+  fn fields(&mut self, fields: &[(String, Type, Option<Expression>)], pos: &TokenElement) {
+    let (first, end) = self.lines(pos);
+
+    self.output.push('{');
+    self.trailing(first);
+    self.output.push('\n');
+
+    self.indent += 1;
+    self.fresh   = true;
+
+    for (name, kind, default) in fields {
+      // only defaults know where they were, comments before bare fields end up after them
+      if let Some(ref default) = *default {
+        let (first, _) = self.lines(&default.pos);
+
+        self.comments_before(first);
+        self.gap(first)
+      }
+
+      let kind = self.kind(kind);
+
+      self.write_indent();
+      self.output.push_str(&format!("{}: {}", name, kind));
+
+      if let Some(ref default) = *default {
+        self.output.push_str(" = ");
+        self.expression(default);
+
+        let (_, last) = self.lines(&default.pos);
+
+        self.trailing(last);
+        self.last = self.last.max(last)
+      }
+
+      self.output.push('\n');
+      self.fresh = false
+    }
+
+    self.comments_before(end);
+
+    self.indent -= 1;
+
+    self.write_indent();
+    self.output.push('}');
+
+    self.last = self.last.max(end)
+  }
+
+  // `<T>(a: T, ..rest: int) -> T`, what follows `def`
+  fn signature(&mut self, generics: &[String], params: &[(String, Type)], retty: &Type) {
+    if !generics.is_empty() {
+      self.output.push_str(&format!("<{}>", generics.join(", ")))
+    }
+
+    let params = params.iter().map(|(name, kind)|
+      match kind.mode {
+        TypeMode::Splat(_) => format!("..{}: {}", name, self.kind(kind)),
+        _                  => format!("{}: {}", name, self.kind(kind)),
+      }
+    ).collect::<Vec<String>>();
+
+    self.output.push_str(&format!("({})", params.join(", ")));
+
+    if retty.node != TypeNode::Nil {
+      let retty = self.kind(retty);
+
+      self.output.push_str(" -> ");
+      self.output.push_str(&retty)
+    }
+  }
+
+  fn list(&mut self, elements: &[Expression]) {
+    for (index, element) in elements.iter().enumerate() {
+      if index > 0 {
+        self.output.push_str(", ")
+      }
+
+      self.expression(element)
+    }
+  }
+
+  // a construction right before the block of an `if` or `while` needs parentheses
+  fn condition(&mut self, condition: &Expression) {
+    let parens = matches!(condition.node, ExpressionNode::Construct(..));
+
+    self.wrapped(condition, parens)
+  }
+
+  // what calls, indexing and field access apply to
+  fn callee(&mut self, expression: &Expression) {
+    use self::ExpressionNode::*;
+
+    let parens = matches!(expression.node, Binary(..) | Unary(..) | Unwrap(..) | Else(..) | Cast(..));
+
+    self.wrapped(expression, parens)
+  }
+
+  fn wrapped(&mut self, expression: &Expression, parens: bool) {
+    if parens {
+      self.output.push('(');
+      self.expression(expression);
+      self.output.push(')')
+    } else {
+      self.expression(expression)
+    }
+  }
+
+
+
+  // types are written in the order they were read, so their lengths come up in that order too
+  fn kind(&mut self, kind: &Type) -> String {
+    let node = match kind.node {
+      TypeNode::Array(ref element, len) => {
+        let element = self.kind(element);
+        let len     = self.lengths.get(self.length).cloned().unwrap_or_else(|| len.to_string());
+
+        self.length += 1;
+
+        format!("[{}; {}]", element, len)
+      },
+
+      TypeNode::Generic(ref name, ref args) => format!("{}<{}>", name, self.kinds(args)),
+
+      TypeNode::Func(ref params, ref retty, ..) => {
+        let params = self.kinds(params);
+
+        format!("({}) -> {}", params, self.kind(retty))
+      },
+
+      ref node => node.to_string(),
+    };
+
+    match kind.mode {
+      TypeMode::Optional => format!("{}?", node),
+      _                  => node,
+    }
+  }
+
+  fn kinds(&mut self, kinds: &[Type]) -> String {
+    kinds.iter().map(|kind| self.kind(kind)).collect::<Vec<String>>().join(", ")
+  }
+
+  // what follows each `;` up to its `]`, the only place a `;` can be
+  fn lengths(tokens: &[Token]) -> Vec<String> {
+    let mut lengths = Vec::new();
+
+    for (index, token) in tokens.iter().enumerate() {
+      if token.token_type != TokenType::Symbol || token.lexeme != ";" {
+        continue
+      }
+
+      let mut length = String::new();
+      let mut depth  = 0;
+
+      let mut opening  = true;  // nothing or an opening bracket came last
+      let mut operator = false; // an operator came last
+      let mut prefix   = false; // a `-` in front of its operand came last
+
+      for token in &tokens[index + 1 ..] {
+        let lexeme = token.lexeme.as_str();
+
+        match (&token.token_type, lexeme) {
+          (TokenType::EOL, _) | (TokenType::Whitespace, _) => continue,
+
+          (_, "]") if depth == 0 => break,
+          (_, "[") | (_, "(")    => depth += 1,
+          (_, "]") | (_, ")")    => depth -= 1,
+          _                      => (),
+        }
+
+        if !length.is_empty() && !opening && !prefix && !matches!(lexeme, ")" | "]") {
+          length.push(' ')
+        }
+
+        length.push_str(lexeme);
+
+        let current = token.token_type == TokenType::Operator;
+
+        prefix   = current && (opening || operator);
+        operator = current;
+        opening  = matches!(lexeme, "(" | "[")
+      }
+
+      lengths.push(length)
+    }
+
+    lengths
+  }
+
+  fn precedence(operator: &Operator) -> u8 {
+    Operator::from_str(operator.as_str()).map_or(0, |(_, precedence)| precedence)
+  }
+
+  fn escape(text: &str, delimeter: char) -> String {
+    let mut escaped = String::new();
+
+    for c in text.chars() {
+      match c {
+        '\\'                => escaped.push_str("\\\\"),
+        '\n'                => escaped.push_str("\\n"),
+        '\r'                => escaped.push_str("\\r"),
+        '\t'                => escaped.push_str("\\t"),
+        c if c == delimeter => { escaped.push('\\'); escaped.push(c) },
+        c                   => escaped.push(c),
+      }
+    }
+
+    escaped
+  }
+
+
+
+  // comments starting before `line`, each on its own line
+  fn comments_before(&mut self, line: usize) {
+    while let Some(comment) = self.comments.get(self.next) {
+      if comment.line >= line {
+        break
+      }
+
+      let (start, end, text) = (comment.line, comment.end, comment.text.clone());
+
+      self.gap(start);
+      self.write_indent();
+      self.output.push_str(&text);
+      self.output.push('\n');
+
+      self.last  = end;
+      self.next += 1
+    }
+  }
+
+  // comments on the lines of what was just written, the first one kept at the end of it
+  fn trailing(&mut self, last: usize) {
+    let mut first = true;
+
+    while let Some(comment) = self.comments.get(self.next) {
+      if comment.line > last {
+        break
+      }
+
+      let (inline, end, text) = (comment.inline, comment.end, comment.text.clone());
+
+      if first && inline {
+        self.output.push(' ')
+      } else {
+        self.output.push('\n');
+        self.write_indent()
+      }
+
+      self.output.push_str(&text);
+
+      first      = false;
+      self.last  = self.last.max(end);
+      self.next += 1
+    }
+  }
+
+  // one blank line where the source had any, none at the start of a block
+  fn gap(&mut self, line: usize) {
+    let blank = (self.last + 1 .. line).any(|line| self.source.lines.get(line - 1).is_some_and(|code| code.trim().is_empty()));
+
+    if blank && !self.fresh {
+      self.output.push('\n')
+    }
+
+    self.fresh = false
+  }
+
+  fn write_indent(&mut self) {
+    for _ in 0 .. self.indent {
+      self.output.push_str("  ")
+    }
+  }
+
+  // first and last line of something in the source
+  fn lines(&self, pos: &TokenElement) -> (usize, usize) {
+    match *pos {
+      TokenElement::Pos(line, _)             => (line.0, line.0),
+      TokenElement::Span(first, _, last, _)  => (first.0, last.0),
+      TokenElement::Line(line)               => (line.0, line.0),
+      TokenElement::Ref(token)               => (token.line.0, token.line.0),
+      _                                      => (self.last, self.last),
+    }
+  }
+}
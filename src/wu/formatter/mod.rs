@@ -0,0 +1,8 @@
+pub mod formatter;
+
+use super::lexer::*;
+use super::parser::*;
+use super::visitor::*;
+use super::source::*;
+
+pub use self::formatter::*;
@@ -0,0 +1,318 @@
+use std::fmt;
+use std::rc::Rc;
+
+use super::*;
+
+
+
+// a value worked out without control flow of its own, blocks and `if`s are lowered into temporaries first
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValueNode<'i> {
+  Int(i64),
+  Float(f64),
+  Str(String),
+  Char(char),
+  Bool(bool),
+  Nil,
+  Name(String), // a variable, parameter or temporary
+  Binary(Rc<Value<'i>>, Operator, Rc<Value<'i>>),
+  Unary(Operator, Rc<Value<'i>>),
+  Call(Rc<Value<'i>>, Vec<Value<'i>>),
+  Unpack(Rc<Value<'i>>, Option<usize>), // a splat spread over the arguments of a call, and how many values every checked call gave it
+  Index(Rc<Value<'i>>, Rc<Value<'i>>),
+  Field(Rc<Value<'i>>, String),
+  Table(Vec<(Key, Value<'i>)>), // arrays, structs and their declarations
+  Function(Vec<(String, Type<'i>)>, Vec<Instruction<'i>>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Key {
+  Index(usize),
+  Field(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Value<'i> {
+  pub node: ValueNode<'i>,
+  pub kind: Type<'i>,
+}
+
+impl<'i> Value<'i> {
+  pub fn new(node: ValueNode<'i>, kind: Type<'i>) -> Self {
+    Value {
+      node,
+      kind,
+    }
+  }
+
+  pub fn name(name: &str, kind: Type<'i>) -> Self {
+    Value::new(ValueNode::Name(name.to_string()), kind)
+  }
+}
+
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InstructionNode<'i> {
+  Local(String, Type<'i>, Option<Value<'i>>),
+  Assign(Value<'i>, Value<'i>),
+  Eval(Value<'i>), // worked out for what it does, like a call
+  If(Value<'i>, Vec<Instruction<'i>>, Vec<Instruction<'i>>),
+  While(Value<'i>, Vec<Instruction<'i>>),
+  Loop(Vec<Instruction<'i>>), // until a `Break`
+  Break,
+  Scope(Vec<Instruction<'i>>),
+  Return(Option<Value<'i>>),
+  Import(String, Vec<String>), // local name and module path
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instruction<'i> {
+  pub node: InstructionNode<'i>,
+  pub line: Option<usize>, // the Wu line it was lowered from
+}
+
+impl<'i> Instruction<'i> {
+  pub fn new(node: InstructionNode<'i>, line: Option<usize>) -> Self {
+    Instruction {
+      node,
+      line,
+    }
+  }
+}
+
+
+
+// a checked file, ready for a backend
+#[derive(Debug, Clone, PartialEq)]
+pub struct Program<'i> {
+  pub body:    Vec<Instruction<'i>>,
+  pub exports: Vec<String>, // top-level names, what importing the file hands out
+}
+
+
+
+impl<'i> fmt::Display for Program<'i> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write_instructions(f, &self.body, 0)?;
+
+    if !self.exports.is_empty() {
+      writeln!(f, "export {}", self.exports.join(", "))?
+    }
+
+    Ok(())
+  }
+}
+
+impl<'i> fmt::Display for Instruction<'i> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write_instruction(f, self, 0)
+  }
+}
+
+impl<'i> fmt::Display for Value<'i> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write_value(f, self, 0)
+  }
+}
+
+impl fmt::Display for Key {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Key::Index(index)     => write!(f, "[{}]", index),
+      Key::Field(ref field) => write!(f, "{}", field),
+    }
+  }
+}
+
+
+
+// one instruction to a line, with the Wu line it came from in the margin
+fn write_instructions(f: &mut fmt::Formatter, instructions: &[Instruction], depth: usize) -> fmt::Result {
+  for instruction in instructions {
+    write_instruction(f, instruction, depth)?
+  }
+
+  Ok(())
+}
+
+fn write_instruction(f: &mut fmt::Formatter, instruction: &Instruction, depth: usize) -> fmt::Result {
+  use self::InstructionNode::*;
+
+  match instruction.line {
+    Some(line) => write!(f, "{:>4} | {}", line, "  ".repeat(depth))?,
+    None       => write!(f, "     | {}", "  ".repeat(depth))?,
+  }
+
+  match instruction.node {
+    Local(ref name, ref kind, ref value) => {
+      write!(f, "local {}: {}", name, kind)?;
+
+      if let Some(ref value) = *value {
+        write!(f, " = ")?;
+        write_value(f, value, depth)?
+      }
+
+      writeln!(f)
+    },
+
+    Assign(ref place, ref value) => {
+      write_value(f, place, depth)?;
+      write!(f, " = ")?;
+      write_value(f, value, depth)?;
+
+      writeln!(f)
+    },
+
+    Eval(ref value) => {
+      write!(f, "eval ")?;
+      write_value(f, value, depth)?;
+
+      writeln!(f)
+    },
+
+    If(ref condition, ref body, ref otherwise) => {
+      write!(f, "if ")?;
+      write_value(f, condition, depth)?;
+      writeln!(f, " {{")?;
+
+      write_instructions(f, body, depth + 1)?;
+
+      if !otherwise.is_empty() {
+        writeln!(f, "     | {}}} else {{", "  ".repeat(depth))?;
+
+        write_instructions(f, otherwise, depth + 1)?
+      }
+
+      writeln!(f, "     | {}}}", "  ".repeat(depth))
+    },
+
+    While(ref condition, ref body) => {
+      write!(f, "while ")?;
+      write_value(f, condition, depth)?;
+      writeln!(f, " {{")?;
+
+      write_instructions(f, body, depth + 1)?;
+
+      writeln!(f, "     | {}}}", "  ".repeat(depth))
+    },
+
+    Loop(ref body) | Scope(ref body) => {
+      writeln!(f, "{} {{", if let Loop(_) = instruction.node { "loop" } else { "scope" })?;
+
+      write_instructions(f, body, depth + 1)?;
+
+      writeln!(f, "     | {}}}", "  ".repeat(depth))
+    },
+
+    Break => writeln!(f, "break"),
+
+    Return(ref value) => {
+      write!(f, "return")?;
+
+      if let Some(ref value) = *value {
+        write!(f, " ")?;
+        write_value(f, value, depth)?
+      }
+
+      writeln!(f)
+    },
+
+    Import(ref name, ref path) => writeln!(f, "import {} = {}", name, path.join(".")),
+  }
+}
+
+// values print with their types wherever those aren't plain from the value itself
+fn write_value(f: &mut fmt::Formatter, value: &Value, depth: usize) -> fmt::Result {
+  use self::ValueNode::*;
+
+  match value.node {
+    Int(n)        => write!(f, "{}", n),
+    Float(n)      => write!(f, "{:?}", n),
+    Str(ref s)    => write!(f, "{:?}", s),
+    Char(c)       => write!(f, "{:?}", c),
+    Bool(b)       => write!(f, "{}", b),
+    Nil           => write!(f, "nil"),
+    Name(ref n)   => write!(f, "{}: {}", n, value.kind),
+
+    Binary(ref left, ref op, ref right) => {
+      write!(f, "(")?;
+      write_value(f, left, depth)?;
+      write!(f, " {} ", op)?;
+      write_value(f, right, depth)?;
+      write!(f, ")")
+    },
+
+    Unary(ref op, ref operand) => {
+      write!(f, "({} ", op)?;
+      write_value(f, operand, depth)?;
+      write!(f, ")")
+    },
+
+    Call(ref called, ref args) => {
+      write_value(f, called, depth)?;
+      write!(f, "(")?;
+
+      for (index, arg) in args.iter().enumerate() {
+        if index > 0 {
+          write!(f, ", ")?
+        }
+
+        write_value(f, arg, depth)?
+      }
+
+      write!(f, "): {}", value.kind)
+    },
+
+    Unpack(ref splat, len) => {
+      write!(f, "*")?;
+      write_value(f, splat, depth)?;
+
+      match len {
+        Some(len) => write!(f, " of {}", len),
+        None      => Ok(()),
+      }
+    },
+
+    Index(ref indexed, ref index) => {
+      write_value(f, indexed, depth)?;
+      write!(f, "[")?;
+      write_value(f, index, depth)?;
+      write!(f, "]")
+    },
+
+    Field(ref base, ref field) => {
+      write_value(f, base, depth)?;
+      write!(f, ".{}", field)
+    },
+
+    Table(ref entries) => {
+      write!(f, "{} {{", value.kind)?;
+
+      for (index, (key, entry)) in entries.iter().enumerate() {
+        write!(f, "{} {} = ", if index > 0 { "," } else { "" }, key)?;
+        write_value(f, entry, depth)?
+      }
+
+      write!(f, " }}")
+    },
+
+    Function(ref params, ref body) => {
+      write!(f, "fn(")?;
+
+      for (index, (name, kind)) in params.iter().enumerate() {
+        if index > 0 {
+          write!(f, ", ")?
+        }
+
+        write!(f, "{}: {}", name, kind)?
+      }
+
+      writeln!(f, ") {{")?;
+
+      write_instructions(f, body, depth + 1)?;
+
+      write!(f, "     | {}}}", "  ".repeat(depth))
+    },
+  }
+}
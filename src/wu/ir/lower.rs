@@ -0,0 +1,858 @@
+use std::collections::{ HashMap, HashSet };
+use std::rc::Rc;
+
+use super::*;
+
+
+
+// stands in for a local until it's known whether that can be assigned to directly
+const TARGET: &str = "\u{3}";
+
+// what the value of a block goes to once its last expression is reached
+#[derive(Clone)]
+enum Tail<'t> {
+  Effect,          // nowhere, it's only run
+  Return,          // out of the function around it
+  Into(Value<'t>), // a variable, field or temporary
+}
+
+
+
+// what a name in scope stands for, as far as lowering cares
+#[derive(Clone)]
+enum Binding {
+  Constant(ExpressionNode<'static>), // folded, inlined wherever it's used
+  Splat,                             // a splat parameter, spread where it's unwrapped
+  Local,                             // anything else, shadowing those before it
+}
+
+
+
+// desugars a checked ast into instructions, with the types the visitor found for it
+pub struct Lowering<'l, 'v: 'l> {
+  types: &'l HashMap<*const Expression<'v>, Type<'v>>,
+
+  body:      Vec<Instruction<'v>>, // where lowered instructions go right now
+  line:      Option<usize>,        // the Wu line being lowered
+  temps:     HashSet<String>,      // temporaries made so far
+  names:     HashSet<String>,      // every name the program mentions, which temporaries stay clear of
+  bindings:  Vec<HashMap<String, Binding>>, // constants and splats by scope, and the names shadowing them
+}
+
+impl<'l, 'v: 'l> Lowering<'l, 'v> {
+  pub fn new(types: &'l HashMap<*const Expression<'v>, Type<'v>>) -> Self {
+    Lowering {
+      types,
+
+      body:      Vec::new(),
+      line:      None,
+      temps:     HashSet::new(),
+      names:     HashSet::new(),
+      bindings:  vec!(HashMap::new()),
+    }
+  }
+
+
+
+  pub fn lower(mut self, ast: &'v [Statement<'v>]) -> Program<'v> {
+    for statement in ast {
+      Self::mentioned(statement, &mut self.names)
+    }
+
+    for statement in ast {
+      self.statement(statement)
+    }
+
+    // every file doubles as a module, `require` hands out its top-level names
+    let mut exports = Vec::new();
+
+    for statement in ast {
+      if let StatementNode::Variable(_, ref name, ..) = statement.node {
+        if !exports.contains(name) {
+          exports.push(name.clone())
+        }
+      }
+    }
+
+    Program {
+      body: self.body,
+      exports,
+    }
+  }
+
+
+
+  fn statement(&mut self, statement: &'v Statement<'v>) {
+    use self::StatementNode::*;
+
+    self.line = Self::line_of(&statement.pos).or(self.line);
+
+    match statement.node {
+      Expression(ref expression) => self.effect(expression),
+
+      Variable(ref kind, ref name, ref right, _) => self.variable(kind, name, right.as_ref()),
+
+      Assignment(ref left, ref right) => {
+        let place = self.value(left);
+
+        self.into(right, place)
+      },
+
+      Return(ref value) => match *value {
+        Some(ref value) => self.tail(value),
+        None            => self.emit(InstructionNode::Return(None)),
+      },
+
+      Import(ref path) => self.emit(InstructionNode::Import(path.last().unwrap().clone(), path.clone())),
+
+      Extern(..) => (), // refers to what Lua already has
+      Error      => (), // files that didn't parse never get this far
+    }
+  }
+
+  fn variable(&mut self, kind: &Type<'v>, name: &str, right: Option<&'v Expression<'v>>) {
+    let right = match right {
+      Some(right) => right,
+      None        => {
+        self.shadow(name);
+
+        return self.emit(InstructionNode::Local(name.to_string(), kind.clone(), None))
+      },
+    };
+
+    // without an annotation, a variable is what its value is
+    let kind = if kind.node == TypeNode::Nil {
+      Type::new(self.kind_of(right).node, kind.mode.clone())
+    } else {
+      kind.clone()
+    };
+
+    // constants folding into a literal are inlined wherever they're used
+    if let TypeMode::Immutable = kind.mode {
+      if let Some(folded) = self.fold(right) {
+        self.bindings.last_mut().unwrap().insert(name.to_string(), Binding::Constant(folded.clone()));
+
+        let value = self.literal(&folded, kind.clone());
+
+        return self.emit(InstructionNode::Local(name.to_string(), kind, Some(value)))
+      }
+    }
+
+    if let ExpressionNode::Block(_) | ExpressionNode::If(..) | ExpressionNode::While(..) = right.node {
+      let assigned = self.nested(|lowering| lowering.into(right, Value::name(TARGET, kind.clone())));
+
+      // a value reading the variable it shadows is worked out before that's declared
+      if Self::mentions(&assigned, name) {
+        let temp = self.temp();
+
+        self.emit(InstructionNode::Local(temp.clone(), kind.clone(), None));
+        self.body.extend(Self::rename(assigned, &temp));
+        self.shadow(name);
+        self.emit(InstructionNode::Local(name.to_string(), kind.clone(), Some(Value::name(&temp, kind))))
+      } else {
+        self.shadow(name);
+        self.emit(InstructionNode::Local(name.to_string(), kind, None));
+        self.body.extend(Self::rename(assigned, name))
+      }
+
+      return
+    }
+
+    let value = self.value(right);
+
+    self.shadow(name);
+    self.emit(InstructionNode::Local(name.to_string(), kind, Some(value)))
+  }
+
+
+
+  // an expression run for what it does
+  fn effect(&mut self, expression: &'v Expression<'v>) {
+    use self::ExpressionNode::*;
+
+    match expression.node {
+      Block(ref content) => {
+        let body = self.nested(|lowering| lowering.block(content, Tail::Effect));
+
+        self.emit(InstructionNode::Scope(body))
+      },
+
+      If(ref condition, ref body, ref elses) => self.branches(condition, body, elses.as_ref().map_or(&[][..], |elses| &elses[..]), Tail::Effect),
+
+      While(ref condition, ref body) => self.repeat(condition, body, Tail::Effect),
+
+      EOF | Empty => (),
+
+      _ => {
+        let value = self.value(expression);
+
+        self.emit(InstructionNode::Eval(value))
+      },
+    }
+  }
+
+  // an expression whose value is returned
+  fn tail(&mut self, expression: &'v Expression<'v>) {
+    use self::ExpressionNode::*;
+
+    match expression.node {
+      // already inside a function, which is scope enough
+      Block(ref content) => self.block(content, Tail::Return),
+
+      If(ref condition, ref body, ref elses) => self.branches(condition, body, elses.as_ref().map_or(&[][..], |elses| &elses[..]), Tail::Return),
+
+      // the last value of the body, once the loop is done
+      _ => {
+        let value = self.value(expression);
+
+        self.emit(InstructionNode::Return(Some(value)))
+      },
+    }
+  }
+
+  // an expression whose value ends up in `place`
+  fn into(&mut self, expression: &'v Expression<'v>, place: Value<'v>) {
+    use self::ExpressionNode::*;
+
+    match expression.node {
+      // a lone value needs no scope of its own
+      Block(ref content) => if content.len() > 1 {
+        let body = self.nested(|lowering| lowering.block(content, Tail::Into(place)));
+
+        self.emit(InstructionNode::Scope(body))
+      } else {
+        self.block(content, Tail::Into(place))
+      },
+
+      If(ref condition, ref body, ref elses) => self.branches(condition, body, elses.as_ref().map_or(&[][..], |elses| &elses[..]), Tail::Into(place)),
+
+      While(ref condition, ref body) => self.repeat(condition, body, Tail::Into(place)),
+
+      EOF | Empty => (),
+
+      _ => {
+        let value = self.value(expression);
+
+        self.emit(InstructionNode::Assign(place, value))
+      },
+    }
+  }
+
+  fn finish(&mut self, expression: &'v Expression<'v>, tail: Tail<'v>) {
+    match tail {
+      Tail::Effect      => self.effect(expression),
+      Tail::Return      => self.tail(expression),
+      Tail::Into(place) => self.into(expression, place),
+    }
+  }
+
+
+
+  // only the last statement of a block may give it a value
+  fn block(&mut self, content: &'v [Statement<'v>], tail: Tail<'v>) {
+    self.bindings.push(HashMap::new());
+
+    for (index, statement) in content.iter().enumerate() {
+      match statement.node {
+        StatementNode::Expression(ref last) if index == content.len() - 1 => {
+          self.line = Self::line_of(&statement.pos).or(self.line);
+
+          self.finish(last, tail.clone())
+        },
+
+        _ => self.statement(statement),
+      }
+    }
+
+    self.bindings.pop();
+  }
+
+  fn branches(&mut self, condition: &'v Expression<'v>, body: &'v Expression<'v>, elses: &'v [(Option<Expression<'v>>, Expression<'v>, TokenElement<'v>)], tail: Tail<'v>) {
+    let line      = self.line;
+    let condition = self.value(condition);
+
+    let then = self.nested(|lowering| lowering.branch(body, tail.clone()));
+
+    // an `elif` is an `if` of its own in the `else`, what its condition needs is worked out only there
+    let otherwise = match elses.split_first() {
+      Some(((condition, body, pos), rest)) => self.nested(|lowering| {
+        lowering.line = Self::line_of(pos).or(line);
+
+        match *condition {
+          Some(ref condition) => lowering.branches(condition, body, rest, tail),
+          None                => lowering.branch(body, tail),
+        }
+      }),
+
+      None => Vec::new(),
+    };
+
+    self.line = line;
+
+    self.emit(InstructionNode::If(condition, then, otherwise))
+  }
+
+  fn branch(&mut self, body: &'v Expression<'v>, tail: Tail<'v>) {
+    match body.node {
+      ExpressionNode::Block(ref content) => self.block(content, tail),
+      _                                  => self.finish(body, tail),
+    }
+  }
+
+  // `tail` receives the value of the last body expression on every iteration,
+  // which is what makes `i = while i < 10 { i + 1 }` terminate
+  fn repeat(&mut self, condition: &'v Expression<'v>, body: &'v Expression<'v>, tail: Tail<'v>) {
+    let line = self.line;
+
+    let mut condition_value = None;
+
+    let check     = self.nested(|lowering| condition_value = Some(lowering.value(condition)));
+    let condition = condition_value.unwrap();
+
+    let content = self.nested(|lowering| match body.node {
+      ExpressionNode::Block(ref content) => lowering.block(content, tail),
+
+      _ => lowering.effect(body),
+    });
+
+    self.line = line;
+
+    if check.is_empty() {
+      self.emit(InstructionNode::While(condition, content))
+    } else {
+      // what the condition needs is worked out again on every iteration
+      let kind = condition.kind.clone();
+      let stop = Instruction::new(InstructionNode::If(Value::new(ValueNode::Unary(Operator::Not, Rc::new(condition)), kind), vec!(Instruction::new(InstructionNode::Break, line)), Vec::new()), line);
+
+      let body = check.into_iter().chain(Some(stop)).chain(content).collect();
+
+      self.emit(InstructionNode::Loop(body))
+    }
+  }
+
+
+
+  // a value, with whatever it needs worked out ahead of it
+  fn value(&mut self, expression: &'v Expression<'v>) -> Value<'v> {
+    use self::ExpressionNode::*;
+
+    let kind = self.kind_of(expression);
+
+    let node = match expression.node {
+      Int(n)     => ValueNode::Int(n),
+      Float(n)   => ValueNode::Float(n),
+      Str(ref s) => ValueNode::Str(s.clone()),
+      Char(c)    => ValueNode::Char(c),
+      Bool(b)    => ValueNode::Bool(b),
+
+      Nil | Empty | EOF | Error => ValueNode::Nil,
+
+      Identifier(ref name) => match self.constant(name) {
+        Some(folded) => return self.literal(&folded, kind),
+        None         => ValueNode::Name(name.clone()),
+      },
+
+      // the right side of `and` and `or` runs only when needed, and so does what it needs
+      Binary(ref left, ref op, ref right) if *op == Operator::And || *op == Operator::Or => {
+        let left = self.value(left);
+
+        let mut right_value = None;
+
+        let needed = self.nested(|lowering| right_value = Some(lowering.value(right)));
+        let right  = right_value.unwrap();
+
+        if needed.is_empty() {
+          ValueNode::Binary(Rc::new(left), op.clone(), Rc::new(right))
+        } else {
+          let temp      = self.temp();
+          let result    = Value::name(&temp, kind.clone());
+          let condition = if *op == Operator::And {
+            result.clone()
+          } else {
+            Value::new(ValueNode::Unary(Operator::Not, Rc::new(result.clone())), kind.clone())
+          };
+
+          let assign = Instruction::new(InstructionNode::Assign(result.clone(), right), self.line);
+
+          self.emit(InstructionNode::Local(temp, kind.clone(), Some(left)));
+          self.emit(InstructionNode::If(condition, needed.into_iter().chain(Some(assign)).collect(), Vec::new()));
+
+          return result
+        }
+      },
+
+      Binary(ref left, ref op, ref right) => {
+        let mut values = self.operands(&[left, right]).into_iter();
+
+        ValueNode::Binary(Rc::new(values.next().unwrap()), op.clone(), Rc::new(values.next().unwrap()))
+      },
+
+      Unary(ref op, ref operand) => ValueNode::Unary(op.clone(), Rc::new(self.value(operand))),
+
+      // optionals are just values that may be nil, splats are spread
+      Unwrap(ref operand) => match operand.node {
+        Identifier(ref name) if matches!(self.binding(name), Some(Binding::Splat)) => {
+          let len = match self.kind_of(operand).mode {
+            TypeMode::Splat(len) => len,
+            _                    => None,
+          };
+
+          ValueNode::Unpack(Rc::new(self.value(operand)), len)
+        },
+
+        _ => return Value::new(self.value(operand).node, kind),
+      },
+
+      // types are gone at runtime
+      Cast(ref operand, _) => return Value::new(self.value(operand).node, kind),
+
+      Call(ref called, ref args, _) => {
+        let mut values = self.operands(&Some(&**called).into_iter().chain(args).collect::<Vec<_>>()).into_iter();
+
+        ValueNode::Call(Rc::new(values.next().unwrap()), values.collect())
+      },
+
+      Index(ref indexed, ref index) => {
+        let mut values = self.operands(&[indexed, index]).into_iter();
+
+        ValueNode::Index(Rc::new(values.next().unwrap()), Rc::new(values.next().unwrap()))
+      },
+
+      Access(ref base, ref field) => ValueNode::Field(Rc::new(self.value(base)), field.clone()),
+
+      Array(ref content) => ValueNode::Table(
+        self.operands(&content.iter().collect::<Vec<_>>()).into_iter().enumerate().map(|(index, element)| (Key::Index(index), element)).collect()
+      ),
+
+      // the declaration holds the defaults
+      Struct(_, ref fields) => ValueNode::Table(
+        fields.iter().filter_map(|(field, _, default)| default.as_ref().map(|default| (Key::Field(field.clone()), self.value(default)))).collect()
+      ),
+
+      Construct(ref called, ref args) => {
+        let fields = match self.kind_of(called).node {
+          TypeNode::StructDef(_, ref fields) => fields.clone(),
+          _                                  => Rc::new(Vec::new()),
+        };
+
+        let given = fields.iter().enumerate().map(|(index, (field, _, _))|
+          args.iter().enumerate().find(|&(position, arg)| match arg.0 {
+            Some(ref name) => name == field,
+            None           => position == index,
+          }).map(|(_, arg)| &arg.1)
+        ).collect::<Vec<_>>();
+
+        let mut values = self.operands(&Some(&**called).into_iter().chain(given.iter().filter_map(|arg| *arg)).collect::<Vec<_>>()).into_iter();
+
+        let declaration = values.next().unwrap();
+
+        let mut entries = Vec::new();
+
+        for ((field, field_kind, _), given) in fields.iter().zip(given) {
+          // what isn't given is the declared default
+          let value = match given {
+            Some(_) => values.next().unwrap(),
+            None    => Value::new(ValueNode::Field(Rc::new(declaration.clone()), field.clone()), field_kind.clone()),
+          };
+
+          entries.push((Key::Field(field.clone()), value))
+        }
+
+        ValueNode::Table(entries)
+      },
+
+      // the default is only worked out when needed
+      Else(ref optional, ref default) => {
+        let temp   = self.temp();
+        let result = Value::name(&temp, kind.clone());
+
+        let optional = self.value(optional);
+
+        self.emit(InstructionNode::Local(temp, kind.clone(), Some(optional)));
+
+        let nil       = Value::new(ValueNode::Nil, Type::from(TypeNode::Nil));
+        let condition = Value::new(ValueNode::Binary(Rc::new(result.clone()), Operator::Eq, Rc::new(nil)), Type::from(TypeNode::Bool));
+        let fallback  = self.nested(|lowering| lowering.into(default, result.clone()));
+
+        self.emit(InstructionNode::If(condition, fallback, Vec::new()));
+
+        return result
+      },
+
+      Function(ref params, _, ref body, _) => {
+        let line = self.line;
+
+        // parameters shadow constants of the same name, splats are known by their declaration
+        self.bindings.push(
+          params.iter().map(|param| (param.0.clone(), if let TypeMode::Splat(_) = param.1.mode { Binding::Splat } else { Binding::Local })).collect()
+        );
+
+        let body = self.nested(|lowering| lowering.branch(body, Tail::Return));
+
+        self.bindings.pop();
+
+        self.line = line;
+
+        ValueNode::Function(params.iter().map(|param| (param.0.clone(), param.1.clone())).collect(), body)
+      },
+
+      Block(_) | If(..) | While(..) => {
+        let temp   = self.temp();
+        let result = Value::name(&temp, kind.clone());
+
+        self.emit(InstructionNode::Local(temp, kind, None));
+        self.into(expression, result.clone());
+
+        return result
+      },
+    };
+
+    Value::new(node, kind)
+  }
+
+
+
+  // lowers operands left to right, where one needs instructions of its own the ones before it
+  // are kept in temporaries first, so they're still worked out before what it does
+  fn operands(&mut self, expressions: &[&'v Expression<'v>]) -> Vec<Value<'v>> {
+    let mut values: Vec<Value<'v>> = Vec::new();
+
+    for expression in expressions {
+      let mut value = None;
+
+      let needed = self.nested(|lowering| value = Some(lowering.value(expression)));
+
+      if !needed.is_empty() {
+        for earlier in ::std::mem::take(&mut values) {
+          if self.settled(&earlier) {
+            values.push(earlier);
+
+            continue
+          }
+
+          let temp = self.temp();
+
+          self.emit(InstructionNode::Local(temp.clone(), earlier.kind.clone(), Some(earlier.clone())));
+
+          values.push(Value::name(&temp, earlier.kind))
+        }
+
+        self.body.extend(needed)
+      }
+
+      values.push(value.unwrap())
+    }
+
+    values
+  }
+
+  // whether a value comes out the same whatever runs before it's read
+  fn settled(&self, value: &Value<'v>) -> bool {
+    match value.node {
+      ValueNode::Int(_) | ValueNode::Float(_) | ValueNode::Str(_) | ValueNode::Char(_) | ValueNode::Bool(_) | ValueNode::Nil => true,
+      ValueNode::Function(..)                                                                                         => true,
+      ValueNode::Name(ref name)                                                                                       => self.temps.contains(name),
+      _                                                                                                               => false,
+    }
+  }
+
+
+
+  fn emit(&mut self, node: InstructionNode<'v>) {
+    self.body.push(Instruction::new(node, self.line))
+  }
+
+  // lowers into instructions of their own, handing them back
+  fn nested<F: FnOnce(&mut Self)>(&mut self, lower: F) -> Vec<Instruction<'v>> {
+    let outer = ::std::mem::take(&mut self.body);
+    let line  = self.line;
+
+    lower(self);
+
+    self.line = line;
+
+    ::std::mem::replace(&mut self.body, outer)
+  }
+
+  fn temp(&mut self) -> String {
+    let mut count = self.temps.len();
+
+    while self.names.contains(&format!("__value{}", count)) {
+      count += 1
+    }
+
+    let temp = format!("__value{}", count);
+
+    self.names.insert(temp.clone());
+    self.temps.insert(temp.clone());
+
+    temp
+  }
+
+  // the names in a statement, declared or used
+  fn mentioned(statement: &Statement, names: &mut HashSet<String>) {
+    use self::StatementNode::*;
+
+    match statement.node {
+      Expression(ref expression) => Self::mentioned_in(expression, names),
+
+      Variable(_, ref name, ref right, _) => {
+        names.insert(name.clone());
+
+        if let Some(ref right) = *right {
+          Self::mentioned_in(right, names)
+        }
+      },
+
+      Assignment(ref left, ref right) => {
+        Self::mentioned_in(left, names);
+        Self::mentioned_in(right, names)
+      },
+
+      Return(Some(ref value)) => Self::mentioned_in(value, names),
+
+      Import(ref path)       => { names.insert(path.last().unwrap().clone()); },
+      Extern(ref path, ..)   => { names.insert(path[0].clone()); },
+      Return(None) | Error   => (),
+    }
+  }
+
+  fn mentioned_in(expression: &Expression, names: &mut HashSet<String>) {
+    use self::ExpressionNode::*;
+
+    match expression.node {
+      Identifier(ref name) => { names.insert(name.clone()); },
+
+      Block(ref statements) => for statement in statements {
+        Self::mentioned(statement, names)
+      },
+
+      Function(ref params, _, ref body, _) => {
+        names.extend(params.iter().map(|param| param.0.clone()));
+
+        Self::mentioned_in(body, names)
+      },
+
+      Binary(ref left, _, ref right) | Index(ref left, ref right) | While(ref left, ref right) | Else(ref left, ref right) => {
+        Self::mentioned_in(left, names);
+        Self::mentioned_in(right, names)
+      },
+
+      Unwrap(ref operand) | Unary(_, ref operand) | Cast(ref operand, _) | Access(ref operand, _) => Self::mentioned_in(operand, names),
+
+      Array(ref elements) => for element in elements {
+        Self::mentioned_in(element, names)
+      },
+
+      Call(ref called, ref args, _) => {
+        Self::mentioned_in(called, names);
+
+        for arg in args {
+          Self::mentioned_in(arg, names)
+        }
+      },
+
+      If(ref condition, ref body, ref elses) => {
+        Self::mentioned_in(condition, names);
+        Self::mentioned_in(body, names);
+
+        for (condition, body, _) in elses.iter().flatten() {
+          if let Some(ref condition) = *condition {
+            Self::mentioned_in(condition, names)
+          }
+
+          Self::mentioned_in(body, names)
+        }
+      },
+
+      Struct(_, ref fields) => for (_, _, default) in fields {
+        if let Some(ref default) = *default {
+          Self::mentioned_in(default, names)
+        }
+      },
+
+      Construct(ref left, ref fields) => {
+        Self::mentioned_in(left, names);
+
+        for (_, value) in fields {
+          Self::mentioned_in(value, names)
+        }
+      },
+
+      Int(_) | Float(_) | Str(_) | Char(_) | Bool(_) | Nil | EOF | Empty | Error => (),
+    }
+  }
+
+  // what the visitor typed an expression as, nil for what it never had to
+  fn kind_of(&self, expression: &'v Expression<'v>) -> Type<'v> {
+    self.types.get(&(expression as *const Expression<'v>)).cloned().unwrap_or_else(|| Type::from(TypeNode::Nil))
+  }
+
+  fn line_of(pos: &TokenElement) -> Option<usize> {
+    Span::new(None, pos).map(|span| span.start.0)
+  }
+
+
+
+  fn shadow(&mut self, name: &str) {
+    self.bindings.last_mut().unwrap().insert(name.to_string(), Binding::Local);
+  }
+
+  fn binding(&self, name: &str) -> Option<&Binding> {
+    self.bindings.iter().rev().filter_map(|scope| scope.get(name)).next()
+  }
+
+  fn constant(&self, name: &str) -> Option<ExpressionNode<'static>> {
+    match self.binding(name) {
+      Some(Binding::Constant(value)) => Some(value.clone()),
+      _                              => None,
+    }
+  }
+
+  fn fold(&self, expression: &Expression<'v>) -> Option<ExpressionNode<'static>> {
+    use self::ExpressionNode::*;
+
+    match Parser::fold_expression(&self.inline_constants(expression)).ok()?.node {
+      Int(n)     => Some(Int(n)),
+      Float(n)   => Some(Float(n)),
+      Bool(n)    => Some(Bool(n)),
+      Str(ref n) => Some(Str(n.clone())),
+      Char(n)    => Some(Char(n)),
+      _          => None,
+    }
+  }
+
+  // puts known constants in place, so `b :: a * 2` folds as well
+  fn inline_constants<'b>(&self, expression: &Expression<'b>) -> Expression<'b> {
+    use self::ExpressionNode::*;
+
+    let node = match expression.node {
+      Identifier(ref name) => self.constant(name).unwrap_or_else(|| expression.node.clone()),
+
+      Binary(ref left, ref op, ref right) => Binary(Rc::new(self.inline_constants(left)), op.clone(), Rc::new(self.inline_constants(right))),
+      Unary(ref op, ref operand)          => Unary(op.clone(), Rc::new(self.inline_constants(operand))),
+
+      _ => expression.node.clone(),
+    };
+
+    Expression::new(node, expression.pos.clone())
+  }
+
+  fn literal(&self, folded: &ExpressionNode, kind: Type<'v>) -> Value<'v> {
+    use self::ExpressionNode::*;
+
+    let node = match *folded {
+      Int(n)     => ValueNode::Int(n),
+      Float(n)   => ValueNode::Float(n),
+      Bool(n)    => ValueNode::Bool(n),
+      Str(ref n) => ValueNode::Str(n.clone()),
+      Char(n)    => ValueNode::Char(n),
+      _          => ValueNode::Nil,
+    };
+
+    Value::new(node, kind)
+  }
+
+
+
+  // whether `name` is read anywhere in `instructions`
+  fn mentions(instructions: &[Instruction], name: &str) -> bool {
+    let mut found = false;
+
+    Self::visit_names(instructions, &mut |n| found |= n == name);
+
+    found
+  }
+
+  fn visit_names<F: FnMut(&str)>(instructions: &[Instruction], found: &mut F) {
+    use self::InstructionNode::*;
+
+    for instruction in instructions {
+      match instruction.node {
+        Local(_, _, ref value) => if let Some(ref value) = *value {
+          Self::value_names(value, found)
+        },
+
+        Assign(ref place, ref value) => {
+          Self::value_names(place, found);
+          Self::value_names(value, found)
+        },
+
+        Eval(ref value) | Return(Some(ref value)) => Self::value_names(value, found),
+
+        If(ref condition, ref then, ref otherwise) => {
+          Self::value_names(condition, found);
+          Self::visit_names(then, found);
+          Self::visit_names(otherwise, found)
+        },
+
+        While(ref condition, ref body) => {
+          Self::value_names(condition, found);
+          Self::visit_names(body, found)
+        },
+
+        Loop(ref body) | Scope(ref body) => Self::visit_names(body, found),
+
+        Break | Return(None) | Import(..) => (),
+      }
+    }
+  }
+
+  fn value_names<F: FnMut(&str)>(value: &Value, found: &mut F) {
+    use self::ValueNode::*;
+
+    match value.node {
+      Name(ref name) => found(name),
+
+      Binary(ref left, _, ref right) | Index(ref left, ref right) => {
+        Self::value_names(left, found);
+        Self::value_names(right, found)
+      },
+
+      Unary(_, ref operand) | Field(ref operand, _) | Unpack(ref operand, _) => Self::value_names(operand, found),
+
+      Call(ref called, ref args) => {
+        Self::value_names(called, found);
+
+        for arg in args {
+          Self::value_names(arg, found)
+        }
+      },
+
+      Table(ref entries) => for (_, entry) in entries {
+        Self::value_names(entry, found)
+      },
+
+      Function(_, ref body) => Self::visit_names(body, found),
+
+      _ => (),
+    }
+  }
+
+  // the same instructions, assigning to `name` where they assigned to `TARGET`
+  fn rename(instructions: Vec<Instruction<'v>>, name: &str) -> Vec<Instruction<'v>> {
+    use self::InstructionNode::*;
+
+    instructions.into_iter().map(|instruction| {
+      let node = match instruction.node {
+        Assign(place, value) => Assign(Self::rename_value(place, name), value),
+
+        If(condition, then, otherwise) => If(condition, Self::rename(then, name), Self::rename(otherwise, name)),
+        While(condition, body)         => While(condition, Self::rename(body, name)),
+        Loop(body)                     => Loop(Self::rename(body, name)),
+        Scope(body)                    => Scope(Self::rename(body, name)),
+
+        node => node,
+      };
+
+      Instruction::new(node, instruction.line)
+    }).collect()
+  }
+
+  fn rename_value(value: Value<'v>, name: &str) -> Value<'v> {
+    match value.node {
+      ValueNode::Name(ref n) if n == TARGET => Value::name(name, value.kind.clone()),
+      _                                   => value,
+    }
+  }
+}
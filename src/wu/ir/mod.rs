@@ -0,0 +1,10 @@
+pub mod ir;
+pub mod lower;
+
+use super::error::*;
+use super::lexer::*;
+use super::parser::*;
+use super::visitor::*;
+
+pub use self::ir::*;
+pub use self::lower::*;
\ No newline at end of file
@@ -6,7 +6,7 @@ use std::rc::Rc;
 
 pub struct Lexer<'l> {
   tokenizer: Tokenizer<'l>,
-  matchers:  Vec<Rc<Matcher<'l>>>,
+  matchers:  Vec<Rc<dyn Matcher<'l>>>,
   source:    &'l Source,
 }
 
@@ -33,7 +33,7 @@ impl<'l> Lexer<'l> {
     lexer.matchers.push(
       Rc::new(
         KeyMatcher::new(Keyword, &[
-          "def", "->", "as", "if", "elif", "else", "return",
+          "def", "->", "as", "if", "elif", "else", "while", "return", "import", "extern", "nil",
         ])
       )
     );
@@ -44,6 +44,12 @@ impl<'l> Lexer<'l> {
       )
     );
 
+    lexer.matchers.push(
+      Rc::new(
+        KeyMatcher::new(Operator, &["and", "or", "not"])
+      )
+    );
+
     lexer.matchers.push(
       Rc::new(
         ConstantStringMatcher::new(Symbol, &[".."])
@@ -62,7 +68,7 @@ impl<'l> Lexer<'l> {
 
     lexer.matchers.push(
       Rc::new(
-        ConstantCharMatcher::new(Symbol, &['(', ')', '[', ']', '{', '}', ',', ':', ';', '=', '.', '|'])
+        ConstantCharMatcher::new(Symbol, &['(', ')', '[', ']', '{', '}', ',', ':', ';', '=', '.', '|', '?'])
       )
     );
 
@@ -91,22 +97,28 @@ impl<'l> Iterator for Lexer<'l> {
         None    => {
           let pos = self.tokenizer.pos;
 
+          // skipped, so lexing can go on
+          self.tokenizer.advance();
+
           return Some(
-            Err(
-              response!(
-                Wrong("bumped into weird character"),
-                self.source.file,
-                TokenElement::Pos(
-                  (pos.0, &self.source.lines.get(pos.0.saturating_sub(1)).unwrap_or(self.source.lines.last().unwrap_or(&String::new()))),
-                  (pos.1 + 1, pos.1 + 1),
-                )
+            fail!(
+              "E0101" => Wrong("bumped into weird character"),
+              self.source.file,
+              TokenElement::Pos(
+                (pos.0, self.source.lines.get(pos.0.saturating_sub(1)).unwrap_or(self.source.lines.last().unwrap_or(&String::new()))),
+                (pos.1 + 1, pos.1 + 1),
               )
             )
           )
         },
       },
 
-      Err(_) => return Some(Err(())),
+      // a matcher gave up halfway through a token, there's no telling where the next one starts
+      Err(_) => {
+        self.tokenizer.index = self.tokenizer.items.len();
+
+        return Some(Err(()))
+      },
     };
 
     match token.token_type {
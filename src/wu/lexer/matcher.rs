@@ -12,15 +12,12 @@ macro_rules! token {
 
     let accum: String = $accum;
     let pos           = tokenizer.last_position();
+    let len           = tokenizer.index - tokenizer.peek_snapshot().map_or(0, |snapshot| snapshot.index); // characters read, quotes and escapes too
 
 
     let line = tokenizer.source.lines.get(pos.0.saturating_sub(1)).unwrap_or(tokenizer.source.lines.last().unwrap());
 
-    if TokenType::Str == token_type || TokenType::Char == token_type {
-      Token::new(token_type, (pos.0, &line), (pos.1 + 1, pos.1 + accum.len() + 2), &accum) // delimeters
-    } else {
-      Token::new(token_type, (pos.0, &line), (pos.1 + 1, pos.1 + accum.len()), &accum)
-    }
+    Token::new(token_type, (pos.0, &line), (pos.1 + 1, pos.1 + len), &accum)
   }};
 }
 
@@ -36,29 +33,45 @@ pub struct CommentMatcher;
 
 impl<'t> Matcher<'t> for CommentMatcher {
   fn try_match(&self, tokenizer: &mut Tokenizer<'t>) -> Result<Option<Token<'t>>, ()> {
-    if tokenizer.peek_range(3).unwrap_or_else(String::new) == "---" {
+    let mut comment = String::new();
+
+    if tokenizer.peek_range(3).unwrap_or_default() == "---" {
+      comment.push_str("---");
       tokenizer.advance_n(3);
 
       while !tokenizer.end() {
-        if tokenizer.peek_range(3).unwrap_or_else(String::new) == "---" {
+        if tokenizer.peek_range(3).unwrap_or_default() == "---" {
+          comment.push_str("---");
           tokenizer.advance_n(3);
           break
         }
 
-        tokenizer.advance()
-      }
+        comment.extend(tokenizer.peek());
 
-      Ok(Some(token!(tokenizer, EOL, "\n".into())))
+        // lines inside the comment still count
+        if tokenizer.peek() == Some('\n') {
+          tokenizer.pos.0 += 1;
+          tokenizer.pos.1 = 0;
+          tokenizer.index += 1
+        } else {
+          tokenizer.advance()
+        }
+      }
 
-    } else if tokenizer.peek_range(2).unwrap_or_else(String::new) == "--" {
+    } else if tokenizer.peek_range(2).unwrap_or_default() == "--" {
       while !tokenizer.end() && tokenizer.peek() != Some('\n') {
+        comment.extend(tokenizer.peek());
         tokenizer.advance()
       }
-
-      Ok(Some(token!(tokenizer, EOL, "\n".into())))
     } else {
-      Ok(None)
+      return Ok(None)
     }
+
+    let mut token = token!(tokenizer, EOL, "\n".into());
+
+    token.trivia = Some(comment);
+
+    Ok(Some(token))
   }
 }
 
@@ -164,14 +177,12 @@ impl<'t> Matcher<'t> for StringLiteralMatcher {
 
           '"'
         } else if tokenizer.peek_n(1) == Some('\'') {
-          return Err(
-            response!(
-              Wrong("no such thing as a raw character literal"),
-              tokenizer.source.file,
-              TokenElement::Pos(
-                (pos.0, &tokenizer.source.lines.get(pos.0.saturating_sub(1)).unwrap_or(tokenizer.source.lines.last().unwrap())),
-                (pos.1 - 1, pos.1),
-              )
+          return fail!(
+            "E0104" => Wrong("no such thing as a raw character literal"),
+            tokenizer.source.file,
+            TokenElement::Pos(
+              (pos.0, tokenizer.source.lines.get(pos.0.saturating_sub(1)).unwrap_or(tokenizer.source.lines.last().unwrap())),
+              (pos.1 - 1, pos.1),
             )
           )
         } else {
@@ -188,14 +199,12 @@ impl<'t> Matcher<'t> for StringLiteralMatcher {
 
     loop {
       if tokenizer.end() {
-        return Err(
-          response!(
-            Wrong(format!("unterminated delimeter `{}`", delimeter)),
-            tokenizer.source.file,
-            TokenElement::Pos(
-              (pos.0 + 1, &tokenizer.source.lines.get(pos.0.saturating_sub(1)).unwrap_or(tokenizer.source.lines.last().unwrap())),
-              (pos.1.saturating_sub(1), pos.1 + 1),
-            )
+        return fail!(
+          "E0102" => Wrong(format!("unterminated delimeter `{}`", delimeter)),
+          tokenizer.source.file,
+          TokenElement::Pos(
+            (pos.0 + 1, tokenizer.source.lines.get(pos.0.saturating_sub(1)).unwrap_or(tokenizer.source.lines.last().unwrap())),
+            (pos.1.saturating_sub(1), pos.1 + 1),
           )
         )
       }
@@ -213,14 +222,12 @@ impl<'t> Matcher<'t> for StringLiteralMatcher {
             'n' => '\n',
             'r' => '\r',
             't' => '\t',
-            escaped => return Err(
-              response!(
-                Wrong(format!("unexpected escape character: {}", escaped)),
-                tokenizer.source.file,
-                TokenElement::Pos(
-                  (tokenizer.pos.0, &tokenizer.source.lines.get(pos.0.saturating_sub(1)).unwrap_or(tokenizer.source.lines.last().unwrap())),
-                  (tokenizer.pos.1 - 1, tokenizer.pos.1),
-                )
+            escaped => return fail!(
+              "E0103" => Wrong(format!("unexpected escape character: {}", escaped)),
+              tokenizer.source.file,
+              TokenElement::Pos(
+                (tokenizer.pos.0, tokenizer.source.lines.get(pos.0.saturating_sub(1)).unwrap_or(tokenizer.source.lines.last().unwrap())),
+                (tokenizer.pos.1 - 1, tokenizer.pos.1),
               )
             ),
           }
@@ -236,7 +243,7 @@ impl<'t> Matcher<'t> for StringLiteralMatcher {
 
           // check for valid closing delimeter and alternative
           c => if c == delimeter {
-            if string.len() > 0 && string != " " {
+            if !string.is_empty() && string != " " {
               break
             } else {
               string.push(tokenizer.next().unwrap())
@@ -256,14 +263,12 @@ impl<'t> Matcher<'t> for StringLiteralMatcher {
       if string.len() > 1 {
         let pos = tokenizer.last_position();
 
-        Err(
-          response!(
-            Wrong("character literal may not contain more than one codepoint"),
-            tokenizer.source.file,
-            TokenElement::Pos(
-              (pos.0, &tokenizer.source.lines.get(pos.0.saturating_sub(1)).unwrap_or(tokenizer.source.lines.last().unwrap())),
-              (pos.1 + 2, pos.1 + string.len() + 1),
-            )
+        fail!(
+          "E0104" => Wrong("character literal may not contain more than one codepoint"),
+          tokenizer.source.file,
+          TokenElement::Pos(
+            (pos.0, tokenizer.source.lines.get(pos.0.saturating_sub(1)).unwrap_or(tokenizer.source.lines.last().unwrap())),
+            (pos.1 + 2, pos.1 + string.len() + 1),
           )
         )
       } else {
@@ -279,11 +284,11 @@ pub struct IdentifierMatcher;
 
 impl<'t> Matcher<'t> for IdentifierMatcher {
   fn try_match(&self, tokenizer: &mut Tokenizer<'t>) -> Result<Option<Token<'t>>, ()> {
-    if !tokenizer.peek().unwrap().is_alphabetic() && !(tokenizer.peek().unwrap() == '_') {
+    if !tokenizer.peek().unwrap().is_alphabetic() && tokenizer.peek().unwrap() != '_' {
       return Ok(None)
     }
 
-    let accum = tokenizer.collect_while(|c| c.is_alphanumeric() || "_!?".contains(c));
+    let accum = tokenizer.collect_while(|c| c.is_alphanumeric() || "_!".contains(c));
 
     if accum.is_empty() {
       Ok(None)
@@ -301,7 +306,7 @@ impl<'t> Matcher<'t> for NumberLiteralMatcher {
     let mut accum = String::new();
 
     let curr = tokenizer.next().unwrap();
-    if curr.is_digit(10) {
+    if curr.is_ascii_digit() {
       accum.push(curr)
     } else if curr == '.' {
       accum.push_str("0.")
@@ -311,18 +316,16 @@ impl<'t> Matcher<'t> for NumberLiteralMatcher {
 
     while !tokenizer.end() {
       let current = tokenizer.peek().unwrap();
-      if !current.is_whitespace() && current.is_digit(10) || current == '.' {
+      if !current.is_whitespace() && current.is_ascii_digit() || current == '.' {
         if current == '.' && accum.contains('.') {
           let pos = tokenizer.pos;
           
-          return Err(
-            response!(
-              Wrong("unexpected extra decimal point"),
-              tokenizer.source.file,
-              TokenElement::Pos(
-                (pos.0, &tokenizer.source.lines.get(pos.0.saturating_sub(1)).unwrap_or(tokenizer.source.lines.last().unwrap())),
-                (pos.1 + 1, pos.1 + 1),
-              )
+          return fail!(
+            "E0105" => Wrong("unexpected extra decimal point"),
+            tokenizer.source.file,
+            TokenElement::Pos(
+              (pos.0, tokenizer.source.lines.get(pos.0.saturating_sub(1)).unwrap_or(tokenizer.source.lines.last().unwrap())),
+              (pos.1 + 1, pos.1 + 1),
             )
           )
         }
@@ -343,9 +346,10 @@ impl<'t> Matcher<'t> for NumberLiteralMatcher {
 
         Ok(Some(token!(tokenizer, Float, literal)))
       } else {
+        // too large ones are kept as written, for the parser to report
         let literal: String = match accum.parse::<i64>() {
           Ok(result) => result.to_string(),
-          Err(error) => panic!("unable to parse int: {}", error)
+          Err(_)     => accum,
         };
 
         Ok(Some(token!(tokenizer, Int, literal)))
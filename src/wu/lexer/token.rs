@@ -1,4 +1,3 @@
-use colored::Colorize;
 use std::fmt;
 
 #[derive(Debug, Clone, PartialEq)]
@@ -46,10 +45,11 @@ pub enum TokenElement<'e> {
   Ref(&'e Token<'e>),
   Line((usize, &'e str)),
   Pos((usize, &'e str), (usize, usize)),
+  Span((usize, &'e str), usize, (usize, &'e str), usize), // from a column on one line to a column on a later one
   Row(&'e [&'e Token<'e>]),
 }
 
-use self::TokenElement::{ Row, Line, Pair, Type, Pos, Ref, Lexeme, };
+use self::TokenElement::{ Pair, Type, Ref, Lexeme, };
 
 impl<'t> PartialEq<Token<'t>> for TokenElement<'t> {
   fn eq (&self, rhs: &Token<'t>) -> bool {
@@ -57,76 +57,13 @@ impl<'t> PartialEq<Token<'t>> for TokenElement<'t> {
   }
 }
 
-impl<'s> fmt::Display for TokenElement<'s> {
-  fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
-    match *self {
-      Ref (r) => {
-        if r.slice.1 > r.line.1.len() {
-          write!(f, "{}", Pos(r.line, (r.slice.0, r.line.1.len())))
-        } else {
-          write!(f, "{}", Pos(r.line, r.slice))
-        }
-      }
-
-      Line(line) => {
-        let linepad = format!("{:5} │", " ").blue().bold();
-        let lineno = format!("{:5} │ ", line.0);
-        let srcline = format!("{}{}", lineno.blue().bold(), line.1);
-
-        write!(f, "\n{}\n{}\n{}",
-          linepad,
-          srcline,
-          linepad
-        )
-      },
-
-      Pos(line, slice) => {
-        let linepad = format!("{:5} │", " ").blue().bold();
-        let lineno = format!("{:5} │ ", line.0).blue().bold();
-        let mut mark = line.1[slice.0.saturating_sub(1) .. slice.1].to_string();
-
-        if mark.split_whitespace().count() == 0 {
-          mark = format!("{:─>count$}", ">".bold().magenta(), count=mark.len());
-        } else {
-          mark = format!("{}", mark.bold().magenta());
-        }
-
-        write!(f, "\n{}\n{}{}{}{}\n{}",
-          linepad,
-          lineno, &line.1[..slice.0.saturating_sub(1)], mark, &line.1[slice.1..],
-          linepad
-        )
-      },
-
-      Row(row) => {
-        let mut mark = format!("{:offset$}", "", offset = row[0].slice.0);
-        let mut len = row[0].slice.0;
-
-        for token in row {
-          mark = format!(
-            "{}{:offset$}{:▔<count$}", mark, "", "".magenta().bold(),
-            offset = token.slice.0 - len,
-            count = token.slice.1 - token.slice.0 + 1
-          );
-          len = token.slice.1 + 1;
-        }
-
-        write!(f, "{}{}", Line(row[0].line), mark)
-      },
-
-      _ => write!(f, ""),
-    }
-  }
-}
-
-
-
 #[derive(Debug, Clone, PartialEq)]
 pub struct Token<'t> {
   pub token_type: TokenType,
   pub line:       (usize, &'t str),
   pub slice:      (usize, usize),
   pub lexeme:     String,
+  pub trivia:     Option<String>, // the comment an end of line stands in for, kept for the formatter
 }
 
 impl<'t> Token<'t> {
@@ -135,7 +72,8 @@ impl<'t> Token<'t> {
       token_type,
       line,
       slice,
-      lexeme: lexeme.to_string()
+      lexeme: lexeme.to_string(),
+      trivia: None,
     }
   }
 }
@@ -143,11 +81,11 @@ impl<'t> Token<'t> {
 impl<'t> PartialEq<TokenElement<'t>> for Token<'t> {
   fn eq (&self, rhs: &TokenElement<'t>) -> bool {
     match *rhs {
-      Type (ref t)        => self.token_type == *t,
-      Lexeme (ref l)      => self.lexeme     == *l,
-      Pair (ref t, ref l) => self.lexeme     == *l && self.token_type == *t,
-      Ref (ref t)         => self            == *t,
-      _                   => false
+      Type (ref t)    => self.token_type == *t,
+      Lexeme (l)      => self.lexeme     == l,
+      Pair (ref t, l) => self.lexeme     == l && self.token_type == *t,
+      Ref (t)         => self            == t,
+      _               => false
     }
   }
 }
\ No newline at end of file
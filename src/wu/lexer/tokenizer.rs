@@ -43,7 +43,7 @@ impl<'t> Tokenizer<'t> {
   }
 
   pub fn advance(&mut self) {
-    if let Some(item) = self.items.get(self.index + 1) {
+    if self.items.get(self.index + 1).is_some() {
       self.pos.1 += 1
     }
 
@@ -90,14 +90,14 @@ impl<'t> Tokenizer<'t> {
     self.peek_snapshot().unwrap_or(&Snapshot::new(0, (0, 0))).pos
   }
 
-  pub fn try_match_token(&mut self, matcher: &Matcher<'t>) -> Result<Option<Token<'t>>, ()> {
+  pub fn try_match_token(&mut self, matcher: &dyn Matcher<'t>) -> Result<Option<Token<'t>>, ()> {
     if self.end() {
       return Ok(
         Some(
           Token::new(
             TokenType::EOF,
-            (self.pos.0, if self.source.lines.len() > 0 {
-                &self.source.lines.get(self.pos.0).unwrap_or(self.source.lines.first().unwrap())
+            (self.pos.0, if !self.source.lines.is_empty() {
+                self.source.lines.get(self.pos.0).unwrap_or(self.source.lines.first().unwrap())
               } else {
                 ""
               }
@@ -0,0 +1,152 @@
+use super::*;
+
+
+
+// an identifier in the document, with what it is and where it was declared
+#[derive(Debug, Clone)]
+pub struct Symbol {
+  pub name: String,
+  pub pos:  Site,
+  pub kind: String,
+  pub site: Option<Site>,
+}
+
+// a checked block and the names visible at its end
+#[derive(Debug, Clone)]
+pub struct Scope {
+  pub start: (usize, usize),
+  pub end:   (usize, usize),
+  pub names: Vec<String>,
+}
+
+
+
+// what one check of a document found, owned so it outlives the tree it was found in
+#[derive(Debug, Clone, Default)]
+pub struct Analysis {
+  pub diagnostics: Vec<Diagnostic>,
+  pub symbols:     Vec<Symbol>,
+  pub scopes:      Vec<Scope>,
+  pub globals:     Vec<String>,
+  pub lines:       Vec<String>, // of the document, to count columns the way editors do
+}
+
+impl Analysis {
+  // runs the same phases as compiling, short of generating anything
+  pub fn new(file: &str, content: &str) -> Self {
+    let mut analysis = Analysis {
+      lines: content.lines().map(|line| line.to_string()).collect(),
+      ..Analysis::default()
+    };
+
+    analysis.check(file, content);
+
+    // problems in imported files belong to those
+    analysis.diagnostics = take_diagnostics().into_iter().filter(|d| d.file.as_deref().is_none_or(|f| f == file)).collect();
+
+    analysis
+  }
+
+  fn check(&mut self, file: &str, content: &str) {
+    let source = Source::from(file, content.lines().map(|x| x.into()).collect::<Vec<String>>());
+
+    let mut tokens = Vec::new();
+
+    for token in Lexer::default(content.chars().collect(), &source) {
+      match token {
+        Ok(token) => tokens.push(token),
+        Err(_)    => return,
+      }
+    }
+
+    let mut parser = Parser::new(tokens.iter().collect(), &source);
+
+    let ast = parser.parse();
+
+    let mut visitor = Visitor::new(&source, &ast);
+
+    if visitor.visit().is_ok() && !parser.failed() {
+      Linter::new(&source, &ast, &LintLevels::default()).lint()
+    }
+
+    for reference in &visitor.references {
+      self.symbols.push(
+        Symbol {
+          name: reference.name.clone(),
+          pos:  reference.pos,
+          kind: reference.kind.to_string(),
+          site: reference.site,
+        }
+      )
+    }
+
+    for (pos, names) in &visitor.scopes {
+      if let Some(span) = Span::new(None, pos) {
+        self.scopes.push(
+          Scope {
+            start: span.start,
+            end:   span.end,
+            names: names.clone(),
+          }
+        )
+      }
+    }
+
+    if let Some((symtab, _)) = visitor.tabs.first() {
+      self.globals = symtab.visible_names()
+    }
+  }
+
+
+
+  // the identifier under a cursor, lines and columns start at 1
+  pub fn symbol_at(&self, line: usize, column: usize) -> Option<&Symbol> {
+    self.symbols.iter().rev().find(|symbol| symbol.pos.0 == line && (symbol.pos.1).0 <= column && column <= (symbol.pos.1).1 + 1)
+  }
+
+  // names visible from a cursor, those of the innermost block around it first
+  pub fn names_at(&self, line: usize, column: usize) -> Vec<String> {
+    let innermost = self.scopes.iter()
+      .filter(|scope| scope.start <= (line, column) && (line, column) <= scope.end)
+      .max_by_key(|scope| scope.start);
+
+    let mut names = Vec::new();
+
+    for name in innermost.map_or(&self.globals, |scope| &scope.names) {
+      if !names.contains(name) {
+        names.push(name.clone())
+      }
+    }
+
+    names
+  }
+
+  // a column counted in characters, which is how tang counts them, as the UTF-16 code units before it
+  pub fn utf16(&self, line: usize, column: usize) -> usize {
+    self.line(line).chars().take(column).map(char::len_utf16).sum()
+  }
+
+  // the column a number of UTF-16 code units into a line ends at, counted in characters
+  pub fn column(&self, line: usize, utf16: usize) -> usize {
+    let mut units = 0;
+
+    for (column, c) in self.line(line).chars().enumerate() {
+      if units >= utf16 {
+        return column
+      }
+
+      units += c.len_utf16()
+    }
+
+    self.line(line).chars().count() + utf16.saturating_sub(units)
+  }
+
+  fn line(&self, line: usize) -> &str {
+    self.lines.get(line.wrapping_sub(1)).map_or("", |line| line.as_str())
+  }
+
+  // the type `name` was declared with, for completions
+  pub fn kind_of(&self, name: &str) -> Option<&str> {
+    self.symbols.iter().find(|symbol| symbol.name == name && symbol.site == Some(symbol.pos)).map(|symbol| symbol.kind.as_str())
+  }
+}
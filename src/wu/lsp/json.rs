@@ -0,0 +1,308 @@
+use std::fmt;
+
+use super::super::error::json_string;
+
+
+
+// just enough JSON for the messages editors send and expect back
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+  Null,
+  Bool(bool),
+  Number(f64),
+  Str(String),
+  Array(Vec<Json>),
+  Object(Vec<(String, Json)>),
+}
+
+static NULL: Json = Json::Null;
+
+impl Json {
+  pub fn object(fields: Vec<(&str, Json)>) -> Self {
+    Json::Object(fields.into_iter().map(|(key, value)| (key.to_string(), value)).collect())
+  }
+
+  pub fn string<T: fmt::Display>(value: T) -> Self {
+    Json::Str(value.to_string())
+  }
+
+  // missing fields read as `null`, so nested lookups don't need checking one by one
+  pub fn get(&self, key: &str) -> &Json {
+    if let Json::Object(ref fields) = *self {
+      if let Some((_, value)) = fields.iter().find(|field| field.0 == key) {
+        return value
+      }
+    }
+
+    &NULL
+  }
+
+  pub fn as_str(&self) -> Option<&str> {
+    match *self {
+      Json::Str(ref s) => Some(s),
+      _                => None,
+    }
+  }
+
+  pub fn as_usize(&self) -> Option<usize> {
+    match *self {
+      Json::Number(n) if n >= 0.0 => Some(n as usize),
+      _                           => None,
+    }
+  }
+
+  pub fn as_array(&self) -> Option<&Vec<Json>> {
+    match *self {
+      Json::Array(ref elements) => Some(elements),
+      _                         => None,
+    }
+  }
+
+  pub fn is_null(&self) -> bool {
+    *self == Json::Null
+  }
+
+
+
+  pub fn parse(text: &str) -> Option<Self> {
+    let mut reader = Reader {
+      chars: text.chars().collect(),
+      index: 0,
+    };
+
+    let value = reader.value()?;
+
+    reader.whitespace();
+
+    if reader.index == reader.chars.len() {
+      Some(value)
+    } else {
+      None
+    }
+  }
+}
+
+impl fmt::Display for Json {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Json::Null        => write!(f, "null"),
+      Json::Bool(b)     => write!(f, "{}", b),
+      Json::Str(ref s)  => write!(f, "{}", json_string(s)),
+
+      // whole numbers go out without a fraction, ids and positions are compared as integers
+      Json::Number(n) => if n.fract() == 0.0 && n.abs() < 1e15 {
+        write!(f, "{}", n as i64)
+      } else {
+        write!(f, "{}", n)
+      },
+
+      Json::Array(ref elements) => {
+        write!(f, "[")?;
+
+        for (index, element) in elements.iter().enumerate() {
+          if index > 0 {
+            write!(f, ",")?
+          }
+
+          write!(f, "{}", element)?
+        }
+
+        write!(f, "]")
+      },
+
+      Json::Object(ref fields) => {
+        write!(f, "{{")?;
+
+        for (index, (key, value)) in fields.iter().enumerate() {
+          if index > 0 {
+            write!(f, ",")?
+          }
+
+          write!(f, "{}:{}", json_string(key), value)?
+        }
+
+        write!(f, "}}")
+      },
+    }
+  }
+}
+
+
+
+struct Reader {
+  chars: Vec<char>,
+  index: usize,
+}
+
+impl Reader {
+  fn value(&mut self) -> Option<Json> {
+    self.whitespace();
+
+    match *self.chars.get(self.index)? {
+      '{' => self.object(),
+      '[' => self.array(),
+      '"' => self.string().map(Json::Str),
+      't' => self.keyword("true", Json::Bool(true)),
+      'f' => self.keyword("false", Json::Bool(false)),
+      'n' => self.keyword("null", Json::Null),
+      _   => self.number(),
+    }
+  }
+
+  fn object(&mut self) -> Option<Json> {
+    let mut fields = Vec::new();
+
+    self.index += 1;
+    self.whitespace();
+
+    if self.eat('}') {
+      return Some(Json::Object(fields))
+    }
+
+    loop {
+      self.whitespace();
+
+      let key = self.string()?;
+
+      self.whitespace();
+
+      if !self.eat(':') {
+        return None
+      }
+
+      fields.push((key, self.value()?));
+
+      self.whitespace();
+
+      if self.eat('}') {
+        return Some(Json::Object(fields))
+      }
+
+      if !self.eat(',') {
+        return None
+      }
+    }
+  }
+
+  fn array(&mut self) -> Option<Json> {
+    let mut elements = Vec::new();
+
+    self.index += 1;
+    self.whitespace();
+
+    if self.eat(']') {
+      return Some(Json::Array(elements))
+    }
+
+    loop {
+      elements.push(self.value()?);
+
+      self.whitespace();
+
+      if self.eat(']') {
+        return Some(Json::Array(elements))
+      }
+
+      if !self.eat(',') {
+        return None
+      }
+    }
+  }
+
+  fn string(&mut self) -> Option<String> {
+    if !self.eat('"') {
+      return None
+    }
+
+    let mut s = String::new();
+
+    loop {
+      let c = *self.chars.get(self.index)?;
+
+      self.index += 1;
+
+      match c {
+        '"'  => return Some(s),
+        '\\' => {
+          let escaped = *self.chars.get(self.index)?;
+
+          self.index += 1;
+
+          match escaped {
+            'n' => s.push('\n'),
+            'r' => s.push('\r'),
+            't' => s.push('\t'),
+            'b' => s.push('\u{8}'),
+            'f' => s.push('\u{c}'),
+            'u' => {
+              let high = self.hex()?;
+
+              // characters outside the basic plane come as two halves, the second one after the first
+              let code = if (0xd800 .. 0xdc00).contains(&high) && self.eat('\\') && self.eat('u') {
+                let low = self.hex()?;
+
+                if !(0xdc00 ..= 0xdfff).contains(&low) {
+                  return None
+                }
+
+                0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)
+              } else {
+                high
+              };
+
+              s.push(::std::char::from_u32(code).unwrap_or('\u{fffd}'))
+            },
+
+            c => s.push(c),
+          }
+        },
+
+        c => s.push(c),
+      }
+    }
+  }
+
+  fn hex(&mut self) -> Option<u32> {
+    let digits = self.chars.get(self.index .. self.index + 4)?.iter().collect::<String>();
+
+    self.index += 4;
+
+    u32::from_str_radix(&digits, 16).ok()
+  }
+
+  fn number(&mut self) -> Option<Json> {
+    let start = self.index;
+
+    while self.chars.get(self.index).is_some_and(|c| c.is_ascii_digit() || ['-', '+', '.', 'e', 'E'].contains(c)) {
+      self.index += 1
+    }
+
+    self.chars[start .. self.index].iter().collect::<String>().parse().ok().map(Json::Number)
+  }
+
+  fn keyword(&mut self, word: &str, value: Json) -> Option<Json> {
+    for c in word.chars() {
+      if !self.eat(c) {
+        return None
+      }
+    }
+
+    Some(value)
+  }
+
+  fn eat(&mut self, c: char) -> bool {
+    if self.chars.get(self.index) == Some(&c) {
+      self.index += 1;
+
+      true
+    } else {
+      false
+    }
+  }
+
+  fn whitespace(&mut self) {
+    while self.chars.get(self.index).is_some_and(|c| c.is_whitespace()) {
+      self.index += 1
+    }
+  }
+}
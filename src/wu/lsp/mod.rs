@@ -0,0 +1,13 @@
+pub mod json;
+pub mod analysis;
+pub mod server;
+
+use super::error::*;
+use super::source::*;
+use super::lexer::*;
+use super::parser::*;
+use super::visitor::*;
+
+pub use self::json::*;
+pub use self::analysis::*;
+pub use self::server::*;
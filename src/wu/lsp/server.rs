@@ -0,0 +1,409 @@
+use std::io::prelude::*;
+use std::collections::HashMap;
+use std::panic;
+
+use super::*;
+use super::super::error::Response::Wrong;
+
+
+
+// a language server speaking JSON-RPC over a pair of streams, one document check per change
+pub struct Server<R: BufRead, W: Write> {
+  input:  R,
+  output: W,
+
+  documents: HashMap<String, Analysis>, // open documents by uri
+  shutdown:  bool,
+}
+
+impl<R: BufRead, W: Write> Server<R, W> {
+  pub fn new(input: R, output: W) -> Self {
+    Server {
+      input,
+      output,
+
+      documents: HashMap::new(),
+      shutdown:  false,
+    }
+  }
+
+
+
+  // serves until the client says `exit`, handing back the exit code the protocol asks for
+  pub fn run(&mut self) -> i32 {
+    while let Some(message) = self.receive() {
+      match Json::parse(&message) {
+        Some(message) => if !self.handle(&message) {
+          break
+        },
+
+        None => self.send(
+          Json::object(vec!(
+            ("jsonrpc", Json::string("2.0")),
+            ("id",      Json::Null),
+            ("error",   Self::error(-32700, "couldn't parse message")),
+          ))
+        ),
+      }
+    }
+
+    if self.shutdown { 0 } else { 1 }
+  }
+
+
+
+  fn handle(&mut self, message: &Json) -> bool {
+    let id     = message.get("id");
+    let params = message.get("params");
+
+    let result = match message.get("method").as_str().unwrap_or("") {
+      "initialize" => Json::object(vec!(
+        ("capabilities", Json::object(vec!(
+          ("textDocumentSync",   Json::Number(1.0)), // the whole document on every change
+          ("hoverProvider",      Json::Bool(true)),
+          ("definitionProvider", Json::Bool(true)),
+          ("completionProvider", Json::object(vec!(("triggerCharacters", Json::Array(Vec::new()))))),
+        ))),
+
+        ("serverInfo", Json::object(vec!(
+          ("name", Json::string("tang-lsp")),
+        ))),
+      )),
+
+      "shutdown" => {
+        self.shutdown = true;
+
+        Json::Null
+      },
+
+      "exit" => return false,
+
+      "textDocument/didOpen" => {
+        let document = params.get("textDocument");
+
+        if let (Some(uri), Some(text)) = (document.get("uri").as_str(), document.get("text").as_str()) {
+          self.check(uri, text)
+        }
+
+        return true
+      },
+
+      "textDocument/didChange" => {
+        let uri    = params.get("textDocument").get("uri").as_str();
+        let change = params.get("contentChanges").as_array().and_then(|changes| changes.last());
+
+        if let (Some(uri), Some(text)) = (uri, change.and_then(|change| change.get("text").as_str())) {
+          self.check(uri, text)
+        }
+
+        return true
+      },
+
+      "textDocument/didClose" => {
+        if let Some(uri) = params.get("textDocument").get("uri").as_str() {
+          self.documents.remove(uri);
+          self.publish(uri, &Analysis::default())
+        }
+
+        return true
+      },
+
+      "textDocument/hover"      => self.hover(params),
+      "textDocument/definition" => self.definition(params),
+      "textDocument/completion" => self.completion(params),
+
+      // notifications nobody asked for are fine to ignore, requests get an answer either way
+      method => {
+        if !id.is_null() {
+          self.send(
+            Json::object(vec!(
+              ("jsonrpc", Json::string("2.0")),
+              ("id",      id.clone()),
+              ("error",   Self::error(-32601, &format!("unknown method `{}`", method))),
+            ))
+          )
+        }
+
+        return true
+      },
+    };
+
+    if !id.is_null() {
+      self.send(
+        Json::object(vec!(
+          ("jsonrpc", Json::string("2.0")),
+          ("id",      id.clone()),
+          ("result",  result),
+        ))
+      )
+    }
+
+    true
+  }
+
+
+
+  // the checker reports what's wrong rather than panicking, if it still does that's a bug in it,
+  // which costs the document its analysis and goes to the editor's log, not the editor its server
+  fn check(&mut self, uri: &str, text: &str) {
+    let path = Self::path(uri);
+
+    let analysis = panic::catch_unwind(|| Analysis::new(&path, text)).unwrap_or_else(|panic| {
+      let why = panic.downcast_ref::<&str>().map(|why| why.to_string()).or_else(|| panic.downcast_ref::<String>().cloned());
+
+      eprintln!("tang-lsp: checking {} panicked: {}", path, why.unwrap_or_else(|| "no message".to_string()));
+
+      take_diagnostics();
+
+      Analysis {
+        diagnostics: vec!(diagnostic!("E0901" => Wrong("the checker crashed on this file, which is a bug in tang"), FilePath(path.clone()))),
+        ..Analysis::default()
+      }
+    });
+
+    self.publish(uri, &analysis);
+    self.documents.insert(uri.to_string(), analysis);
+  }
+
+  fn publish(&mut self, uri: &str, analysis: &Analysis) {
+    let diagnostics = analysis.diagnostics.iter().map(|diagnostic| Self::diagnostic(analysis, uri, diagnostic)).collect();
+
+    self.send(
+      Json::object(vec!(
+        ("jsonrpc", Json::string("2.0")),
+        ("method",  Json::string("textDocument/publishDiagnostics")),
+        ("params",  Json::object(vec!(
+          ("uri",         Json::string(uri)),
+          ("diagnostics", Json::Array(diagnostics)),
+        ))),
+      ))
+    )
+  }
+
+
+
+  fn hover(&self, params: &Json) -> Json {
+    match self.symbol(params) {
+      Some((analysis, symbol)) => Json::object(vec!(
+        ("contents", Json::object(vec!(
+          ("kind",  Json::string("markdown")),
+          ("value", Json::string(format!("```wu\n{}: {}\n```", symbol.name, symbol.kind))),
+        ))),
+
+        ("range", Self::site_range(analysis, symbol.pos)),
+      )),
+
+      None => Json::Null,
+    }
+  }
+
+  fn definition(&self, params: &Json) -> Json {
+    match self.symbol(params).and_then(|(analysis, symbol)| Some((analysis, symbol.site?))) {
+      Some((analysis, site)) => Json::object(vec!(
+        ("uri",   params.get("textDocument").get("uri").clone()),
+        ("range", Self::site_range(analysis, site)),
+      )),
+
+      None => Json::Null,
+    }
+  }
+
+  fn completion(&self, params: &Json) -> Json {
+    let (analysis, line, column) = match self.cursor(params) {
+      Some(cursor) => cursor,
+      None         => return Json::Null,
+    };
+
+    let items = analysis.names_at(line, column).into_iter().map(|name| {
+      let kind = analysis.kind_of(&name).map(|kind| kind.to_string());
+
+      // completion item kinds, 3 is a function and 6 a variable
+      let item_kind = if kind.as_ref().is_some_and(|kind| kind.starts_with('(')) { 3.0 } else { 6.0 };
+
+      Json::object(vec!(
+        ("label",  Json::string(&name)),
+        ("kind",   Json::Number(item_kind)),
+        ("detail", kind.map(Json::Str).unwrap_or(Json::Null)),
+      ))
+    }).collect();
+
+    Json::Array(items)
+  }
+
+
+
+  // the document and the 1-based line and column a request points at
+  fn cursor(&self, params: &Json) -> Option<(&Analysis, usize, usize)> {
+    let analysis = self.documents.get(params.get("textDocument").get("uri").as_str()?)?;
+    let position = params.get("position");
+    let line     = position.get("line").as_usize()? + 1;
+
+    Some((analysis, line, analysis.column(line, position.get("character").as_usize()?) + 1))
+  }
+
+  fn symbol(&self, params: &Json) -> Option<(&Analysis, &Symbol)> {
+    let (analysis, line, column) = self.cursor(params)?;
+
+    analysis.symbol_at(line, column).map(|symbol| (analysis, symbol))
+  }
+
+
+
+  fn diagnostic(analysis: &Analysis, uri: &str, diagnostic: &Diagnostic) -> Json {
+    let severity = match diagnostic.severity {
+      Severity::Wrong => 1.0,
+      Severity::Weird => 2.0,
+      Severity::Note  => 3.0,
+    };
+
+    let mut message = diagnostic.message.clone();
+
+    for help in &diagnostic.help {
+      message.push_str(&format!("\nhelp: {}", help))
+    }
+
+    let related = diagnostic.labels.iter().map(|label|
+      Json::object(vec!(
+        ("location", Json::object(vec!(
+          ("uri",   Json::string(uri)),
+          ("range", Self::span_range(analysis, Some(&label.span))),
+        ))),
+
+        ("message", Json::string(&label.message)),
+      ))
+    ).collect();
+
+    Json::object(vec!(
+      ("range",              Self::span_range(analysis, diagnostic.span.as_ref())),
+      ("severity",           Json::Number(severity)),
+      ("code",               diagnostic.code.map(Json::string).unwrap_or(Json::Null)),
+      ("source",             Json::string("tang")),
+      ("message",            Json::string(message)),
+      ("relatedInformation", Json::Array(related)),
+    ))
+  }
+
+  fn error(code: i32, message: &str) -> Json {
+    Json::object(vec!(
+      ("code",    Json::Number(f64::from(code))),
+      ("message", Json::string(message)),
+    ))
+  }
+
+  // positions in the protocol start at 0, count characters in UTF-16 code units and ranges end after their last character
+  fn position(analysis: &Analysis, line: usize, character: usize) -> Json {
+    Json::object(vec!(
+      ("line",      Json::Number(line.saturating_sub(1) as f64)),
+      ("character", Json::Number(analysis.utf16(line, character) as f64)),
+    ))
+  }
+
+  fn span_range(analysis: &Analysis, span: Option<&Span>) -> Json {
+    let (start, end) = match span {
+      Some(span) => {
+        let (first, last) = span.clamped();
+
+        // nothing to cover, like the end of a line, is an empty range
+        let last = if span.is_multiline() { last } else { last.max(first - 1) };
+
+        (Self::position(analysis, span.start.0, first - 1), Self::position(analysis, span.end.0, last))
+      },
+
+      None => (Self::position(analysis, 1, 0), Self::position(analysis, 1, 0)),
+    };
+
+    Json::object(vec!(("start", start), ("end", end)))
+  }
+
+  fn site_range(analysis: &Analysis, site: Site) -> Json {
+    let (line, (first, last)) = site;
+
+    Json::object(vec!(
+      ("start", Self::position(analysis, line, first.saturating_sub(1))),
+      ("end",   Self::position(analysis, line, last)),
+    ))
+  }
+
+
+
+  // `file:///home/wu/main.wu` is `/home/wu/main.wu`, imports are found next to it
+  fn path(uri: &str) -> String {
+    let path  = uri.strip_prefix("file://").unwrap_or(uri).as_bytes();
+    let mut bytes = Vec::new();
+    let mut index = 0;
+
+    while index < path.len() {
+      let escaped = if path[index] == b'%' {
+        path.get(index + 1 .. index + 3).and_then(|hex| u8::from_str_radix(&String::from_utf8_lossy(hex), 16).ok())
+      } else {
+        None
+      };
+
+      match escaped {
+        Some(byte) => {
+          bytes.push(byte);
+          index += 3
+        },
+
+        None => {
+          bytes.push(path[index]);
+          index += 1
+        },
+      }
+    }
+
+    String::from_utf8_lossy(&bytes).into_owned()
+  }
+
+
+
+  // messages are framed by a `Content-Length` header and a blank line, only the input ending ends the session
+  fn receive(&mut self) -> Option<String> {
+    loop {
+      let mut length = None;
+
+      loop {
+        let mut header = String::new();
+
+        if self.input.read_line(&mut header).ok()? == 0 {
+          return None
+        }
+
+        let header = header.trim();
+
+        if header.is_empty() {
+          break
+        }
+
+        // the header may follow the content of a skipped message on the same line
+        if let Some(at) = header.find("Content-Length:") {
+          length = header[at + "Content-Length:".len() ..].trim().parse::<usize>().ok()
+        }
+      }
+
+      // without a length there's no telling where the content ends, it's read as headers up to the next one
+      let length = match length {
+        Some(length) => length,
+        None         => {
+          eprintln!("tang-lsp: skipped a message without a `Content-Length`");
+
+          continue
+        },
+      };
+
+      let mut content = vec!(0; length);
+
+      self.input.read_exact(&mut content).ok()?;
+
+      // content that isn't UTF-8 doesn't parse, which is answered as such
+      return Some(String::from_utf8_lossy(&content).into_owned())
+    }
+  }
+
+  fn send(&mut self, message: Json) {
+    let content = message.to_string();
+
+    // the client going away ends the session through `receive`
+    let _ = write!(self.output, "Content-Length: {}\r\n\r\n{}", content.len(), content).and_then(|_| self.output.flush());
+  }
+}
@@ -4,4 +4,7 @@ pub mod source;
 pub mod lexer;
 pub mod parser;
 pub mod visitor;
-pub mod compiler;
\ No newline at end of file
+pub mod ir;
+pub mod compiler;
+pub mod formatter;
+pub mod lsp;
\ No newline at end of file
@@ -6,9 +6,12 @@ use super::*;
 #[derive(Debug, Clone, PartialEq)]
 pub enum StatementNode<'s> {
   Expression(Expression<'s>),
-  Variable(Type<'s>, String, Option<Expression<'s>>),
+  Variable(Type<'s>, String, Option<Expression<'s>>, TokenElement<'s>), // with where the name is
   Assignment(Expression<'s>, Expression<'s>),
   Return(Option<Rc<Expression<'s>>>),
+  Import(Vec<String>),
+  Extern(Vec<String>, Type<'s>, Option<Vec<String>>), // with the parameter names of `extern f: def(..)`
+  Error, // a statement that didn't parse, reported already
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -30,7 +33,7 @@ impl<'s> Statement<'s> {
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum ExpressionNode<'e> {
-  Int(u64),
+  Int(i64),
   Float(f64),
   Str(String),
   Char(char),
@@ -38,15 +41,23 @@ pub enum ExpressionNode<'e> {
   Unwrap(Rc<Expression<'e>>),
   Identifier(String),
   Binary(Rc<Expression<'e>>, Operator, Rc<Expression<'e>>),
+  Unary(Operator, Rc<Expression<'e>>),
   Block(Vec<Statement<'e>>),
   Cast(Rc<Expression<'e>>, Type<'e>),
   Array(Vec<Expression<'e>>),
   Index(Rc<Expression<'e>>, Rc<Expression<'e>>),
-  Function(Vec<(String, Type<'e>)>, Type<'e>, Rc<Expression<'e>>, Option<Vec<String>>),
-  Call(Rc<Expression<'e>>, Vec<Expression<'e>>),
+  Function(Vec<(String, Type<'e>, TokenElement<'e>)>, Type<'e>, Rc<Expression<'e>>, Option<Vec<String>>), // parameters with where their names are
+  Call(Rc<Expression<'e>>, Vec<Expression<'e>>, Vec<Type<'e>>), // called, arguments, explicit type arguments
   If(Rc<Expression<'e>>, Rc<Expression<'e>>, Option<Vec<(Option<Expression<'e>>, Expression<'e>, TokenElement<'e>)>>),
+  While(Rc<Expression<'e>>, Rc<Expression<'e>>),
+  Struct(String, Vec<(String, Type<'e>, Option<Expression<'e>>)>),
+  Construct(Rc<Expression<'e>>, Vec<(Option<String>, Expression<'e>)>),
+  Access(Rc<Expression<'e>>, String),
+  Else(Rc<Expression<'e>>, Rc<Expression<'e>>), // optional value or a default, `a else 0`
+  Nil,
   EOF,
   Empty,
+  Error, // an expression that didn't parse, reported already
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -68,7 +79,7 @@ impl<'e> Expression<'e> {
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Operator {
-  Add, Sub, Mul, Div, Mod, Pow, Concat, Eq, Lt, Gt, NEq, LtEq, GtEq,
+  Add, Sub, Mul, Div, Mod, Pow, Concat, Eq, Lt, Gt, NEq, LtEq, GtEq, And, Or, Not, Neg,
 }
 
 impl Operator {
@@ -76,25 +87,41 @@ impl Operator {
     use self::Operator::*;
 
     let op_prec = match operator {
-      "==" => (Eq,     1),
-      "<"  => (Lt,     1),
-      ">"  => (Gt,     1),
-      "!=" => (NEq,    1),
-      "<=" => (LtEq,   1),
-      ">=" => (GtEq,   1),
-      "+"  => (Add,    2),
-      "-"  => (Sub,    2),
-      "++" => (Concat, 2),
-      "*"  => (Mul,    3),
-      "/"  => (Div,    3),
-      "%"  => (Mod,    3),
-      "^"  => (Pow,    4),
-      _    => return None,
+      "or"  => (Or,     1),
+      "and" => (And,    2),
+      "=="  => (Eq,     3),
+      "<"   => (Lt,     3),
+      ">"   => (Gt,     3),
+      "!="  => (NEq,    3),
+      "<="  => (LtEq,   3),
+      ">="  => (GtEq,   3),
+      "+"   => (Add,    4),
+      "-"   => (Sub,    4),
+      "++"  => (Concat, 4),
+      "*"   => (Mul,    5),
+      "/"   => (Div,    5),
+      "%"   => (Mod,    5),
+      "^"   => (Pow,    6),
+      _     => return None,
     };
 
     Some(op_prec)
   }
 
+  pub fn from_prefix(operator: &str) -> Option<Operator> {
+    use self::Operator::*;
+
+    match operator {
+      "-"   => Some(Neg),
+      "not" => Some(Not),
+      _     => None,
+    }
+  }
+
+  pub fn is_right_associative(&self) -> bool {
+    *self == Operator::Pow
+  }
+
   pub fn as_str(&self) -> &str {
     use self::Operator::*;
     
@@ -112,6 +139,10 @@ impl Operator {
       NEq    => "!=",
       LtEq   => "<=",
       GtEq   => ">=",
+      And    => "and",
+      Or     => "or",
+      Not    => "not",
+      Neg    => "-",
     }
   }
 }
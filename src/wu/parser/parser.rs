@@ -1,5 +1,6 @@
 use super::*;
 use super::super::error::Response::Wrong;
+use super::super::error::reject;
 
 use std::rc::Rc;
 
@@ -7,6 +8,9 @@ pub struct Parser<'p> {
   index:  usize,
   tokens: Vec<&'p Token<'p>>,
   source: &'p Source,
+
+  no_construct: bool, // `if a {` must not parse as constructing `a`
+  failed:       bool, // a statement was skipped after an error
 }
 
 impl<'p> Parser<'p> {
@@ -15,19 +19,98 @@ impl<'p> Parser<'p> {
       tokens,
       source,
       index: 0,
+
+      no_construct: false,
+      failed:       false,
     }
   }
 
 
 
-  pub fn parse(&mut self) -> Result<Vec<Statement<'p>>, ()> {
+  // keeps going after a broken statement, it stays in the tree as an error node so the rest can still be checked
+  pub fn parse(&mut self) -> Vec<Statement<'p>> {
     let mut ast = Vec::new();
 
     while self.remaining() > 0 {
-      ast.push(self.parse_statement()?)
+      ast.push(self.parse_recovering())
+    }
+
+    ast
+  }
+
+
+
+  // whether anything was reported, a tree with error nodes in it is only good for checking
+  pub fn failed(&self) -> bool {
+    self.failed
+  }
+
+
+
+  fn parse_recovering(&mut self) -> Statement<'p> {
+    let start = self.index;
+
+    match self.parse_statement() {
+      Ok(statement) => statement,
+      Err(_)        => {
+        let position = self.current_position();
+
+        self.recover("\n");
+
+        // a broken declaration still declares its name, uses of it aren't reported again
+        match self.declared_name(start) {
+          Some((name, site)) => Statement::new(
+            StatementNode::Variable(
+              Type::from(TypeNode::Nil),
+              name,
+              Some(Expression::new(ExpressionNode::Error, position.clone())),
+              site
+            ),
+            position
+          ),
+
+          None => Statement::new(StatementNode::Error, position),
+        }
+      },
     }
+  }
+
+
 
-    Ok(ast)
+  // `name:` at the start of a statement
+  fn declared_name(&self, start: usize) -> Option<(String, TokenElement<'p>)> {
+    let mut tokens = self.tokens[start.min(self.tokens.len()) ..].iter().skip_while(|token| token.token_type == TokenType::EOL);
+
+    match (tokens.next(), tokens.next()) {
+      (Some(name), Some(colon)) if name.token_type == TokenType::Identifier && colon.lexeme == ":" => Some((name.lexeme.clone(), TokenElement::Pos(name.line, name.slice))),
+      _                                                                                         => None,
+    }
+  }
+
+
+
+  // skips what's left of something broken, up to and over `stop` outside of any brackets
+  fn recover(&mut self, stop: &str) {
+    self.failed = true;
+
+    let mut depth = 0;
+
+    while self.remaining() > 0 {
+      match self.current_lexeme().as_str() {
+        "(" | "[" | "{" => depth += 1,
+        ")" | "]" | "}" => depth = if depth > 0 { depth - 1 } else { 0 },
+
+        lexeme if lexeme == stop && depth == 0 => {
+          self.index += 1;
+
+          return
+        },
+
+        _ => (),
+      }
+
+      self.index += 1
+    }
   }
 
 
@@ -59,6 +142,57 @@ impl<'p> Parser<'p> {
           }
         },
 
+        "extern" => {
+          let position = self.current_position();
+
+          self.next()?;
+
+          let mut path = vec!(self.eat_type(&Identifier)?);
+
+          while self.current_lexeme() == "." {
+            self.next()?;
+
+            path.push(self.eat_type(&Identifier)?)
+          }
+
+          self.eat_lexeme(":")?;
+
+          let (kind, names) = if self.current_lexeme() == "def" {
+            self.next()?;
+
+            let (generics, params, retty) = self.parse_signature()?;
+            let (names, params)           = params.into_iter().map(|(name, kind, _)| (name, kind)).unzip();
+
+            (Type::from(TypeNode::Func(params, Rc::new(retty), generics, None)), Some(names))
+          } else {
+            (self.parse_type()?, None)
+          };
+
+          Statement::new(
+            StatementNode::Extern(path, kind, names),
+            self.span_from(position)
+          )
+        },
+
+        "import" => {
+          let position = self.current_position();
+
+          self.next()?;
+
+          let mut path = vec!(self.eat_type(&Identifier)?);
+
+          while self.remaining() > 0 && self.current_lexeme() == "." {
+            self.next()?;
+
+            path.push(self.eat_type(&Identifier)?)
+          }
+
+          Statement::new(
+            StatementNode::Import(path),
+            self.span_from(position)
+          )
+        },
+
         _ => {
           let expression = self.parse_expression()?;
 
@@ -80,15 +214,32 @@ impl<'p> Parser<'p> {
           ":" => {
             self.next()?;
 
+            let site     = position;
             let position = self.current_position();
             let backup   = self.index;
 
-            if let Some(right) = self.parse_right_hand()? {
+            // `name :: value` and `name: type : value` are constants
+            if self.current_lexeme() == ":" {
+              self.next()?;
+
+              let right = self.parse_constant(&name)?;
+
+              Statement::new(
+                StatementNode::Variable(
+                  Type::new(TypeNode::Nil, TypeMode::Immutable),
+                  name,
+                  Some(right),
+                  site
+                ),
+                self.span_from(position)
+              )
+            } else if let Some(right) = self.parse_right_hand()? {
               Statement::new(
                 StatementNode::Variable(
                   Type::from(TypeNode::Nil),
                   name,
-                  Some(right)
+                  Some(right),
+                  site
                 ),
                 self.span_from(position)
               )
@@ -101,14 +252,38 @@ impl<'p> Parser<'p> {
                 self.parse_type()?
               };
 
-              if self.current_lexeme() == "=" {
+              if self.remaining() > 0 && self.current_lexeme() == ":" {
+                self.next()?;
+
+                let right = self.parse_constant(&name)?;
+
+                Statement::new(
+                  StatementNode::Variable(
+                    Type::new(kind.node, TypeMode::Immutable),
+                    name,
+                    Some(right),
+                    site
+                  ),
+                  self.span_from(position)
+                )
+              } else if self.current_lexeme() == "=" {
                 self.next()?;
 
+                let right = if kind.node == TypeNode::Nil && self.current_lexeme() == "{" {
+                  match self.parse_struct(&name)? {
+                    Some(declaration) => declaration,
+                    None              => self.parse_expression()?,
+                  }
+                } else {
+                  self.parse_expression()?
+                };
+
                 Statement::new(
                   StatementNode::Variable(
                     kind,
                     name,
-                    Some(self.parse_expression()?)
+                    Some(right),
+                    site
                   ),
                   self.span_from(position)
                 )
@@ -117,7 +292,8 @@ impl<'p> Parser<'p> {
                   StatementNode::Variable(
                     kind,
                     name,
-                    None
+                    None,
+                    site
                   ),
                   self.span_from(position)
                 )
@@ -148,10 +324,19 @@ impl<'p> Parser<'p> {
 
             let position = expression.pos.clone();
 
-            Statement::new(
-              StatementNode::Expression(expression),
-              position,
-            )
+            if self.current_lexeme() == "=" {
+              self.next()?;
+
+              Statement::new(
+                StatementNode::Assignment(expression, self.parse_expression()?),
+                position
+              )
+            } else {
+              Statement::new(
+                StatementNode::Expression(expression),
+                position,
+              )
+            }
           },
         }
       },
@@ -187,42 +372,23 @@ impl<'p> Parser<'p> {
         let mut position = self.current_position();
 
         self.next()?;
-        self.next_newline()?;
 
-        let generics = if self.current_lexeme() == "<" {
-          self.parse_block_of(("<", ">"), &Self::_parse_name)?
-        } else {
-          Vec::new()
-        };
+        let (generics, params, retty) = self.parse_signature()?;
 
         self.next_newline()?;
 
-        let params = if self.current_lexeme() == "(" {
-          self.parse_block_of(("(", ")"), &Self::_parse_param_comma)?
-        } else {
-          Vec::new()
-        };
-
-        let retty = if self.current_lexeme() == "->" {
-          self.next()?;
+        self.expect_lexeme("{")?;
 
-          self.parse_type()?
-        } else {
-          Type::from(TypeNode::Nil)
-        };
+        let body = Rc::new(self.parse_expression()?);
 
         position = self.span_from(position);
 
-        self.next_newline()?;
-
-        self.expect_lexeme("{")?;
-
         Some(
           Expression::new(
             ExpressionNode::Function(
               params,
               retty,
-              Rc::new(self.parse_expression()?),
+              body,
               Some(generics)
             ),
 
@@ -238,8 +404,114 @@ impl<'p> Parser<'p> {
 
 
 
+  // `<T>(a: T) -> T`, what follows `def`
+  fn parse_signature(&mut self) -> Result<(Vec<String>, Vec<(String, Type<'p>, TokenElement<'p>)>, Type<'p>), ()> {
+    self.next_newline()?;
+
+    let generics = if self.current_lexeme() == "<" {
+      self.parse_block_of(("<", ">"), &Self::_parse_name)?
+    } else {
+      Vec::new()
+    };
+
+    self.next_newline()?;
+
+    let params = if self.current_lexeme() == "(" {
+      self.parse_block_of(("(", ")"), &Self::_parse_param_comma)?
+    } else {
+      Vec::new()
+    };
+
+    let retty = if self.current_lexeme() == "->" {
+      self.next()?;
+
+      self.parse_type()?
+    } else {
+      Type::from(TypeNode::Nil)
+    };
+
+    Ok((generics, params, retty))
+  }
+
+
+
+  fn parse_constant(&mut self, name: &str) -> Result<Expression<'p>, ()> {
+    if let Some(right) = self.parse_right_hand()? {
+      return Ok(right)
+    }
+
+    if self.current_lexeme() == "{" {
+      if let Some(declaration) = self.parse_struct(name)? {
+        return Ok(declaration)
+      }
+    }
+
+    self.parse_expression()
+  }
+
+
+
+  // `{ x: float = 100 }` is a struct declaration when every statement is a typed field,
+  // anything else is left to be parsed as a block
+  fn parse_struct(&mut self, name: &str) -> Result<Option<Expression<'p>>, ()> {
+    let position = self.current_position();
+    let backup   = self.index;
+
+    let mut fields = Vec::new();
+
+    for statement in self.parse_block_of(("{", "}"), &Self::_parse_statement)? {
+      match statement.node {
+        StatementNode::Variable(kind, field, value, _) => if kind.node != TypeNode::Nil {
+          fields.push((field, kind, value))
+        } else {
+          self.index = backup;
+
+          return Ok(None)
+        },
+
+        _ => {
+          self.index = backup;
+
+          return Ok(None)
+        }
+      }
+    }
+
+    if fields.is_empty() {
+      self.index = backup;
+
+      return Ok(None)
+    }
+
+    Ok(
+      Some(
+        Expression::new(
+          ExpressionNode::Struct(name.to_string(), fields),
+          position
+        )
+      )
+    )
+  }
+
+
+
+  // conditions are followed by a block, which would otherwise read as a struct construction
+  fn parse_condition(&mut self) -> Result<Expression<'p>, ()> {
+    let no_construct = self.no_construct;
+
+    self.no_construct = true;
+
+    let condition = self.parse_expression();
+
+    self.no_construct = no_construct;
+
+    condition
+  }
+
+
+
   fn parse_expression(&mut self) -> Result<Expression<'p>, ()> {
-    let atom = self.parse_atom()?;
+    let atom = self.parse_unary()?;
 
     if self.current_type() == &TokenType::Operator {
       self.parse_binary(atom)
@@ -250,6 +522,71 @@ impl<'p> Parser<'p> {
 
 
 
+  // Prefix operators bind tighter than every binary operator but `^`,
+  // so `-a ^ 2` is `-(a ^ 2)` while `2 ^ -a` is still allowed, like in Lua
+  fn parse_unary(&mut self) -> Result<Expression<'p>, ()> {
+    if self.remaining() == 0 || self.current_type() != &TokenType::Operator {
+      return self.parse_atom()
+    }
+
+    let position = self.current_position();
+
+    if self.current_lexeme() == "*" {
+      self.next()?;
+
+      let operand = self.parse_unary()?;
+
+      return Ok(
+        Expression::new(
+          ExpressionNode::Unwrap(Rc::new(operand)),
+          self.span_from(position)
+        )
+      )
+    }
+
+    match Operator::from_prefix(&self.current_lexeme()) {
+      Some(operator) => {
+        self.next()?;
+
+        let operand = self.parse_unary()?;
+        let operand = self.parse_power(operand)?;
+
+        Ok(
+          Expression::new(
+            ExpressionNode::Unary(operator, Rc::new(operand)),
+            self.span_from(position)
+          )
+        )
+      },
+
+      None => self.parse_atom()
+    }
+  }
+
+
+
+  fn parse_power(&mut self, base: Expression<'p>) -> Result<Expression<'p>, ()> {
+    if self.current_lexeme() != "^" {
+      return Ok(base)
+    }
+
+    self.next()?;
+
+    let exponent = self.parse_unary()?;
+    let exponent = self.parse_power(exponent)?;
+
+    let position = base.pos.clone();
+
+    Ok(
+      Expression::new(
+        ExpressionNode::Binary(Rc::new(base), Operator::Pow, Rc::new(exponent)),
+        self.span_from(position)
+      )
+    )
+  }
+
+
+
   fn parse_atom(&mut self) -> Result<Expression<'p>, ()> {
     use self::TokenType::*;
 
@@ -265,10 +602,21 @@ impl<'p> Parser<'p> {
       let position   = self.current_position();
 
       let expression = match token_type {
-        Int => Expression::new(
-          ExpressionNode::Int(self.eat()?.parse::<u64>().unwrap()),
-          position
-        ),
+        Int => {
+          let literal = self.eat()?;
+
+          match literal.parse::<i64>() {
+            Ok(n) => Expression::new(ExpressionNode::Int(n), position),
+
+            Err(_) => return reject(
+              diagnostic!(
+                "E0207" => Wrong(format!("integer `{}` is too large, an `int` goes up to {}", literal, i64::MAX)),
+                self.source.file,
+                position
+              ).help(format!("write it as a float, `{}.0`", literal))
+            ),
+          }
+        },
 
         Float => Expression::new(
           ExpressionNode::Float(self.eat()?.parse::<f64>().unwrap()),
@@ -295,32 +643,16 @@ impl<'p> Parser<'p> {
           position
         ),
 
-        Operator => match self.current_lexeme().as_str() {
-          "*" => {
-            self.next()?;
-
-            Expression::new(
-              ExpressionNode::Unwrap(
-                Rc::new(self.parse_expression()?)
-              ),
-
-              self.span_from(position)
-            )
-          },
-
-          ref symbol => return Err(
-            response!(
-              Wrong(format!("unexpected symbol `{}`", symbol)),
-              self.source.file,
-              TokenElement::Ref(self.current())
-            )
-          )
-        },
+        Operator => return fail!(
+          "E0201" => Wrong(format!("unexpected operator `{}`", self.current_lexeme())),
+          self.source.file,
+          TokenElement::Ref(self.current())
+        ),
 
         Symbol => match self.current_lexeme().as_str() {
           "{" => Expression::new(
             ExpressionNode::Block(self.parse_block_of(("{", "}"), &Self::_parse_statement)?),
-            position
+            self.span_from(position)
           ),
 
           "[" => Expression::new(
@@ -340,7 +672,15 @@ impl<'p> Parser<'p> {
                 self.span_from(position)
               )
             } else {
-              let expression = self.parse_expression()?;
+              let no_construct = self.no_construct;
+
+              self.no_construct = false;
+
+              let expression = self.parse_expression();
+
+              self.no_construct = no_construct;
+
+              let expression = expression?;
 
               self.eat_lexeme(")")?;
 
@@ -348,26 +688,33 @@ impl<'p> Parser<'p> {
             }
           },
 
-          ref symbol => return Err(
-            response!(
-              Wrong(format!("unexpected symbol `{}`", symbol)),
-              self.source.file,
-              TokenElement::Ref(self.current())
-            )
+          ref symbol => return fail!(
+            "E0201" => Wrong(format!("unexpected symbol `{}`", symbol)),
+            self.source.file,
+            TokenElement::Ref(self.current())
           )
         },
 
         Keyword => match self.current_lexeme().as_str() {
+          "nil" => {
+            self.next()?;
+
+            Expression::new(
+              ExpressionNode::Nil,
+              position
+            )
+          },
+
           "if" => {
             self.next()?;
 
-            let condition   = Rc::new(self.parse_expression()?);
-            let if_position = self.span_from(position.clone());
+            let condition     = Rc::new(self.parse_condition()?);
+            let body_position = self.current_position();
 
-            let body        = Rc::new(
+            let body          = Rc::new(
               Expression::new(
                 ExpressionNode::Block(self.parse_block_of(("{", "}"), &Self::_parse_statement)?),
-                position
+                self.span_from(body_position)
               )
             );
 
@@ -380,11 +727,11 @@ impl<'p> Parser<'p> {
                 "elif" => {
                   self.next()?;
 
-                  let condition = self.parse_expression()?;
+                  let condition = self.parse_condition()?;
                   let position  = self.current_position();
                   let body      = Expression::new(
                     ExpressionNode::Block(self.parse_block_of(("{", "}"), &Self::_parse_statement)?),
-                    position
+                    self.span_from(position)
                   );
 
                   elses.push((Some(condition), body, branch_position))
@@ -396,7 +743,7 @@ impl<'p> Parser<'p> {
                   let position  = self.current_position();
                   let body      = Expression::new(
                     ExpressionNode::Block(self.parse_block_of(("{", "}"), &Self::_parse_statement)?),
-                    position
+                    self.span_from(position)
                   );
 
                   elses.push((None, body, branch_position))
@@ -407,26 +754,41 @@ impl<'p> Parser<'p> {
             }
 
             Expression::new(
-              ExpressionNode::If(condition, body, if elses.len() > 0 { Some(elses) } else { None }),
-              if_position
+              ExpressionNode::If(condition, body, if !elses.is_empty() { Some(elses) } else { None }),
+              self.span_from(position)
             )
           },
 
-          ref symbol => return Err(
-            response!(
-              Wrong(format!("unexpected keyword `{}`", symbol)),
-              self.source.file,
-              TokenElement::Ref(self.current())
+          "while" => {
+            self.next()?;
+
+            let condition     = Rc::new(self.parse_condition()?);
+            let body_position = self.current_position();
+
+            let body = Rc::new(
+              Expression::new(
+                ExpressionNode::Block(self.parse_block_of(("{", "}"), &Self::_parse_statement)?),
+                self.span_from(body_position)
+              )
+            );
+
+            Expression::new(
+              ExpressionNode::While(condition, body),
+              self.span_from(position)
             )
-          )
-        },
+          },
 
-        ref token_type => return Err(
-          response!(
-            Wrong(format!("unexpected token `{}`", token_type)),
+          ref symbol => return fail!(
+            "E0201" => Wrong(format!("unexpected keyword `{}`", symbol)),
             self.source.file,
             TokenElement::Ref(self.current())
           )
+        },
+
+        ref token_type => return fail!(
+          "E0201" => Wrong(format!("unexpected token `{}`", token_type)),
+          self.source.file,
+          TokenElement::Ref(self.current())
         )
       };
 
@@ -438,6 +800,21 @@ impl<'p> Parser<'p> {
 
   fn parse_postfix(&mut self, expression: Expression<'p>) -> Result<Expression<'p>, ()> {
     match *self.current_type() {
+      // `foo<int>(10)`, told apart from a comparison by what follows the closing `>`
+      TokenType::Operator if self.current_lexeme() == "<" && self.is_type_arguments() => {
+        let types = self.parse_block_of(("<", ">"), &Self::_parse_type_comma)?;
+        let args  = self.parse_block_of(("(", ")"), &Self::_parse_expression_comma)?;
+
+        let position = expression.pos.clone();
+
+        let call = Expression::new(
+          ExpressionNode::Call(Rc::new(expression), args, types),
+          self.span_from(position)
+        );
+
+        self.parse_postfix(call)
+      },
+
       TokenType::Symbol => match self.current_lexeme().as_str() {
         "(" => {
           let args = self.parse_block_of(("(", ")"), &Self::_parse_expression_comma)?;
@@ -445,7 +822,7 @@ impl<'p> Parser<'p> {
           let position = expression.pos.clone();
 
           let call = Expression::new(
-            ExpressionNode::Call(Rc::new(expression), args),
+            ExpressionNode::Call(Rc::new(expression), args, Vec::new()),
             self.span_from(position)
           );
 
@@ -469,6 +846,34 @@ impl<'p> Parser<'p> {
           self.parse_postfix(index)
         },
 
+        "." => {
+          self.next()?;
+
+          let field    = self.eat_type(&TokenType::Identifier)?;
+          let position = expression.pos.clone();
+
+          let access = Expression::new(
+            ExpressionNode::Access(Rc::new(expression), field),
+            self.span_from(position)
+          );
+
+          self.parse_postfix(access)
+        },
+
+        "{" if !self.no_construct => if let ExpressionNode::Identifier(_) | ExpressionNode::Access(..) = expression.node {
+          let fields   = self.parse_block_of(("{", "}"), &Self::_parse_field_comma)?;
+          let position = expression.pos.clone();
+
+          let construct = Expression::new(
+            ExpressionNode::Construct(Rc::new(expression), fields),
+            self.span_from(position)
+          );
+
+          self.parse_postfix(construct)
+        } else {
+          Ok(expression)
+        },
+
         _ => Ok(expression)
       },
 
@@ -487,6 +892,21 @@ impl<'p> Parser<'p> {
           )
         },
 
+        "else" => {
+          self.next()?;
+          self.next_newline()?;
+
+          let default  = self.parse_unary()?;
+          let position = expression.pos.clone();
+
+          let expression = Expression::new(
+            ExpressionNode::Else(Rc::new(expression), Rc::new(default)),
+            self.span_from(position)
+          );
+
+          self.parse_postfix(expression)
+        },
+
         _ => Ok(expression)
       },
 
@@ -496,59 +916,79 @@ impl<'p> Parser<'p> {
 
 
 
+  // looks ahead from a `<` for a list of types closed by `>` and directly followed by `(`
+  fn is_type_arguments(&self) -> bool {
+    let mut depth = 0;
+
+    for (offset, token) in self.tokens[self.index ..].iter().enumerate() {
+      match (&token.token_type, token.lexeme.as_str()) {
+        (&TokenType::Operator, "<") => depth += 1,
+        (&TokenType::Operator, ">") => {
+          depth -= 1;
+
+          if depth == 0 {
+            return self.tokens.get(self.index + offset + 1).is_some_and(|next| next.lexeme == "(")
+          }
+        },
+
+        (&TokenType::Identifier, _) | (&TokenType::Int, _) | (&TokenType::EOL, _) | (&TokenType::Keyword, "->") => (),
+
+        (&TokenType::Symbol, symbol) => if ![",", ".", ";", "[", "]", "(", ")"].contains(&symbol) {
+          return false
+        },
+
+        _ => return false,
+      }
+    }
+
+    false
+  }
+
+
+
   // A simple shunting-yard implementation
-  // ... naively hoping for unary operations to just play along
   fn parse_binary(&mut self, left: Expression<'p>) -> Result<Expression<'p>, ()> {
     let left_position = left.pos.clone();
 
     let mut expression_stack = vec!(left);
-    let mut operator_stack   = vec!(Operator::from_str(&self.eat()?).unwrap());
+    let mut operator_stack: Vec<(Operator, u8)> = Vec::new();
 
-    expression_stack.push(self.parse_atom()?);
+    while self.current_type() == &TokenType::Operator {
+      let position = self.current_position();
 
-    while operator_stack.len() > 0 {
-      while self.current_type() == &TokenType::Operator {
-        let position               = self.current_position();
-        let (operator, precedence) = Operator::from_str(&self.eat()?).unwrap();
-
-        if precedence < operator_stack.last().unwrap().1 {
-          let right = expression_stack.pop().unwrap();
-          let left  = expression_stack.pop().unwrap();
+      let (operator, precedence) = match Operator::from_str(&self.current_lexeme()) {
+        Some(op) => op,
+        None     => return fail!(
+          "E0201" => Wrong(format!("unexpected operator `{}`", self.current_lexeme())),
+          self.source.file,
+          position
+        )
+      };
 
-          expression_stack.push(
-            Expression::new(
-              ExpressionNode::Binary(Rc::new(left), operator_stack.pop().unwrap().0, Rc::new(right)),
-              self.current_position(),
-            )
-          );
+      self.next()?;
 
-          if self.remaining() > 0 {
-            expression_stack.push(self.parse_atom()?);
-            operator_stack.push((operator, precedence))
-          } else {
-            return Err(
-              response!(
-                Wrong("reached EOF in operation"),
-                self.source.file,
-                position
-              )
-            )
-          }
+      while let Some(&(_, top)) = operator_stack.last() {
+        if top > precedence || (top == precedence && !operator.is_right_associative()) {
+          Self::reduce_binary(&mut expression_stack, &mut operator_stack)
         } else {
-          expression_stack.push(self.parse_atom()?);
-          operator_stack.push((operator, precedence))
+          break
         }
       }
 
-      let right = expression_stack.pop().unwrap();
-      let left  = expression_stack.pop().unwrap();
-
-      expression_stack.push(
-        Expression::new(
-          ExpressionNode::Binary(Rc::new(left), operator_stack.pop().unwrap().0, Rc::new(right)),
-          self.current_position(),
+      if self.remaining() == 0 {
+        return fail!(
+          "E0202" => Wrong("reached EOF in operation"),
+          self.source.file,
+          position
         )
-      );
+      }
+
+      expression_stack.push(self.parse_unary()?);
+      operator_stack.push((operator, precedence))
+    }
+
+    while !operator_stack.is_empty() {
+      Self::reduce_binary(&mut expression_stack, &mut operator_stack)
     }
 
     let expression = expression_stack.pop().unwrap();
@@ -561,6 +1001,20 @@ impl<'p> Parser<'p> {
     )
   }
 
+  fn reduce_binary(expression_stack: &mut Vec<Expression<'p>>, operator_stack: &mut Vec<(Operator, u8)>) {
+    let right = expression_stack.pop().unwrap();
+    let left  = expression_stack.pop().unwrap();
+
+    let position = Self::join(&left.pos, &right.pos);
+
+    expression_stack.push(
+      Expression::new(
+        ExpressionNode::Binary(Rc::new(left), operator_stack.pop().unwrap().0, Rc::new(right)),
+        position,
+      )
+    )
+  }
+
 
 
   fn parse_type(&mut self) -> Result<Type<'p>, ()> {
@@ -575,7 +1029,27 @@ impl<'p> Parser<'p> {
         "float" => Type::from(TypeNode::Float),
 
         "bool"  => Type::from(TypeNode::Bool),
-        id      => Type::id(id),
+
+        "any"   => Type::from(TypeNode::Any),
+
+        id => {
+          let mut id = id.to_string();
+
+          // types from imported modules, `vector.point`
+          while self.remaining() > 0 && self.current_lexeme() == "." {
+            self.next()?;
+
+            id = format!("{}.{}", id, self.eat_type(&Identifier)?)
+          }
+
+          if self.remaining() > 0 && self.current_lexeme() == "<" {
+            let args = self.parse_block_of(("<", ">"), &Self::_parse_type_comma)?;
+
+            Type::from(TypeNode::Generic(id, args))
+          } else {
+            Type::id(&id)
+          }
+        },
       },
 
       Symbol => match self.current_lexeme().as_str() {
@@ -593,15 +1067,21 @@ impl<'p> Parser<'p> {
 
           let expression = self.parse_expression()?;
 
-          let len = if let ExpressionNode::Int(ref len) = Self::fold_expression(&expression)?.node {
-            *len as usize
-          } else {
-            return Err(
-              response!(
-                Wrong(format!("length of array can be nothing but int")),
+          let len = if let ExpressionNode::Int(len) = Self::fold_expression(&expression)?.node {
+            if len < 0 {
+              return fail!(
+                "E0203" => Wrong(format!("length of array can't be negative, got {}", len)),
                 self.source.file,
                 expression.pos
               )
+            }
+
+            len as usize
+          } else {
+            return fail!(
+              "E0203" => Wrong("length of array can be nothing but int".to_string()),
+              self.source.file,
+              expression.pos
             )
           };
 
@@ -620,24 +1100,27 @@ impl<'p> Parser<'p> {
           Type::function(params, return_type)
         },
 
-        _ => return Err(
-          response!(
-            Wrong(format!("unexpected symbol `{}` in type", self.current_lexeme())),
-            self.source.file,
-            self.current_position()
-          )
-        )
-      }
-
-      _ => return Err(
-        response!(
-          Wrong(format!("expected type found `{}`", self.current_lexeme())),
+        _ => return fail!(
+          "E0204" => Wrong(format!("unexpected symbol `{}` in type", self.current_lexeme())),
           self.source.file,
           self.current_position()
         )
+      }
+
+      _ => return fail!(
+        "E0204" => Wrong(format!("expected type found `{}`", self.current_lexeme())),
+        self.source.file,
+        self.current_position()
       )
     };
 
+    // `int?` may be nil
+    if self.remaining() > 0 && self.current_lexeme() == "?" {
+      self.next()?;
+
+      return Ok(Type::new(t.node, TypeMode::Optional))
+    }
+
     Ok(t)
   }
 
@@ -647,12 +1130,10 @@ impl<'p> Parser<'p> {
     if self.remaining() > 0 {
       match self.current_lexeme().as_str() {
         "\n" => self.next(),
-        _    => Err(
-          response!(
-            Wrong(format!("expected new line found: `{}`", self.current_lexeme())),
-            self.source.file,
-            self.current_position()
-          )
+        _    => fail!(
+          "E0205" => Wrong(format!("expected new line found: `{}`", self.current_lexeme())),
+          self.source.file,
+          self.current_position()
         )
       }
     } else {
@@ -677,11 +1158,9 @@ impl<'p> Parser<'p> {
       self.index += 1;
       Ok(())
     } else {
-      Err(
-        response!(
-          Wrong("moving outside token stack"),
-          self.source.file
-        )
+      fail!(
+        "E0901" => Wrong("moving outside token stack"),
+        self.source.file
       )
     }
   }
@@ -701,21 +1180,51 @@ impl<'p> Parser<'p> {
 
   fn span_from(&self, left_position: TokenElement<'p>) -> TokenElement<'p> {
     match left_position {
-      TokenElement::Pos(ref line, ref slice) => if let TokenElement::Pos(_, ref slice2) = self.current_position() {
-        TokenElement::Pos(*line, (slice.0, if slice2.1 < line.1.len() { slice2.1 } else { line.1.len() } ))
-      } else {
-        left_position.clone()
+      TokenElement::Pos(ref line, ref slice) => {
+        let current = self.current();
+
+        if current.line.0 == line.0 {
+          TokenElement::Pos(*line, (slice.0, current.slice.1.min(line.1.chars().count())))
+        } else {
+          // the span ended on an earlier line than the token after it, like a block before its newline
+          let last = &self.tokens[self.index.saturating_sub(1).min(self.tokens.len() - 1)];
+
+          Self::join(&left_position, &TokenElement::Pos(last.line, last.slice))
+        }
       },
 
       _ => left_position.clone(),
     }
   }
 
+  // from the start of `left` to the end of `right`
+  fn join(left: &TokenElement<'p>, right: &TokenElement<'p>) -> TokenElement<'p> {
+    let start = match *left {
+      TokenElement::Pos(line, slice)     => (line, slice.0),
+      TokenElement::Span(line, start, ..) => (line, start),
+      _                                  => return left.clone(),
+    };
+
+    let end = match *right {
+      TokenElement::Pos(line, slice)    => (line, slice.1),
+      TokenElement::Span(.., line, end) => (line, end),
+      _                                 => return left.clone(),
+    };
+
+    if (end.0).0 < (start.0).0 || (end.0).0 == (start.0).0 && end.1 < start.1 {
+      left.clone()
+    } else if (end.0).0 == (start.0).0 {
+      TokenElement::Pos(start.0, (start.1, end.1.min((start.0).1.chars().count())))
+    } else {
+      TokenElement::Span(start.0, start.1, end.0, end.1)
+    }
+  }
+
   fn current(&self) -> &'p Token<'p> {
     if self.index > self.tokens.len() - 1 {
-      &self.tokens[self.tokens.len() - 1]
+      self.tokens[self.tokens.len() - 1]
     } else {
-      &self.tokens[self.index]
+      self.tokens[self.index]
     }
   }
 
@@ -733,12 +1242,10 @@ impl<'p> Parser<'p> {
 
       Ok(lexeme)
     } else {
-      Err(
-        response!(
-          Wrong(format!("expected `{}`, found `{}`", lexeme, self.current_lexeme())),
-          self.source.file,
-          self.current_position()
-        )
+      fail!(
+        "E0206" => Wrong(format!("expected `{}`, found `{}`", lexeme, self.current_lexeme())),
+        self.source.file,
+        self.current_position()
       )
     }
   }
@@ -750,12 +1257,10 @@ impl<'p> Parser<'p> {
 
       Ok(lexeme)
     } else {
-      Err(
-        response!(
-          Wrong(format!("expected `{}`, found `{}`", token_type, self.current_type())),
-          self.source.file,
-          self.current_position()
-        )
+      fail!(
+        "E0206" => Wrong(format!("expected `{}`, found `{}`", token_type, self.current_type())),
+        self.source.file,
+        self.current_position()
       )
     }
   }
@@ -768,28 +1273,13 @@ impl<'p> Parser<'p> {
     &self.current().token_type
   }
 
-  fn expect_type(&self, token_type: TokenType) -> Result<(), ()> {
-    if self.current_type() == &token_type {
-      Ok(())
-    } else {
-      Err(
-        response!(
-          Wrong(format!("expected `{}`, found `{}`", token_type, self.current_type())),
-          self.source.file
-        )
-      )
-    }
-  }
-
   fn expect_lexeme(&self, lexeme: &str) -> Result<(), ()> {
     if self.current_lexeme() == lexeme {
       Ok(())
     } else {
-      Err(
-        response!(
-          Wrong(format!("expected `{}`, found `{}`", lexeme, self.current_lexeme())),
-          self.source.file
-        )
+      fail!(
+        "E0206" => Wrong(format!("expected `{}`, found `{}`", lexeme, self.current_lexeme())),
+        self.source.file
       )
     }
   }
@@ -798,13 +1288,28 @@ impl<'p> Parser<'p> {
 
   // A helper method for parsing sequences defined by provided static methods,
   // for as long as given static method returns Some(B)
-  fn parse_block_of<B>(&mut self, delimeters: (&str, &str), parse_with: &Fn(&mut Self) -> Result<Option<B>, ()>) -> Result<Vec<B>, ()> {
+  fn parse_block_of<B>(&mut self, delimeters: (&str, &str), parse_with: &dyn Fn(&mut Self) -> Result<Option<B>, ()>) -> Result<Vec<B>, ()> {
+    let opening = self.current();
+
     self.eat_lexeme(delimeters.0)?;
 
+    let start = self.index;
+
     let mut block_tokens = Vec::new();
     let mut nest_count   = 1;
 
     while nest_count > 0 {
+      if self.remaining() == 0 {
+        // picks up again right after the opening, to skip no more than the rest of its line
+        self.index = start;
+
+        return fail!(
+          "E0102" => Wrong(format!("unterminated delimeter `{}`", delimeters.0)),
+          self.source.file,
+          TokenElement::Ref(opening)
+        )
+      }
+
       if self.current_lexeme() == delimeters.1 {
         nest_count -= 1
       } else if self.current_lexeme() == delimeters.0 {
@@ -830,6 +1335,9 @@ impl<'p> Parser<'p> {
         block.push(element)
       }
 
+      // whatever was recovered from inside is reported already, the block has error nodes in place of it
+      self.failed |= parser.failed;
+
       Ok(block)
     } else {
       Ok(Vec::new())
@@ -838,9 +1346,9 @@ impl<'p> Parser<'p> {
 
 
 
-  fn _parse_statement(self: &mut Self) -> Result<Option<Statement<'p>>, ()> {
+  fn _parse_statement(&mut self) -> Result<Option<Statement<'p>>, ()> {
     if self.remaining() > 0 {
-      Ok(Some(self.parse_statement()?))
+      Ok(Some(self.parse_recovering()))
     } else {
       Ok(None)
     }
@@ -848,7 +1356,7 @@ impl<'p> Parser<'p> {
 
 
 
-  fn _parse_expression(self: &mut Self) -> Result<Option<Expression<'p>>, ()> {
+  fn _parse_expression(&mut self) -> Result<Option<Expression<'p>>, ()> {
     let expression = self.parse_expression()?;
 
     match expression.node {
@@ -859,7 +1367,7 @@ impl<'p> Parser<'p> {
 
 
 
-  fn _parse_name(self: &mut Self) -> Result<Option<String>, ()> {
+  fn _parse_name(&mut self) -> Result<Option<String>, ()> {
     if self.remaining() == 0 {
       Ok(None)
     } else {
@@ -879,13 +1387,47 @@ impl<'p> Parser<'p> {
 
 
 
+  // a broken element is skipped up to the next comma and stands in as an error node
+  fn element_or_error<B>(&mut self, parse_with: &dyn Fn(&mut Self) -> Result<Option<B>, ()>, error: &dyn Fn(Expression<'p>) -> B) -> Result<Option<B>, ()> {
+    // recovering can't skip past the end, so an unfinished last element would be retried forever
+    if self.remaining() == 0 {
+      return Ok(None)
+    }
+
+    match parse_with(self) {
+      Err(_) => {
+        let position = self.current_position();
+
+        self.recover(",");
+
+        Ok(Some(error(Expression::new(ExpressionNode::Error, position))))
+      },
+
+      element => element,
+    }
+  }
+
+
+
+  fn _parse_expression_comma(&mut self) -> Result<Option<Expression<'p>>, ()> {
+    self.element_or_error(&Self::expression_comma, &|error| error)
+  }
+
+
+
+  fn _parse_field_comma(&mut self) -> Result<Option<(Option<String>, Expression<'p>)>, ()> {
+    self.element_or_error(&Self::field_comma, &|error| (None, error))
+  }
+
+
+
   // Static method for parsing sequence `expr* ,* \n*` - for things like [1, 2, 3, 4,]
-  fn _parse_expression_comma(self: &mut Self) -> Result<Option<Expression<'p>>, ()> {
+  fn expression_comma(&mut self) -> Result<Option<Expression<'p>>, ()> {
     if self.remaining() > 0 && self.current_lexeme() == "\n" {
       self.next()?
     }
 
-    let expression = Self::_parse_expression(self);
+    let expression = Self::_parse_expression(self)?;
 
     if self.remaining() > 0 && self.current_lexeme() == "\n" {
         self.next()?
@@ -899,12 +1441,56 @@ impl<'p> Parser<'p> {
       }
     }
 
-    expression
+    Ok(expression)
+  }
+
+
+
+  // Static method for parsing struct construction fields `(name:)? expr (,|\n)*`
+  fn field_comma(&mut self) -> Result<Option<(Option<String>, Expression<'p>)>, ()> {
+    self.next_newline()?;
+
+    if self.remaining() == 0 {
+      return Ok(None)
+    }
+
+    let name = match self.tokens.get(self.index + 1) {
+      Some(token) if self.current_type() == &TokenType::Identifier && token.lexeme == ":" => {
+        let name = self.eat()?;
+
+        self.next()?;
+        self.next_newline()?;
+
+        Some(name)
+      },
+
+      _ => None
+    };
+
+    let expression = self.parse_expression()?;
+
+    if let (Some(name), ExpressionNode::EOF) = (&name, &expression.node) {
+      return fail!(
+        "E0202" => Wrong(format!("reached EOF before the value of field `{}`", name)),
+        self.source.file,
+        expression.pos
+      )
+    }
+
+    let separated  = self.current_lexeme() == "\n";
+
+    self.next_newline()?;
+
+    if self.remaining() > 0 && (self.current_lexeme() == "," || !separated) {
+      self.eat_lexeme(",")?;
+    }
+
+    Ok(Some((name, expression)))
   }
 
 
 
-  fn _parse_param_comma(self: &mut Self) -> Result<Option<(String, Type<'p>)>, ()> {
+  fn _parse_param_comma(&mut self) -> Result<Option<(String, Type<'p>, TokenElement<'p>)>, ()> {
     if self.remaining() > 0 && self.current_lexeme() == "\n" {
       self.next()?
     }
@@ -922,6 +1508,7 @@ impl<'p> Parser<'p> {
       self.next_newline()?;
     }
 
+    let site = self.current_position();
     let name = self.eat_type(&TokenType::Identifier)?;
     
     self.eat_lexeme(":")?;
@@ -932,7 +1519,7 @@ impl<'p> Parser<'p> {
       kind.mode = TypeMode::Splat(None)
     }
 
-    let param = Some((name, kind));
+    let param = Some((name, kind, site));
 
     if self.remaining() > 0 && self.current_lexeme() == "\n" {
       self.next()?
@@ -951,7 +1538,7 @@ impl<'p> Parser<'p> {
 
 
 
-  fn _parse_type_comma(self: &mut Self) -> Result<Option<Type<'p>>, ()> {
+  fn _parse_type_comma(&mut self) -> Result<Option<Type<'p>>, ()> {
     if self.remaining() == 0 {
       Ok(None)
     } else {
@@ -975,17 +1562,44 @@ impl<'p> Parser<'p> {
     use self::ExpressionNode::*;
     use self::Operator::*;
 
+    // overflowing or dividing by zero is left for runtime
+    let int = |n: Option<i64>| n.map(Int).unwrap_or_else(|| expression.node.clone());
+
+    // and so is what 5.1 and LuaJIT, going through floats, can't work out exactly
+    let exact = |n: i64| (-(1 << 53) ..= 1 << 53).contains(&n);
+
     let node = match expression.node {
       Binary(ref left, ref op, ref right) => {
-        let node = match (&Self::fold_expression(&*left)?.node, op, &Self::fold_expression(&*right)?.node) {
-          (&Int(ref a),   &Add, &Int(ref b))     => Int(a + b),
-          (&Float(ref a), &Add, &Float(ref b)) => Float(a + b),
-          (&Int(ref a),   &Sub, &Int(ref b))     => Int(a - b),
-          (&Float(ref a), &Sub, &Float(ref b)) => Float(a - b),
-          (&Int(ref a),   &Mul, &Int(ref b))     => Int(a * b),
-          (&Float(ref a), &Mul, &Float(ref b)) => Float(a * b),
-          (&Int(ref a),   &Div, &Int(ref b))     => Int(a / b),
-          (&Float(ref a), &Div, &Float(ref b)) => Float(a / b),
+        let node = match (&Self::fold_expression(left)?.node, op, &Self::fold_expression(right)?.node) {
+          (Int(a),   &Add, Int(b))                             => int(a.checked_add(*b)),
+          (Float(a), &Add, Float(b))                           => Float(a + b),
+          (Int(a),   &Sub, Int(b))                             => int(a.checked_sub(*b)),
+          (Float(a), &Sub, Float(b))                           => Float(a - b),
+          (Int(a),   &Mul, Int(b))                             => int(a.checked_mul(*b)),
+          (Float(a), &Mul, Float(b))                           => Float(a * b),
+          (Int(a),   &Div, Int(b))   if exact(*a) && exact(*b) => int(Self::floor_div(*a, *b)),
+          (Float(a), &Div, Float(b))                           => Float(a / b),
+          (Int(a),   &Mod, Int(b))   if exact(*a) && exact(*b) => int(Self::floor_mod(*a, *b)),
+          (Float(a), &Mod, Float(b))                           => Float(a - (a / b).floor() * b),
+          (Int(a),   &Pow, Int(b))   if 0 <= *b && *b < 64     => int(a.checked_pow(*b as u32).filter(|n| exact(*n))),
+          (Float(a), &Pow, Float(b))                           => Float(a.powf(*b)),
+          (Bool(a),  &And, Bool(b))                            => Bool(*a && *b),
+          (Bool(a),  &Or,  Bool(b))                            => Bool(*a || *b),
+
+          _ => expression.node.clone()
+        };
+
+        Expression::new(
+          node,
+          expression.pos.clone()
+        )
+      },
+
+      Unary(ref op, ref operand) => {
+        let node = match (op, &Self::fold_expression(operand)?.node) {
+          (&Not, Bool(a))  => Bool(!a),
+          (&Neg, Int(a))   => int(a.checked_neg()),
+          (&Neg, Float(a)) => Float(-a),
 
           _ => expression.node.clone()
         };
@@ -1001,4 +1615,28 @@ impl<'p> Parser<'p> {
 
     Ok(node)
   }
+
+
+
+  // `int` division rounds down, like Lua's `//`, `-7 / 2` is `-4`
+  pub fn floor_div(a: i64, b: i64) -> Option<i64> {
+    let quotient = a.checked_div(b)?;
+
+    if a % b != 0 && (a < 0) != (b < 0) {
+      Some(quotient - 1)
+    } else {
+      Some(quotient)
+    }
+  }
+
+  // and `%` takes the sign of the divisor, so `a == a / b * b + a % b`
+  pub fn floor_mod(a: i64, b: i64) -> Option<i64> {
+    let rest = a.checked_rem(b)?;
+
+    if rest != 0 && (rest < 0) != (b < 0) {
+      Some(rest + b)
+    } else {
+      Some(rest)
+    }
+  }
 }
\ No newline at end of file
@@ -0,0 +1,505 @@
+use std::rc::Rc;
+use std::collections::HashMap;
+
+use super::*;
+use super::super::error::Response::{ Wrong, Weird, };
+use super::super::error::{ report, similar, };
+
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Level {
+  Allow,
+  Warn,
+  Deny,
+}
+
+impl Level {
+  pub fn from(name: &str) -> Option<Self> {
+    match name {
+      "allow" => Some(Level::Allow),
+      "warn"  => Some(Level::Warn),
+      "deny"  => Some(Level::Deny),
+      _       => None,
+    }
+  }
+}
+
+// every lint with its stable code
+pub const LINTS: &[(&str, &str)] = &[
+  ("unused_variables", "W0001"), // locals and parameters that are never read
+  ("unreachable_code", "W0002"), // statements after a `return` in the same block
+  ("shadowing",        "W0003"), // a binding hiding one from an enclosing scope
+];
+
+// levels by lint name, lints that aren't set warn
+#[derive(Debug, Clone, Default)]
+pub struct LintLevels(HashMap<String, Level>);
+
+impl LintLevels {
+  pub fn set(&mut self, lint: &str, level: Level) -> Result<(), String> {
+    if LINTS.iter().any(|&(name, _)| name == lint) {
+      self.0.insert(lint.to_string(), level);
+
+      Ok(())
+    } else {
+      let names = LINTS.iter().map(|&(name, _)| name.to_string()).collect::<Vec<String>>();
+
+      Err(
+        match similar(lint, names.iter()) {
+          Some(name) => format!("unknown lint `{}`, did you mean `{}`?", lint, name),
+          None       => format!("unknown lint `{}`", lint),
+        }
+      )
+    }
+  }
+
+  pub fn get(&self, lint: &str) -> Option<Level> {
+    self.0.get(lint).cloned()
+  }
+}
+
+// `-- #![deny(shadowing)]` covers the whole file, `-- #[allow(unused_variables)]` the statement below it
+fn attribute(line: &str) -> Option<(bool, &str)> {
+  let line = line.trim();
+
+  if !line.starts_with("--") {
+    return None
+  }
+
+  let line = line[2 ..].trim();
+
+  if !line.ends_with(']') {
+    return None
+  }
+
+  if let Some(inner) = line.strip_prefix("#![") {
+    Some((true, &inner[.. inner.len() - 1]))
+  } else {
+    line.strip_prefix("#[").map(|inner| (false, &inner[.. inner.len() - 1]))
+  }
+}
+
+
+
+pub struct Linter<'l> {
+  source: &'l Source,
+  ast:    &'l Vec<Statement<'l>>,
+
+  scope:    Rc<SymTab>,
+  declared: Vec<Vec<(String, TokenElement<'l>, Level, bool)>>, // bindings of each scope, with whether they're parameters
+  levels:   Vec<LintLevels>,                                   // the command line first, then the file and statements around the current one
+}
+
+impl<'l> Linter<'l> {
+  pub fn new(source: &'l Source, ast: &'l Vec<Statement<'l>>, levels: &LintLevels) -> Self {
+    Linter {
+      source,
+      ast,
+
+      scope:    Rc::new(SymTab::global()),
+      declared: vec![Vec::new()],
+      levels:   vec![levels.clone()],
+    }
+  }
+
+  pub fn lint(&mut self) {
+    let mut file = LintLevels::default();
+
+    for (index, line) in self.source.lines.iter().enumerate() {
+      if let Some((true, inner)) = attribute(line) {
+        self.read_attribute(inner, &mut file, index)
+      }
+    }
+
+    self.levels.push(file);
+
+    // top-level bindings are exports, they're only checked for what's inside them
+    for statement in self.ast.iter() {
+      self.statement(statement)
+    }
+  }
+
+
+
+  fn read_attribute(&self, inner: &str, levels: &mut LintLevels, index: usize) {
+    let position = TokenElement::Line((index + 1, self.source.lines[index].as_str()));
+
+    let (level, lints) = match inner.find('(') {
+      Some(open) if inner.ends_with(')') => (&inner[.. open], &inner[open + 1 .. inner.len() - 1]),
+      _                                  => return,
+    };
+
+    let level = match Level::from(level.trim()) {
+      Some(level) => level,
+      None        => return report(
+        diagnostic!(
+          "W0000" => Weird(format!("unknown lint level `{}`, expected `allow`, `warn` or `deny`", level.trim())),
+          self.source.file,
+          position
+        )
+      ),
+    };
+
+    for lint in lints.split(',').map(|lint| lint.trim()).filter(|lint| !lint.is_empty()) {
+      if let Err(message) = levels.set(lint, level) {
+        report(
+          diagnostic!(
+            "W0000" => Weird(message),
+            self.source.file,
+            position
+          )
+        )
+      }
+    }
+  }
+
+  // the attribute comments right above the line a statement starts on
+  fn statement_levels(&self, statement: &Statement<'l>) -> Option<LintLevels> {
+    let line = match statement.pos {
+      TokenElement::Pos(line, _) | TokenElement::Span(line, ..) => line.0,
+      _                                                          => return None,
+    };
+
+    let mut levels = LintLevels::default();
+    let mut found  = false;
+
+    let mut index = line.saturating_sub(1);
+
+    while index > 0 {
+      match attribute(&self.source.lines[index - 1]) {
+        Some((false, inner)) => {
+          self.read_attribute(inner, &mut levels, index - 1);
+          found = true
+        },
+
+        Some((true, _)) => (),
+        None            => break,
+      }
+
+      index -= 1
+    }
+
+    if found {
+      Some(levels)
+    } else {
+      None
+    }
+  }
+
+  fn level(&self, lint: &str) -> Level {
+    self.levels.iter().rev().filter_map(|levels| levels.get(lint)).next().unwrap_or(Level::Warn)
+  }
+
+  fn warn(&self, lint: &str, level: Level, message: String, pos: &TokenElement<'l>, label: Option<(&TokenElement<'l>, String)>, help: Option<String>) {
+    let code = LINTS.iter().find(|&&(name, _)| name == lint).map(|&(_, code)| code).unwrap_or("W0000");
+
+    let response = match level {
+      Level::Allow => return,
+      Level::Warn  => Weird(message),
+      Level::Deny  => Wrong(message),
+    };
+
+    let mut diagnostic = diagnostic!(code => response, self.source.file, pos);
+
+    if let Some((position, message)) = label {
+      diagnostic = diagnostic.label(&self.source.file, position, message)
+    }
+
+    if let Some(help) = help {
+      diagnostic = diagnostic.help(help)
+    }
+
+    diagnostic = match level {
+      Level::Deny => diagnostic.help(format!("`{}` is denied", lint)),
+      _           => diagnostic.help(format!("silence `{}` with `-- #[allow({})]` above the statement", lint, lint)),
+    };
+
+    report(diagnostic)
+  }
+
+
+
+  fn push_scope(&mut self, names: &[String]) {
+    self.scope = Rc::new(SymTab::new(self.scope.clone(), names));
+    self.declared.push(Vec::new())
+  }
+
+  fn pop_scope(&mut self) {
+    for (name, pos, level, parameter) in self.declared.pop().unwrap() {
+      if name.starts_with('_') || self.scope.is_used(&name) {
+        continue
+      }
+
+      self.warn(
+        "unused_variables", level,
+        format!("unused {} `{}`", if parameter { "parameter" } else { "variable" }, name),
+        &pos,
+        None,
+        Some(format!("prefix it with an underscore, `_{}`, if that's on purpose", name))
+      )
+    }
+
+    let parent = self.scope.parent.clone().unwrap();
+
+    self.scope = parent
+  }
+
+  fn declare(&mut self, name: &str, pos: TokenElement<'l>) {
+    let level = self.level("unused_variables");
+
+    match self.scope.get_name(name) {
+      Some((_, 0)) => return,
+
+      Some((_, depth)) => {
+        let outer = self.declared[self.declared.len() - 1 - depth].iter()
+          .find(|declared| declared.0 == name)
+          .map(|declared| declared.1.clone());
+
+        let level = self.level("shadowing");
+
+        self.warn(
+          "shadowing", level,
+          format!("`{}` shadows a binding from an enclosing scope", name),
+          &pos,
+          outer.as_ref().map(|outer| (outer, format!("`{}` is first bound here", name))),
+          None
+        )
+      },
+
+      None => (),
+    }
+
+    self.scope.add_name(name);
+
+    let last = self.declared.len() - 1;
+
+    self.declared[last].push((name.to_string(), pos, level, false))
+  }
+
+  // struct names used as types count as reads
+  fn mark_type(&self, t: &Type) {
+    match t.node {
+      TypeNode::Id(ref name) => self.scope.mark_used(name.split('.').next().unwrap()),
+
+      TypeNode::Generic(ref name, ref args) => {
+        self.scope.mark_used(name.split('.').next().unwrap());
+
+        for arg in args {
+          self.mark_type(arg)
+        }
+      },
+
+      TypeNode::Array(ref t, _) => self.mark_type(t),
+
+      TypeNode::Func(ref params, ref return_type, ..) => {
+        for param in params {
+          self.mark_type(param)
+        }
+
+        self.mark_type(return_type)
+      },
+
+      _ => (),
+    }
+  }
+
+
+
+  fn statement(&mut self, statement: &'l Statement<'l>) {
+    use self::StatementNode::*;
+
+    let levels = self.statement_levels(statement);
+    let scoped = levels.is_some();
+
+    if let Some(levels) = levels {
+      self.levels.push(levels)
+    }
+
+    match statement.node {
+      Expression(ref expression) => self.expression(expression),
+
+      Variable(ref kind, ref name, ref right, ref position) => {
+        self.mark_type(kind);
+
+        // functions may call themselves
+        if let Some(ExpressionNode::Function(..)) = right.as_ref().map(|right| &right.node) {
+          self.declare(name, position.clone());
+          self.expression(right.as_ref().unwrap())
+        } else {
+          if let Some(ref right) = *right {
+            self.expression(right)
+          }
+
+          self.declare(name, position.clone())
+        }
+      },
+
+      Assignment(ref left, ref right) => {
+        self.expression(right);
+
+        // writing to a variable isn't reading it
+        if let ExpressionNode::Identifier(_) = left.node {
+        } else {
+          self.expression(left)
+        }
+      },
+
+      Return(ref expression) => if let Some(ref expression) = *expression {
+        self.expression(expression)
+      },
+
+      Extern(_, ref kind, _) => self.mark_type(kind),
+      Import(..)             => (),
+      Error                  => (),
+    }
+
+    if scoped {
+      self.levels.pop();
+    }
+  }
+
+  fn expression(&mut self, expression: &'l Expression<'l>) {
+    use self::ExpressionNode::*;
+
+    match expression.node {
+      Identifier(ref name) => self.scope.mark_used(name),
+
+      Block(ref statements) => {
+        self.push_scope(&[]);
+
+        let mut returned = false;
+
+        for statement in statements {
+          if returned {
+            let level = self.statement_levels(statement).and_then(|levels| levels.get("unreachable_code")).unwrap_or(self.level("unreachable_code"));
+
+            self.warn(
+              "unreachable_code", level,
+              "unreachable statement".to_string(),
+              &Self::statement_position(statement),
+              None,
+              None
+            );
+
+            returned = false
+          }
+
+          if let StatementNode::Return(_) = statement.node {
+            returned = true
+          }
+
+          self.statement(statement)
+        }
+
+        self.pop_scope()
+      },
+
+      Function(ref params, ref return_type, ref body, _) => {
+        for param in params {
+          self.mark_type(&param.1)
+        }
+
+        self.mark_type(return_type);
+
+        let names = params.iter().map(|param| param.0.clone()).collect::<Vec<String>>();
+
+        self.push_scope(&names);
+
+        let level = self.level("unused_variables");
+        let last  = self.declared.len() - 1;
+
+        for param in params {
+          self.declared[last].push((param.0.clone(), param.2.clone(), level, true))
+        }
+
+        self.expression(body);
+
+        self.pop_scope()
+      },
+
+      If(ref condition, ref body, ref elses) => {
+        self.expression(condition);
+        self.expression(body);
+
+        if let Some(ref elses) = *elses {
+          for (condition, body, _) in elses {
+            if let Some(ref condition) = *condition {
+              self.expression(condition)
+            }
+
+            self.expression(body)
+          }
+        }
+      },
+
+      While(ref condition, ref body) => {
+        self.expression(condition);
+        self.expression(body)
+      },
+
+      Call(ref called, ref args, ref types) => {
+        self.expression(called);
+
+        for arg in args {
+          self.expression(arg)
+        }
+
+        for t in types {
+          self.mark_type(t)
+        }
+      },
+
+      Construct(ref left, ref args) => {
+        self.expression(left);
+
+        for arg in args {
+          self.expression(&arg.1)
+        }
+      },
+
+      Struct(_, ref fields) => for field in fields {
+        self.mark_type(&field.1);
+
+        if let Some(ref value) = field.2 {
+          self.expression(value)
+        }
+      },
+
+      Cast(ref expression, ref t) => {
+        self.expression(expression);
+        self.mark_type(t)
+      },
+
+      Array(ref content) => for element in content {
+        self.expression(element)
+      },
+
+      Binary(ref left, _, ref right) | Index(ref left, ref right) | Else(ref left, ref right) => {
+        self.expression(left);
+        self.expression(right)
+      },
+
+      Unary(_, ref expression) | Unwrap(ref expression) | Access(ref expression, _) => self.expression(expression),
+
+      _ => (),
+    }
+  }
+
+
+
+  // variables are positioned after their name, this takes the name in
+  fn statement_position(statement: &Statement<'l>) -> TokenElement<'l> {
+    if let StatementNode::Variable(_, _, _, ref name) = statement.node {
+      if let TokenElement::Pos(_, (start, _)) = *name {
+        return match statement.pos {
+          TokenElement::Pos(line, (_, end))         => TokenElement::Pos(line, (start, end)),
+          TokenElement::Span(line, _, last, end)    => TokenElement::Span(line, start, last, end),
+          ref other                                 => other.clone(),
+        }
+      }
+    }
+
+    statement.pos.clone()
+  }
+}
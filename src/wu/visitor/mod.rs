@@ -1,6 +1,7 @@
 pub mod visitor;
 pub mod symtab;
 pub mod typetab;
+pub mod lint;
 
 use super::parser::*;
 use super::source::*;
@@ -8,4 +9,5 @@ use super::lexer::*;
 
 pub use self::visitor::*;
 pub use self::symtab::*;
-pub use self::typetab::*;
\ No newline at end of file
+pub use self::typetab::*;
+pub use self::lint::*;
\ No newline at end of file
@@ -1,14 +1,21 @@
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{ HashMap, HashSet, };
 
 use std::rc::Rc;
 
 
 
+// a line with the first and last column on it
+pub type Site = (usize, (usize, usize));
+
 #[derive(Clone, Debug)]
 pub struct SymTab {
-  pub parent: Option<Rc<SymTab>>,
-  pub names:  RefCell<HashMap<String, usize>>,
+  pub parent:   Option<Rc<SymTab>>,
+  pub names:    RefCell<HashMap<String, usize>>,
+  pub used:     RefCell<HashSet<String>>,       // names of this scope that were read, for the lints
+  pub sites:    RefCell<HashMap<String, Site>>, // where names of this scope were declared, for editors
+  pub poisoned: RefCell<HashSet<String>>,       // names of this scope whose declarations failed to check
+  pub narrowed: RefCell<HashSet<String>>,       // names of this scope standing for optionals further out known to be there
 }
 
 impl SymTab {
@@ -20,15 +27,23 @@ impl SymTab {
     }
 
     SymTab {
-      parent:  Some(parent),
-      names:   RefCell::new(hash_names),
+      parent:   Some(parent),
+      names:    RefCell::new(hash_names),
+      used:     RefCell::new(HashSet::new()),
+      sites:    RefCell::new(HashMap::new()),
+      poisoned: RefCell::new(HashSet::new()),
+      narrowed: RefCell::new(HashSet::new()),
     }
   }
 
   pub fn global() -> Self {
     SymTab {
-      parent:  None,
-      names:   RefCell::new(HashMap::new()),
+      parent:   None,
+      names:    RefCell::new(HashMap::new()),
+      used:     RefCell::new(HashSet::new()),
+      sites:    RefCell::new(HashMap::new()),
+      poisoned: RefCell::new(HashSet::new()),
+      narrowed: RefCell::new(HashSet::new()),
     }
   }
 
@@ -54,6 +69,107 @@ impl SymTab {
     }
   }
 
+  // marks the binding `name` resolves to as read
+  pub fn mark_used(&self, name: &str) {
+    if self.names.borrow().contains_key(name) {
+      self.used.borrow_mut().insert(name.to_string());
+    } else if let Some(ref parent) = self.parent {
+      parent.mark_used(name)
+    }
+  }
+
+  pub fn is_used(&self, name: &str) -> bool {
+    self.used.borrow().contains(name)
+  }
+
+  // uses of a poisoned name fail without being reported, it was reported where it was declared
+  pub fn poison(&self, name: &str) {
+    self.poisoned.borrow_mut().insert(name.to_string());
+  }
+
+  pub fn cure(&self, name: &str) {
+    self.poisoned.borrow_mut().remove(name);
+  }
+
+  // whether the binding `name` resolves to is poisoned, names declared nearer hide those further out
+  pub fn is_poisoned(&self, name: &str) -> bool {
+    if self.poisoned.borrow().contains(name) {
+      return true
+    }
+
+    if self.names.borrow().contains_key(name) {
+      return false
+    }
+
+    match self.parent {
+      Some(ref parent) => parent.is_poisoned(name),
+      None             => false,
+    }
+  }
+
+  // reads of a narrowed name see it without its `?`, assignments to it still go by its declaration
+  pub fn narrow(&self, name: &str) {
+    self.narrowed.borrow_mut().insert(name.to_string());
+  }
+
+  pub fn is_narrowed(&self, name: &str) -> bool {
+    if self.names.borrow().contains_key(name) {
+      return self.narrowed.borrow().contains(name)
+    }
+
+    match self.parent {
+      Some(ref parent) => parent.is_narrowed(name),
+      None             => false,
+    }
+  }
+
+  // like `get_name`, looking past narrowings to the declaration
+  pub fn get_declared(&self, name: &str) -> Option<(usize, usize)> {
+    self.get_declared_internal(name, 0)
+  }
+
+  fn get_declared_internal(&self, name: &str, env_index: usize) -> Option<(usize, usize)> {
+    if let Some(index) = self.names.borrow().get(name) {
+      if !self.narrowed.borrow().contains(name) {
+        return Some((*index, env_index))
+      }
+    }
+
+    match self.parent {
+      Some(ref parent) => parent.get_declared_internal(name, env_index + 1),
+      None             => None,
+    }
+  }
+
+  pub fn add_site(&self, name: &str, site: Site) {
+    self.sites.borrow_mut().insert(name.to_string(), site);
+  }
+
+  // where the binding `name` resolves to was declared, narrowed names have no site of their own
+  pub fn get_site(&self, name: &str) -> Option<Site> {
+    if let Some(site) = self.sites.borrow().get(name) {
+      return Some(*site)
+    }
+
+    match self.parent {
+      Some(ref parent) => parent.get_site(name),
+      None             => None,
+    }
+  }
+
+  // every name that can be looked up from here, innermost scope first
+  pub fn visible_names(&self) -> Vec<String> {
+    let mut names = self.names.borrow().keys().cloned().collect::<Vec<String>>();
+
+    names.sort();
+
+    if let Some(ref parent) = self.parent {
+      names.extend(parent.visible_names())
+    }
+
+    names
+  }
+
   pub fn visualize(&self, env_index: usize) {
     if env_index > 0 {
       if let Some(ref p) = self.parent {
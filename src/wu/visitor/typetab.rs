@@ -47,7 +47,7 @@ impl<'t> TypeTab<'t> {
     } else {
       match self.parent {
         Some(ref p) => p.set_type(index, env_index - 1, t),
-        None        => Err(response!(Wrong("[type table] invalid environment index")))
+        None        => fail!("E0902" => Wrong("[type table] invalid environment index"))
       }
     }
   }
@@ -63,7 +63,7 @@ impl<'t> TypeTab<'t> {
     } else {
       match self.parent {
         Some(ref p) => p.get_type(index, env_index - 1),
-        None        => Err(response!(Wrong("[type table] invalid environment index")))
+        None        => fail!("E0902" => Wrong("[type table] invalid environment index"))
       }
     }
   }
@@ -74,12 +74,12 @@ impl<'t> TypeTab<'t> {
     if env_index == 0 {
       match self.covers.get(&index) {
         Some(v) => Ok(v.clone()),
-        None    => Err(response!(Wrong("[type table] invalid type index")))
+        None    => fail!("E0902" => Wrong("[type table] invalid type index"))
       }
     } else {
       match self.parent {
         Some(ref p) => p.get_cover(index, env_index - 1),
-        None        => Err(response!(Wrong("[type table] invalid environment index")))
+        None        => fail!("E0902" => Wrong("[type table] invalid environment index"))
       }
     }
   }
@@ -1,8 +1,14 @@
-use std::fmt::{ self, Display, Write, Formatter };
+use std::fmt::{ self, Display, Formatter };
 use std::rc::Rc;
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{ HashMap, HashSet, };
+
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::Path;
 
 use super::super::error::Response::Wrong;
+use super::super::error::{ reject, similar, };
 
 use super::*;
 use super::TokenElement;
@@ -17,23 +23,29 @@ pub enum TypeNode<'t> {
   Str,
   Char,
   Nil,
+  Any,                                                    // what Lua functions taking whatever they're given take
   Id(String),
+  Generic(String, Vec<Type<'t>>),                         // a generic function's signature with its type arguments, `id<int>`
   Array(Rc<Type<'t>>, usize),
   Func(Vec<Type<'t>>, Rc<Type<'t>>, Vec<String>, Option<&'t ExpressionNode<'t>>),
+  Struct(String, Rc<Vec<(String, Type<'t>, bool)>>),    // instance, fields are (name, type, has default)
+  StructDef(String, Rc<Vec<(String, Type<'t>, bool)>>), // the declared struct itself
+  Module(String, Rc<Vec<(String, Type<'t>)>>),          // exported top-level names of an imported file
 }
 
 impl<'t> TypeNode<'t> {
   pub fn check_expression(&self, other: &'t ExpressionNode<'t>) -> bool {
     use self::TypeNode::*;
 
+    if *self == Any {
+      return true
+    }
+
     match *other {
-      ExpressionNode::Int(_) => match *self {
-        Int | Float => true,
-        _           => false,
-      },
+      ExpressionNode::Int(_) => matches!(*self, Int | Float),
 
       ExpressionNode::Array(ref content) => {
-        let array_content = if let &Array(ref array_content, ref len) = self {
+        let array_content = if let Array(array_content, len) = self {
           if *len != content.len() {
             return false
           }
@@ -64,15 +76,20 @@ impl<'t> PartialEq for TypeNode<'t> {
     use self::TypeNode::*;
 
     match (self, other) {
-      (&Int,                                 &Int)                                 => true,
-      (&Str,                                 &Str)                                 => true,
-      (&Float,                               &Float)                               => true,
-      (&Char,                                &Char)                                => true,
-      (&Bool,                                &Bool)                                => true,
-      (&Nil,                                 &Nil)                                 => true,
-      (&Array(ref a, ref la),                &Array(ref b, ref lb))                => a == b && la == lb,
-      (&Id(ref a),                           &Id(ref b))                           => a == b,
-      (&Func(ref a_params, ref a_retty, ..), &Func(ref b_params, ref b_retty, ..)) => a_params == b_params && a_retty == b_retty,
+      (Int,                         Int)                         => true,
+      (Str,                         Str)                         => true,
+      (Float,                       Float)                       => true,
+      (Char,                        Char)                        => true,
+      (Bool,                        Bool)                        => true,
+      (Nil,                         Nil)                         => true,
+      (Any,                         Any)                         => true,
+      (Array(a, la),                Array(b, lb))                => a == b && la == lb,
+      (Id(a),                       Id(b))                       => a == b,
+      (Generic(a, a_args),          Generic(b, b_args))          => a == b && a_args == b_args,
+      (Func(a_params, a_retty, ..), Func(b_params, b_retty, ..)) => a_params == b_params && a_retty == b_retty,
+      (Struct(a, _),                Struct(b, _))                => a == b,
+      (StructDef(a, _),             StructDef(b, _))             => a == b,
+      (Module(a, _),                Module(b, _))                => a == b,
 
       _ => false,
     }
@@ -88,7 +105,7 @@ pub enum TypeMode {
   Optional,
   Regular,
   Splat(Option<usize>),
-  Unwrap(usize),
+  Unwrap(Option<usize>), // how many values a splat spreads into, unknown in a body checked by itself
 }
 
 impl<'t> Display for TypeNode<'t> {
@@ -102,11 +119,26 @@ impl<'t> Display for TypeNode<'t> {
       Str              => write!(f, "str"),
       Char             => write!(f, "char"),
       Nil              => write!(f, "nil"),
+      Any              => write!(f, "any"),
       Array(ref n, l)  => write!(f, "[{}; {}]", n, l),
       Id(ref n)        => write!(f, "{}", n),
 
+      Generic(ref n, ref args) => {
+        write!(f, "{}<", n)?;
+
+        for (index, element) in args.iter().enumerate() {
+          if index < args.len() - 1 {
+            write!(f, "{}, ", element)?
+          } else {
+            write!(f, "{}", element)?
+          }
+        }
+
+        write!(f, ">")
+      },
+
       Func(ref params, ref return_type, ..) => {
-        write!(f, "(");
+        write!(f, "(")?;
 
         for (index, element) in params.iter().enumerate() {
           if index < params.len() - 1 {
@@ -118,6 +150,10 @@ impl<'t> Display for TypeNode<'t> {
 
         write!(f, ") -> {}", return_type)
       },
+
+      Struct(ref n, _)    => write!(f, "{}", n),
+      StructDef(ref n, _) => write!(f, "struct {}", n),
+      Module(ref n, _)    => write!(f, "module {}", n),
     }
   }
 }
@@ -133,11 +169,10 @@ impl PartialEq for TypeMode {
       (&Regular,    &Immutable)   => true,
       (&Immutable,  &Immutable)   => true,
       (&Immutable,  &Regular)     => true,
-      (_,           &Optional)    => true,
-      (&Optional,   _)            => true,
+      (&Optional,   &Optional)    => true,
       (&Undeclared, _)            => false,
       (_,           &Undeclared)  => false,
-      (&Splat(a),      &Splat(b)) => &a == &b,
+      (&Splat(a),      &Splat(b)) => a == b,
       (&Unwrap(_),  _)            => true,
       (_,           &Unwrap(_))   => true,
       _                           => false,
@@ -153,7 +188,7 @@ impl Display for TypeMode {
       Regular    => Ok(()),
       Immutable  => write!(f, "constant "),
       Undeclared => write!(f, "undeclared "),
-      Optional   => write!(f, "optional "),
+      Optional   => Ok(()),
       Splat(_)   => write!(f, ".."),
       Unwrap(_)  => write!(f, "*"),
     }
@@ -190,11 +225,42 @@ impl<'t> Type<'t> {
   pub fn function(params: Vec<Type<'t>>, return_type: Type<'t>) -> Self {
     Type::new(TypeNode::Func(params, Rc::new(return_type), Vec::new(), None), TypeMode::Regular)
   }
+
+  // the same type without the function bodies it points at, so it outlives the ast it was found in
+  pub fn detached(&self) -> Type<'static> {
+    use self::TypeNode::*;
+
+    let fields = |fields: &Rc<Vec<(String, Type<'t>, bool)>>| Rc::new(fields.iter().map(|(name, t, default)| (name.clone(), t.detached(), *default)).collect());
+
+    let node = match self.node {
+      Int   => Int,
+      Float => Float,
+      Bool  => Bool,
+      Str   => Str,
+      Char  => Char,
+      Nil   => Nil,
+      Any   => Any,
+
+      Id(ref name)                                 => Id(name.clone()),
+      Generic(ref name, ref args)                  => Generic(name.clone(), args.iter().map(Type::detached).collect()),
+      Array(ref t, len)                            => Array(Rc::new(t.detached()), len),
+      Func(ref params, ref retty, ref generics, _) => Func(params.iter().map(Type::detached).collect(), Rc::new(retty.detached()), generics.clone(), None),
+      Struct(ref name, ref content)                => Struct(name.clone(), fields(content)),
+      StructDef(ref name, ref content)             => StructDef(name.clone(), fields(content)),
+      Module(ref name, ref exports)                => Module(name.clone(), Rc::new(exports.iter().map(|(name, t)| (name.clone(), t.detached())).collect())),
+    };
+
+    Type::new(node, self.mode.clone())
+  }
 }
 
 impl<'t> Display for Type<'t> {
   fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-    write!(f, "{}{}", self.mode, self.node)
+    if let TypeMode::Optional = self.mode {
+      write!(f, "{}?", self.node)
+    } else {
+      write!(f, "{}{}", self.mode, self.node)
+    }
   }
 }
 
@@ -208,6 +274,17 @@ pub enum FlagContext<'f> {
 
 
 
+// an identifier as it was resolved, or a name where it was declared, for editors
+#[derive(Debug, Clone)]
+pub struct Reference<'v> {
+  pub name: String,
+  pub pos:  Site,
+  pub kind: Type<'v>,
+  pub site: Option<Site>,
+}
+
+
+
 pub struct Visitor<'v> {
   pub tabs:       Vec<(SymTab, TypeTab<'v>)>,
   pub tab_frames: Vec<(SymTab, TypeTab<'v>)>,
@@ -216,10 +293,44 @@ pub struct Visitor<'v> {
   pub ast:     &'v Vec<Statement<'v>>,
 
   pub flag: Option<FlagContext<'v>>,
+
+  pub modules:   Rc<RefCell<HashMap<String, Option<Type<'static>>>>>, // shared by every visitor of a compile, `None` while being checked
+  pub instances: HashSet<String>,                                      // generic and splat function instantiations already checked, and bodies that failed by themselves
+  pub covers:    Vec<HashMap<String, Type<'v>>>,                       // type arguments of the instantiations being checked
+
+  pub references: Vec<Reference<'v>>,                 // every identifier that was typed and every declared name
+  pub scopes:     Vec<(TokenElement<'v>, Vec<String>)>, // the names visible at the end of each checked block
+
+  pub types: HashMap<*const Expression<'v>, Type<'v>>, // what each expression of the ast was typed as, for lowering
+}
+
+thread_local! {
+  static PRELUDE: RefCell<Option<Vec<(String, Type<'static>)>>> = const { RefCell::new(None) };
 }
 
+
+
 impl<'v> Visitor<'v> {
   pub fn new(source: &'v Source, ast: &'v Vec<Statement<'v>>) -> Self {
+    let symtab      = SymTab::global();
+    let mut typetab = TypeTab::global();
+
+    for (name, t) in Self::prelude() {
+      typetab.grow();
+
+      let index = symtab.add_name(&name);
+
+      typetab.set_type(index, 0, t).unwrap()
+    }
+
+    let mut visitor = Self::without_prelude(source, ast);
+
+    visitor.tabs = vec!((SymTab::new(Rc::new(symtab), &[]), TypeTab::new(Rc::new(typetab), &[], HashMap::new())));
+
+    visitor
+  }
+
+  fn without_prelude(source: &'v Source, ast: &'v Vec<Statement<'v>>) -> Self {
     Visitor {
       tabs:       vec!((SymTab::global(), TypeTab::global())),
       tab_frames: Vec::new(), // very intelligent hack
@@ -228,17 +339,162 @@ impl<'v> Visitor<'v> {
       ast,
 
       flag: None,
+
+      modules:   Rc::new(RefCell::new(HashMap::new())),
+      instances: HashSet::new(),
+      covers:    Vec::new(),
+
+      references: Vec::new(),
+      scopes:     Vec::new(),
+
+      types: HashMap::new(),
+    }
+  }
+
+  // the bundled `prelude.wu`, checked once per thread
+  fn prelude() -> Vec<(String, Type<'static>)> {
+    PRELUDE.with(|prelude| {
+      if prelude.borrow().is_none() {
+        let exports = Self::check_file("<prelude>", include_str!("prelude.wu"), false, Rc::new(RefCell::new(HashMap::new())));
+
+        *prelude.borrow_mut() = Some(exports.expect("bundled prelude is broken"))
+      }
+
+      prelude.borrow().clone().unwrap()
+    })
+  }
+
+
+
+  // checks a file other than the one being compiled, its exports are detached as its ast is dropped right after
+  fn check_file(file: &str, content: &str, with_prelude: bool, modules: Rc<RefCell<HashMap<String, Option<Type<'static>>>>>) -> Result<Vec<(String, Type<'static>)>, ()> {
+    let source = Source::from(file, content.lines().map(|x| x.into()).collect());
+
+    let mut tokens = Vec::new();
+
+    for token in Lexer::default(content.chars().collect(), &source) {
+      tokens.push(token?)
+    }
+
+    let mut parser = Parser::new(tokens.iter().collect(), &source);
+
+    // a module with holes in it can't say what it exports
+    let ast = parser.parse();
+
+    if parser.failed() {
+      return Err(())
+    }
+
+    let mut visitor = if with_prelude { Visitor::new(&source, &ast) } else { Visitor::without_prelude(&source, &ast) };
+
+    visitor.modules = modules;
+    visitor.visit()?;
+
+    let exports = visitor.exports()?;
+
+    Ok(exports.iter().map(|(name, t)| (name.clone(), t.detached())).collect())
+  }
+
+
+
+  // the top-level names and types of a visited file
+  fn exports(&self) -> Result<Vec<(String, Type<'v>)>, ()> {
+    let mut exports = Vec::new();
+
+    let (symtab, typetab) = &self.tabs[0];
+
+    let mut names = symtab.names.borrow().iter().map(|(name, index)| (name.clone(), *index)).collect::<Vec<_>>();
+
+    names.sort_by_key(|&(_, index)| index);
+
+    for (name, index) in names {
+      let mut t = typetab.get_type(index, 0)?;
+
+      // bodies stay home, they were checked there
+      if let TypeNode::Func(_, _, _, ref mut func) = t.node {
+        *func = None
+      }
+
+      exports.push((name, t))
     }
+
+    Ok(exports)
   }
 
+
+
+  // a statement that doesn't check out is reported and the rest are still checked
   pub fn visit(&mut self) -> Result<(), ()> {
+    let mut failed = false;
+
     for statement in self.ast {
-      self.visit_statement(&statement)?
+      if self.visit_recovering(statement).is_err() {
+
+        failed = true
+      }
     }
 
     self.tab_frames.push(self.tabs.last().unwrap().clone());
 
-    Ok(())
+    if failed {
+      Err(())
+    } else {
+      Ok(())
+    }
+  }
+
+
+
+  // errors leave early, so scopes they were in the middle of are dropped before checking on
+  fn visit_recovering(&mut self, statement: &'v Statement<'v>) -> Result<(), ()> {
+    let depth  = self.tabs.len();
+    let covers = self.covers.len();
+    let flag   = self.flag.clone();
+
+    let result = self.visit_statement(statement);
+
+    if result.is_err() {
+      self.tabs.truncate(depth);
+      self.covers.truncate(covers);
+
+      self.flag = flag;
+
+      self.poison(statement)
+    }
+
+    result
+  }
+
+
+
+  // a declaration that failed to check still leaves its name behind, typed as annotated if it is,
+  // otherwise uses of the name fail without being reported again
+  fn poison(&mut self, statement: &'v Statement<'v>) {
+    // a module that couldn't be imported, its name isn't declared by anything else
+    if let StatementNode::Import(ref path) = statement.node {
+      self.current_tab().0.poison(path.last().unwrap());
+    }
+
+    if let StatementNode::Variable(ref kind, ref name, ..) = statement.node {
+      let declared = match self.current_tab().0.get_name(name) {
+        Some((index, 0)) => self.current_tab().1.get_type(index, 0).ok(),
+        _                => None,
+      };
+
+      if declared.is_some_and(|t| t.node != TypeNode::Nil) {
+        return
+      }
+
+      if kind.node != TypeNode::Nil {
+        if let Ok(kind) = self.resolve_type(kind, &Self::annotation(&statement.pos)) {
+          if self.declare(name, kind).is_ok() {
+            return
+          }
+        }
+      }
+
+      self.current_tab().0.poison(name);
+    }
   }
 
   pub fn visit_statement(&mut self, statement: &'v Statement<'v>) -> Result<(), ()> {
@@ -246,25 +502,98 @@ impl<'v> Visitor<'v> {
 
     match statement.node {
       Expression(ref expression) => self.visit_expression(expression),
-      Variable(..)               => self.visit_variable(&statement.node),
+      Variable(..)               => self.visit_variable(statement),
+
+      Import(ref path) => {
+        let module = self.import(path, &statement.pos)?;
+
+        self.site(path.last().unwrap(), &statement.pos);
+        self.declare(path.last().unwrap(), module)
+      },
+
+      Extern(ref path, ref kind, _) => {
+        let kind = self.resolve_type(kind, &statement.pos)?;
+
+        let t = if path.len() == 1 {
+          kind
+        } else {
+          let existing = match self.current_tab().0.get_name(&path[0]) {
+            Some((index, env_index)) => self.current_tab().1.get_type(index, env_index).ok(),
+            None                     => None,
+          };
+
+          Self::extend_module(existing, &path[0], &path[1 ..], kind)
+        };
+
+        self.site(&path[0], &statement.pos);
+        self.declare(&path[0], t)
+      },
 
       Assignment(ref left, ref right) => {
-        let left_type  = self.type_expression(left)?;
-        let right_type = self.type_expression(right)?;
+        self.visit_expression(right)?;
 
-        if !left_type.node.check_expression(&Parser::fold_expression(right)?.node) && left_type.node != right_type.node {
-          return Err(
-            response!(
-              Wrong(format!("mismatched types, expected type `{}` got `{}`", left_type.node, right_type)),
-              self.source.file,
-              right.pos
+        // `a = ..`, `a[0] = ..` and `a.b = ..` all change `a`
+        let mut root = left;
+
+        while let ExpressionNode::Index(ref inner, _) | ExpressionNode::Access(ref inner, _) = root.node {
+          root = inner
+        }
+
+        if let ExpressionNode::Identifier(ref name) = root.node {
+          if let TypeMode::Immutable = self.type_expression(root)?.mode {
+            return reject(
+              diagnostic!(
+                "E0507" => Wrong(
+                  if ::std::ptr::eq(root, left) {
+                    format!("can't assign twice to constant `{}`", name)
+                  } else {
+                    format!("can't mutate constant `{}`", name)
+                  }
+                ),
+                self.source.file,
+                left.pos
+              ).help(format!("declare `{}` with `:=` instead of `::` to make it a variable", name))
             )
+          }
+        }
+
+        let mut left_type = self.type_expression(left)?;
+        let right_type    = self.type_expression(right)?;
+
+        // a narrowed name takes what it was declared to, and once it may be `nil` again it's read as optional again
+        let narrowed = match left.node {
+          ExpressionNode::Identifier(ref name) if self.current_tab().0.is_narrowed(name) => Some(name),
+          _                                                                          => None,
+        };
+
+        if let Some(name) = narrowed {
+          if let Some((index, env_index)) = self.current_tab().0.get_declared(name) {
+            left_type = self.current_tab().1.get_type(index, env_index)?
+          }
+        }
+
+        if !self.fills(&left_type, right)? {
+          return fail!(
+            "E0401" => Wrong(format!("mismatched types, expected type `{}` got `{}`", left_type, right_type)),
+            self.source.file,
+            right.pos
           )
         }
 
+        if let Some(name) = narrowed {
+          if right_type.node == TypeNode::Nil || right_type.mode == TypeMode::Optional {
+            if let Some((index, env_index)) = self.current_tab().0.get_name(name) {
+              self.current_tab().1.set_type(index, env_index, left_type)?
+            }
+          }
+        }
+
         Ok(())
       },
 
+      // broken statements were reported while parsing, they check as nothing in particular
+      Error => Err(()),
+
       _ => Ok(())
     }
   }
@@ -280,33 +609,29 @@ impl<'v> Visitor<'v> {
             Call(..)   => (),
             Block(..)  => { self.ensure_no_implicit(expression)?; }
 
-            If(_, ref expr, _) => self.ensure_no_implicit(&*expr)?,
+            If(_, ref expr, _) => self.ensure_no_implicit(expr)?,
 
-            _ => return Err(
-              response!(
-                Wrong("unexpected expression without context"),
-                self.source.file,
-                expression.pos
-              )
+            While(..)  => (),
+
+            _ => return fail!(
+              "E0414" => Wrong("unexpected expression without context"),
+              self.source.file,
+              expression.pos
             )
           }
         }
-
-        ()
-      } else {
-        ()
       },
 
       Call(..)   => (),
 
-      If(_, ref expr, _) => self.ensure_no_implicit(&*expr)?,
+      If(_, ref expr, _) => self.ensure_no_implicit(expr)?,
 
-      _ => return Err(
-        response!(
-          Wrong("unexpected expression without context"),
-          self.source.file,
-          expression.pos
-        )
+      While(..) => (),
+
+      _ => return fail!(
+        "E0414" => Wrong("unexpected expression without context"),
+        self.source.file,
+        expression.pos
       )
     }
 
@@ -317,96 +642,121 @@ impl<'v> Visitor<'v> {
     use self::ExpressionNode::*;
 
     match expression.node {
+      Identifier(ref name) if self.current_tab().0.is_poisoned(name) => Err(()),
+
+      Error => Err(()),
+
       Identifier(ref name) => if self.current_tab().0.get_name(name).is_none() {
-        Err(
-          response!(
-            Wrong(format!("no such value `{}` in this scope", name)),
-            self.source.file,
-            expression.pos
-          )
-        )
+        self.no_such_value(name, &expression.pos)
       } else {
         Ok(())
       },
 
       Unwrap(ref expression) => {
-        self.visit_expression(&**expression)?;
+        self.visit_expression(expression)?;
 
-        if let TypeMode::Splat(_) = self.type_expression(&**expression)?.mode {
+        if let TypeMode::Splat(_) | TypeMode::Optional = self.type_expression(expression)?.mode {
           Ok(())
         } else {
-          Err(
-            response!(
-              Wrong("can't unwrap a non-splat value"),
-              self.source.file,
-              expression.pos
-            )
+          fail!(
+            "E0501" => Wrong("can't unwrap a value that is neither splat nor optional"),
+            self.source.file,
+            expression.pos
           )
         }
       }
 
+      Unary(_, ref operand) => {
+        self.visit_expression(operand)?;
+        self.type_expression(expression)?;
+
+        Ok(())
+      },
+
+      Else(ref optional, ref default) => {
+        self.visit_expression(optional)?;
+        self.visit_expression(default)?;
+        self.type_expression(expression)?;
+
+        Ok(())
+      },
+
       Block(ref statements) => {
         self.push_scope();
 
+        let mut failed = false;
+
         for (i, statement) in statements.iter().enumerate() {
           if i < statements.len() - 1 {
             if let StatementNode::Expression(ref expression) = statement.node {
-              self.ensure_no_implicit(expression)?
+              if self.ensure_no_implicit(expression).is_err() {
+                failed = true;
+
+                continue
+              }
             }
           }
 
-          self.visit_statement(statement)?
+          if self.visit_recovering(statement).is_err() {
+            failed = true
+          }
         }
 
+        let names = self.current_tab().0.visible_names();
+
+        self.scopes.push((expression.pos.clone(), names));
         self.pop_scope();
 
-        Ok(())
+        if failed {
+          Err(())
+        } else {
+          Ok(())
+        }
       },
 
       If(ref condition, ref body, ref elses) => {
-        self.visit_expression(&*condition)?;
+        self.visit_expression(condition)?;
 
-        let condition_type = self.type_expression(&*condition)?.node;
+        let condition_type = self.type_expression(condition)?.node;
 
         if condition_type == TypeNode::Bool {
 
           self.push_scope();
+          self.narrow(condition)?;
 
           self.visit_expression(body)?;
           let body_type = self.type_expression(body)?;
 
           self.pop_scope();
 
-          if let &Some(ref elses) = elses {
-            for &(ref maybe_condition, ref body, _) in elses {
+          if let Some(elses) = elses {
+            for (maybe_condition, else_body, _) in elses {
               if let Some(ref condition) = *maybe_condition {
                 let condition_type = self.type_expression(condition)?.node;
 
                 if condition_type != TypeNode::Bool {
-                  return Err(
-                    response!(
-                      Wrong(format!("mismatched condition, must be `bool` got `{}`", condition_type)),
-                      self.source.file,
-                      condition.pos
-                    )
+                  return fail!(
+                    "E0402" => Wrong(format!("mismatched condition, must be `bool` got `{}`", condition_type)),
+                    self.source.file,
+                    condition.pos
                   )
                 }
               }
 
               self.push_scope();
 
-              self.visit_expression(body)?;
-              let else_body_type = self.type_expression(body)?;
+              self.visit_expression(else_body)?;
+              let else_body_type = self.type_expression(else_body)?;
 
               self.pop_scope();
 
               if body_type != else_body_type {
-                return Err(
-                  response!(
-                    Wrong(format!("mismatched types, expected `{}` got `{}`", body_type, else_body_type)),
+                return reject(
+                  diagnostic!(
+                    "E0401" => Wrong(format!("mismatched types, expected `{}` got `{}`", body_type, else_body_type)),
                     self.source.file,
-                    body.pos
-                  )
+                    else_body.pos
+                  ).label(&self.source.file, &body.pos, format!("expected `{}` because of this branch", body_type))
                 )
               }
             }
@@ -415,204 +765,197 @@ impl<'v> Visitor<'v> {
           Ok(())
 
         } else {
-          return Err(
-            response!(
-              Wrong(format!("mismatched condition, must be `bool` got `{}`", condition_type)),
-              self.source.file,
-              expression.pos
-            )
+          fail!(
+            "E0402" => Wrong(format!("mismatched condition, must be `bool` got `{}`", condition_type)),
+            self.source.file,
+            condition.pos
           )
         }
       },
 
-      Call(ref expression, ref args) => {
-        self.visit_expression(expression)?;
+      While(ref condition, ref body) => {
+        self.visit_expression(condition)?;
 
-        let expression_type = self.type_expression(expression)?.node;
+        let condition_type = self.type_expression(condition)?.node;
 
-        let mut covers = HashMap::new();
+        if condition_type == TypeNode::Bool {
+          self.push_scope();
+
+          self.visit_expression(body)?;
 
-        let mut corrected_params = Vec::new(); // because functions don't know what's best for them >:()
+          self.pop_scope();
 
-        if let TypeNode::Func(ref params, _, ref generics, ref func) = expression_type {
-          let mut actual_arg_len = args.len();
+          Ok(())
+        } else {
+          fail!(
+            "E0402" => Wrong(format!("mismatched condition, must be `bool` got `{}`", condition_type)),
+            self.source.file,
+            condition.pos
+          )
+        }
+      },
 
-          let mut type_buffer: Option<Type<'v>> = None; // for unwraps
+      Struct(_, ref fields) => {
+        let mut names = Vec::new();
 
+        for (name, kind, value) in fields {
+          if names.contains(&name) {
+            return fail!(
+              "E0307" => Wrong(format!("field `{}` is declared more than once", name)),
+              self.source.file,
+              expression.pos
+            )
+          }
 
-          let mut has_unwrap = false;
+          names.push(name);
 
-          for (index, param) in params.iter().enumerate() {
-            let arg_type = if index < args.len() {
-              self.type_expression(&args[index])?
-            } else {
-              type_buffer.as_ref().unwrap().clone()
-            };
+          if let Some(ref value) = *value {
+            self.visit_expression(value)?;
 
-            corrected_params.push(arg_type.clone());
+            let kind       = self.resolve_type(kind, &expression.pos)?;
+            let value_type = self.type_expression(value)?;
 
-            let mode = arg_type.mode.clone();
+            if !kind.node.check_expression(&Parser::fold_expression(value)?.node) && kind.node != value_type.node {
+              return fail!(
+                "E0401" => Wrong(format!("mismatched types, expected type `{}` got `{}`", kind.node, value_type)),
+                self.source.file,
+                value.pos
+              )
+            }
+          }
+        }
 
-            if let TypeMode::Unwrap(ref len) = mode {
-              has_unwrap = true;
+        Ok(())
+      },
 
-              type_buffer = Some(arg_type.clone());
+      Construct(ref left, ref args) => {
+        self.visit_expression(left)?;
 
-              actual_arg_len += len
-            }
+        // a broken field makes counting and ordering them meaningless
+        if args.iter().any(|arg| arg.1.node == Error) {
+          return Err(())
+        }
 
-            if let TypeNode::Id(ref name) = param.node {
-              if generics.contains(name) {
-                if let Some(kind) = covers.get(name) {
-                  if &arg_type == kind {
-                    continue
-                  } else {
-                    return Err(
-                      response!(
-                        Wrong(format!("mismatched argument, expected `{}` got `{}`", expression_type, arg_type)),
-                        self.source.file,
-                        expression.pos
-                      )
-                    )
-                  }
-                }
+        let (struct_name, fields) = if let TypeNode::StructDef(ref name, ref fields) = self.type_expression(left)?.node {
+          (name.clone(), fields.clone())
+        } else {
+          return fail!(
+            "E0410" => Wrong(format!("can't construct `{}`, not a struct", self.type_expression(left)?)),
+            self.source.file,
+            left.pos
+          )
+        };
 
-                covers.insert(name.clone(), arg_type.clone());
+        if args.len() > fields.len() {
+          return fail!(
+            "E0310" => Wrong(format!("too many fields, expected {} got {}", fields.len(), args.len())),
+            self.source.file,
+            args.last().unwrap().1.pos
+          )
+        }
 
-                continue
-              }
-            }
+        let mut given = Vec::new();
 
-            if (index < args.len() && !param.node.check_expression(&args[index].node)) && param != &arg_type {
-              return Err(
-                response!(
-                  Wrong(format!("mismatched argument, expected `{}` got `{}`", param, arg_type)),
-                  self.source.file,
-                  args[index].pos
-                )
+        for (i, (name, arg)) in args.iter().enumerate() {
+          let field = match *name {
+            Some(ref name) => match fields.iter().position(|field| &field.0 == name) {
+              Some(field) => field,
+              None        => return fail!(
+                "E0302" => Wrong(format!("no field `{}` on `{}`", name, struct_name)),
+                self.source.file,
+                arg.pos
               )
-            }
-          }
+            },
 
-          if has_unwrap {
-            actual_arg_len -= 0
+            None => if args[.. i].iter().all(|arg| arg.0.is_none()) {
+              i
+            } else {
+              return fail!(
+                "E0311" => Wrong("positional field after named field"),
+                self.source.file,
+                arg.pos
+              )
+            },
+          };
+
+          if given.contains(&field) {
+            return fail!(
+              "E0308" => Wrong(format!("field `{}` is given more than once", fields[field].0)),
+              self.source.file,
+              arg.pos
+            )
           }
 
-          if actual_arg_len > params.len() {
-            let last = params.last().unwrap();
-
-            if let TypeMode::Splat(_) = last.mode {
-              for splat in &args[params.len()..] {
-                let splat_type = self.type_expression(&splat)?;
-
-                if let TypeNode::Id(ref name) = last.node {
-                  if generics.contains(name) {
-                    if let Some(kind) = covers.get(name) {
-                      if &splat_type == kind {
-                        continue
-                      } else {
-                        return Err(
-                          response!(
-                            Wrong(format!("mismatched splat argument, expected `{}` got `{}`", kind, splat_type)),
-                            self.source.file,
-                            splat.pos
-                          )
-                        )
-                      }
-                    }
-                  }
-                }
+          given.push(field);
 
-                if !last.node.check_expression(&splat.node) && last != &splat_type {
-                  return Err(
-                    response!(
-                      Wrong(format!("mismatched splat argument, expected `{}` got `{}`", last, splat_type)),
-                      self.source.file,
-                      splat.pos
-                    )
-                  )
-                }
-              }
-            }
-          }
-          
-
-          if actual_arg_len > params.len() {
-            match params.last().unwrap().mode {
-              TypeMode::Splat(_) => (),
-              _                  => return Err(
-                response!(
-                  Wrong(format!("too many arguments, expected {} got {}", params.len(), actual_arg_len)),
-                  self.source.file,
-                  args.last().unwrap().pos
-                )
-              )
-            }
-          }
-
-
-          if covers.len() > 0 || actual_arg_len > params.len() {
-            if let Function(ref params, ref return_type, ref body, ref generics) = *func.unwrap() {
+          self.visit_expression(arg)?;
 
-              let mut real_params = Vec::new();
+          let kind     = &fields[field].1;
+          let arg_type = self.type_expression(arg)?;
 
-              for (i, param) in params.iter().enumerate() {
-                real_params.push(
-                  (
-                    param.0.clone(),
-                    if let TypeNode::Func(..) = param.1.node {
-                      corrected_params[i].clone()
-                    } else {
-                      param.1.clone()
-                    }
-                  )
-                )
-              }
-
-              self.visit_function(expression.pos.clone(), &real_params, return_type, body, generics, Some(covers), actual_arg_len - params.len())?;
-            } else {
-              unreachable!()
-            }
+          if !kind.node.check_expression(&Parser::fold_expression(arg)?.node) && kind.node != arg_type.node {
+            return fail!(
+              "E0401" => Wrong(format!("mismatched field `{}`, expected `{}` got `{}`", fields[field].0, kind, arg_type)),
+              self.source.file,
+              arg.pos
+            )
           }
+        }
 
-        } else {
-          return Err(
-            response!(
-              Wrong(format!("expected function, found `{}`", expression_type)),
+        for (i, (name, _, has_default)) in fields.iter().enumerate() {
+          if !has_default && !given.contains(&i) {
+            return fail!(
+              "E0309" => Wrong(format!("missing field `{}` without default", name)),
               self.source.file,
               expression.pos
             )
-          )
+          }
         }
 
         Ok(())
       },
 
-      Function(ref params, ref return_type, ref body, ref generics) => self.visit_function(expression.pos.clone(), params, return_type, body, generics, None, 0),
+      Access(ref left, _) => {
+        self.visit_expression(left)?;
+        self.type_expression(expression)?;
 
-      Array(ref content) => {
-        let t = self.type_expression(content.first().unwrap())?;
+        Ok(())
+      },
 
-        for element in content {
-          let element_type = self.type_expression(element)?;
+      Call(ref called, ref args, ref types) => {
+        self.visit_expression(called)?;
 
-          if !t.node.check_expression(&Parser::fold_expression(element)?.node) && t.node != element_type.node {
-            return Err(
-              response!(
-                Wrong(format!("mismatched types in array, expected `{}` got `{}`", t, element_type)),
-                self.source.file,
-                element.pos
-              )
-            )
-          }
+        for arg in args {
+          self.visit_expression(arg)?
         }
 
+        let t = self.type_call(called, args, types, &expression.pos, true)?;
+
+        self.record(expression, &t);
+
         Ok(())
       },
 
+      Function(ref params, ref return_type, ref body, ref generics) => {
+        let checked = self.visit_function(expression.pos.clone(), params, return_type, body, generics, None, None);
+
+        // what is wrong whatever the type arguments is reported here, and not again for each call
+        if checked.is_err() {
+          self.instances.insert(format!("{:p}", body));
+        }
+
+        checked
+      },
+
+      Array(ref content) => self.element_type(content).map(|_| ()),
+
       Index(ref left, ref index) => {
         let left_type = self.type_expression(left)?;
 
+        if Self::is_parameter(&left_type.node) {
+          return self.visit_expression(index)
+        }
+
         if let TypeNode::Array(_, ref len) = left_type.node {
           let index_type = self.type_expression(index)?;
 
@@ -620,33 +963,27 @@ impl<'v> Visitor<'v> {
             TypeNode::Int => {
               if let Int(ref a) = Parser::fold_expression(index)?.node {
                 if *a as usize > *len {
-                  return Err(
-                    response!(
-                      Wrong(format!("index out of bounds, len is {} got {}", len, a)),
-                      self.source.file,
-                      left.pos
-                    )
+                  return fail!(
+                    "E0409" => Wrong(format!("index out of bounds, len is {} got {}", len, a)),
+                    self.source.file,
+                    left.pos
                   )
                 }
               }
             },
 
-            _ => return Err(
-              response!(
-                Wrong(format!("can't index with `{}`, must be positive integer", index_type)),
-                self.source.file,
-                left.pos
-              )
+            _ => return fail!(
+              "E0408" => Wrong(format!("can't index with `{}`, must be positive integer", index_type)),
+              self.source.file,
+              left.pos
             )
           }
 
         } else {
-          return Err(
-            response!(
-              Wrong(format!("can't index `{}`", left_type)),
-              self.source.file,
-              left.pos
-            )
+          return fail!(
+            "E0408" => Wrong(format!("can't index `{}`", left_type)),
+            self.source.file,
+            left.pos
           )
         }
 
@@ -662,58 +999,33 @@ impl<'v> Visitor<'v> {
   fn visit_function(
       &mut self,
       pos: TokenElement<'v>,
-      params: &Vec<(String, Type<'v>)>, return_type: &'v Type<'v>,
+      params: &Vec<(String, Type<'v>, TokenElement<'v>)>, return_type: &'v Type<'v>,
       body: &'v Rc<Expression<'v>>, generics: &Option<Vec<String>>, generic_covers: Option<HashMap<String, Type<'v>>>,
-      splat_len: usize
+      splat_len: Option<usize>
   ) -> Result<(), ()> {
+    // without covers, type parameters stand for themselves, and anything depending on them is left to each instantiation
+    let covers = generic_covers.unwrap_or_else(|| Self::own_parameters(generics.as_deref().unwrap_or(&[])));
+
+    self.covers.push(covers.clone());
+
     let mut param_names = Vec::new();
     let mut param_types = Vec::new();
 
-    let mut return_type = return_type;
-
     for param in params {
       param_names.push(param.0.clone());
 
-      let kind = if let Some(ref generics) = *generics {
-        if let TypeNode::Id(ref name) = return_type.node {
-          if generics.contains(name) {
-            if let Some(ref covers) = generic_covers {
-              return_type = covers.get(name).unwrap()
-            }
-          }
-        }
+      let kind = self.resolve_type(&Self::substitute(&param.1, &covers), &pos)?;
 
-        if let TypeNode::Id(ref name) = param.1.node {
-          if generics.contains(name) {
-            if let Some(ref covers) = generic_covers {
-              Type::new(covers.get(name).unwrap().clone().node, param.1.mode.clone())
-            } else {
-              param.1.clone()
-            }
-          } else {
-            param.1.clone()
-          }
+      param_types.push(
+        if let TypeMode::Splat(_) = kind.mode {
+          Type::new(kind.node, TypeMode::Splat(splat_len))
         } else {
-          param.1.clone()
+          kind
         }
-      } else {
-        param.1.clone()
-      };
-
-      param_types.push(kind);
-    }
-
-    let last_type = param_types.last().unwrap().clone();
-
-    if let TypeMode::Splat(_) = last_type.mode {
-      let len = param_types.len();
-
-      param_types[len - 1] = Type::new(last_type.node, TypeMode::Splat(Some(splat_len)))
+      )
     }
 
-    if generics.is_none() != generic_covers.is_none() && splat_len == 0 {
-      return Ok(())
-    }
+    let return_type = Self::substitute(return_type, &covers);
 
     let parent = self.current_tab().clone();
 
@@ -724,19 +1036,26 @@ impl<'v> Visitor<'v> {
       )
     );
 
+    for (param, kind) in params.iter().zip(param_types.iter()) {
+      self.site(&param.0, &param.2);
+      self.refer(&param.0, &param.2, kind);
+    }
+
     self.visit_expression(body)?;
 
     let body_type = self.type_expression(body)?;
 
     self.pop_scope();
 
-    if return_type != &body_type {
-      Err(
-        response!(
-          Wrong(format!("mismatched return type, expected `{}` got `{}`", return_type, body_type)),
-          self.source.file,
-          pos
-        )
+    let return_type = self.resolve_type(&return_type, &pos)?;
+
+    self.covers.pop();
+
+    if !Self::fits(&return_type, &body_type) {
+      fail!(
+        "E0403" => Wrong(format!("mismatched return type, expected `{}` got `{}`", return_type, body_type)),
+        self.source.file,
+        pos
       )
     } else {
       Ok(())
@@ -745,45 +1064,82 @@ impl<'v> Visitor<'v> {
 
 
 
-  fn visit_variable(&mut self, variable: &'v StatementNode) -> Result<(), ()> {
+  fn visit_variable(&mut self, variable: &'v Statement<'v>) -> Result<(), ()> {
     use self::ExpressionNode::*;
 
-    if let &StatementNode::Variable(ref variable_type, ref name, ref right) = variable {
-      let index = if let Some((index, _)) = self.current_tab().0.get_name(name) {
-        
+    if let StatementNode::Variable(ref variable_type, ref name, ref right, ref position) = variable.node {
+      let variable_type = &self.resolve_type(variable_type, &Self::annotation(&variable.pos))?;
+
+      let index = if let Some((index, 0)) = self.current_tab().0.get_name(name) {
+
         index
       } else {
         self.current_tab().1.grow();
         self.current_tab().0.add_name(name)
       };
 
-      if let &Some(ref right) = right {
-        let right_type = self.type_expression(&right)?;
+      // before the value, functions may call themselves
+      self.site(name, position);
+
+      if let Some(right) = right {
+        let right_type = self.type_expression(right)?;
 
         match right.node {
-          Function(..) | Block(_) | If(..) => (),
+          Function(..) | Block(_) | If(..) | While(..) => (),
           _ => self.visit_expression(right)?,
         }
 
         if variable_type.node != TypeNode::Nil {
-          if !variable_type.node.check_expression(&Parser::fold_expression(right)?.node) && variable_type.node != right_type.node {
-            return Err(
-              response!(
-                Wrong(format!("mismatched types, expected type `{}` got `{}`", variable_type.node, right_type)),
+          // a generic function is instantiated by the annotation, `f: (int) -> int = id`
+          let instantiates = match right_type.node {
+            TypeNode::Func(_, _, ref generics, _) if !generics.is_empty() => Self::unify(&right_type, variable_type, generics, &mut HashMap::new()).is_ok(),
+            _                                                             => false,
+          };
+
+          if !instantiates && !self.fills(variable_type, right)? {
+            return reject(
+              diagnostic!(
+                "E0401" => Wrong(format!("mismatched types, expected type `{}` got `{}`", variable_type, right_type)),
                 self.source.file,
                 right.pos
-              )
+              ).label(&self.source.file, &Self::annotation(&variable.pos), format!("expected `{}` because of this annotation", variable_type))
             )
           } else {
             self.current_tab().1.set_type(index, 0, variable_type.to_owned())?;
           }
 
         } else {
-          self.current_tab().1.set_type(index, 0, right_type)?;
+          if right.node == Nil {
+            return reject(
+              diagnostic!(
+                "E0505" => Wrong("can't infer a type from `nil`"),
+                self.source.file,
+                right.pos
+              ).help(format!("annotate `{}` with an optional type, like `{}: int? = nil`", name, name))
+            )
+          }
+
+          let mode = match (&variable_type.mode, &right_type.mode) {
+            (&TypeMode::Immutable, &TypeMode::Optional) => return reject(
+              diagnostic!(
+                "E0506" => Wrong(format!("constant `{}` can't be optional", name)),
+                self.source.file,
+                right.pos
+              ).help("unwrap the value with `*` or give it a default with `else` first")
+            ),
+
+            (&TypeMode::Immutable, _) => TypeMode::Immutable,
+
+            // constness stays with the binding, not with copies of its value
+            (_, &TypeMode::Immutable) => TypeMode::Regular,
+            (_, mode)                 => mode.clone(),
+          };
+
+          self.current_tab().1.set_type(index, 0, Type::new(right_type.node, mode))?;
         }
 
         match right.node {
-          Function(..) | Block(_) | If(..) => self.visit_expression(right)?,
+          Function(..) | Block(_) | If(..) | While(..) => self.visit_expression(right)?,
           _ => (),
         }
 
@@ -791,6 +1147,11 @@ impl<'v> Visitor<'v> {
         self.current_tab().1.set_type(index, 0, variable_type.to_owned())?;
       }
 
+      let t = self.current_tab().1.get_type(index, 0)?;
+
+      self.refer(name, position, &t);
+      self.current_tab().0.cure(name);
+
       Ok(())
     } else {
       unreachable!()
@@ -808,8 +1169,9 @@ impl<'v> Visitor<'v> {
         self.type_expression(expression)?
       } else {
         Type::from(TypeNode::Nil)
-      }
-      _ => Type::from(TypeNode::Nil)
+      },
+      Error => return Err(()),
+      _     => Type::from(TypeNode::Nil)
     };
 
     Ok(t)
@@ -818,32 +1180,102 @@ impl<'v> Visitor<'v> {
 
 
   pub fn type_expression(&mut self, expression: &'v Expression<'v>) -> Result<Type<'v>, ()> {
+    let t = self.expression_type(expression)?;
+
+    self.record(expression, &t);
+
+    Ok(t)
+  }
+
+
+
+  // a generic or splat body is typed once per instantiation and lowered once, where those disagree it keeps the type parameter
+  fn record(&mut self, expression: &'v Expression<'v>, t: &Type<'v>) {
+    let key = expression as *const Expression<'v>;
+
+    let t = match (self.types.get(&key), self.covers.last()) {
+      // what the body was typed as by itself gives way to its first instantiation
+      (Some(recorded), Some(covers)) if recorded.node != t.node && !covers.is_empty() && !Self::is_parameter(&recorded.node) => {
+        match covers.iter().find(|&(_, cover)| cover.node == t.node) {
+          Some((name, _)) => Type::new(TypeNode::Id(name.clone()), t.mode.clone()),
+          None            => t.clone(),
+        }
+      },
+
+      _ => t.clone(),
+    };
+
+    // calls giving a splat different numbers of values leave it to be counted at runtime
+    let t = match (self.types.get(&key), &t.mode) {
+      (Some(recorded), &TypeMode::Splat(Some(_))) if recorded.mode != t.mode => Type::new(t.node.clone(), TypeMode::Splat(None)),
+      _                                                                      => t,
+    };
+
+    self.types.insert(key, t);
+  }
+
+  fn expression_type(&mut self, expression: &'v Expression<'v>) -> Result<Type<'v>, ()> {
     use self::ExpressionNode::*;
 
     let t = match expression.node {
+      Identifier(ref name) if self.current_tab().0.is_poisoned(name) => return Err(()),
+
+      Error => return Err(()),
+
       Identifier(ref name) => if let Some((index, env_index)) = self.current_tab().0.get_name(name) {
-        self.current_tab().1.get_type(index, env_index)?.clone()
+        let t = self.current_tab().1.get_type(index, env_index)?.clone();
+
+        self.refer(name, &expression.pos, &t);
+
+        t
       } else {
-        return Err(
-          response!(
-            Wrong(format!("no such value `{}` in this scope", name)),
+        return self.no_such_value(name, &expression.pos)
+      },
+
+      Unwrap(ref expr) => {
+        let t = self.type_expression(expr)?;
+
+        match t.mode {
+          TypeMode::Splat(len)     => Type::new(t.node.clone(), TypeMode::Unwrap(len)),
+          TypeMode::Optional       => Type::from(t.node.clone()),
+
+          _ => return fail!(
+            "E0501" => Wrong(format!("can't unwrap `{}`, it's neither splat nor optional", t)),
             self.source.file,
             expression.pos
           )
-        )
+        }
       },
 
-      Unwrap(ref expr) => {
-        let t = self.type_expression(&**expr)?;
+      Else(ref optional, ref default) => {
+        let optional_type = self.type_expression(optional)?;
+
+        if let TypeMode::Optional = optional_type.mode {
+          let value_type   = Type::from(optional_type.node.clone());
+          let default_type = self.type_expression(default)?;
+
+          if !value_type.node.check_expression(&Parser::fold_expression(default)?.node) && !Self::fits(&value_type, &default_type) {
+            return reject(
+              diagnostic!(
+                "E0504" => Wrong(format!("mismatched default, expected `{}` got `{}`", value_type, default_type)),
+                self.source.file,
+                default.pos
+              ).label(&self.source.file, &optional.pos, format!("expected `{}` because this is `{}`", value_type, optional_type))
+            )
+          }
 
-        if let TypeMode::Splat(ref len) = t.mode {
-          Type::new(t.node, TypeMode::Unwrap(len.unwrap()))
+          value_type
         } else {
-          unreachable!()
+          return fail!(
+            "E0503" => Wrong(format!("`{}` is never nil, there's nothing to default", optional_type)),
+            self.source.file,
+            optional.pos
+          )
         }
       },
 
       Empty    => Type::from(TypeNode::Nil),
+      Nil      => Type::from(TypeNode::Nil),
 
       Str(_)   => Type::from(TypeNode::Str),
       Char(_)  => Type::from(TypeNode::Char),
@@ -851,96 +1283,251 @@ impl<'v> Visitor<'v> {
       Int(_)   => Type::from(TypeNode::Int),
       Float(_) => Type::from(TypeNode::Float),
 
-      Call(ref expression, _) => {
-        if let TypeNode::Func(_, ref return_type, ..) = self.type_expression(expression)?.node {
-          (**return_type).clone()
-        } else {
-          panic!("accident (submit an issue): called {:#?}", self.type_expression(expression)?.node)
+      Call(ref called, ref args, ref types) => self.type_call(called, args, types, &expression.pos, false)?,
+
+      Index(ref array, ref index) => {
+        let indexed = self.present(array)?;
+
+        match indexed.node {
+          TypeNode::Array(ref t, _) => {
+            self.type_expression(index)?;
+
+            (**t).clone()
+          },
+
+          ref parameter @ TypeNode::Id(_) => {
+            self.type_expression(index)?;
+
+            Type::from(parameter.clone())
+          },
+
+          _ => return fail!(
+            "E0408" => Wrong(format!("can't index `{}`", indexed)),
+            self.source.file,
+            array.pos
+          ),
         }
       },
 
-      Index(ref array, _) => if let TypeNode::Array(ref t, _) = self.type_expression(array)?.node {
-        (**t).clone()
-      } else {
-        unreachable!()
+      // the branches not giving the type are still typed, for lowering
+      If(ref condition, ref body, ref elses) => {
+        self.type_expression(condition)?;
+
+        self.push_scope();
+        self.narrow(condition)?;
+
+        let body_type = self.type_expression(body);
+
+        self.pop_scope();
+
+        let body_type = body_type?;
+
+        for (condition, body, _) in elses.iter().flatten() {
+          if let Some(ref condition) = *condition {
+            self.type_expression(condition)?;
+          }
+
+          self.push_scope();
+
+          let else_type = self.type_expression(body);
+
+          self.pop_scope();
+
+          else_type?;
+        }
+
+        body_type
+      },
+
+      While(ref condition, ref body) => {
+        self.type_expression(condition)?;
+        self.type_expression(body)?
+      },
+
+      // nothing to go by in `[]`, it only fits where an array of no length does
+      Array(ref content) => Type::array(self.element_type(content)?, content.len()),
+
+      Cast(_, ref t) => Type::from(self.resolve_type(t, &expression.pos)?.node),
+
+      Struct(ref name, ref fields) => {
+        let mut field_types = Vec::new();
+
+        for (field, kind, value) in fields {
+          field_types.push((field.clone(), self.resolve_type(kind, &expression.pos)?, value.is_some()))
+        }
+
+        Type::from(TypeNode::StructDef(name.clone(), Rc::new(field_types)))
       },
 
-      If(_, ref expression, _) => self.type_expression(expression)?,
+      Construct(ref left, _) => {
+        let left_type = self.type_expression(left)?;
+
+        if let TypeNode::StructDef(ref name, ref fields) = left_type.node {
+          Type::from(TypeNode::Struct(name.clone(), fields.clone()))
+        } else {
+          return fail!(
+            "E0410" => Wrong(format!("can't construct `{}`, not a struct", left_type)),
+            self.source.file,
+            left.pos
+          )
+        }
+      },
 
-      Array(ref content) => Type::array(self.type_expression(content.first().unwrap())?, content.len()),
+      Access(ref left, ref field) => {
+        let left_type = self.present(left)?;
 
-      Cast(_, ref t) => Type::from(t.node.clone()),
+        if let TypeNode::Struct(ref name, ref fields) = left_type.node {
+          match fields.iter().find(|f| &f.0 == field) {
+            Some(f) => f.1.clone(),
+            None    => return fail!(
+              "E0302" => Wrong(format!("no field `{}` on `{}`", field, name)),
+              self.source.file,
+              expression.pos
+            )
+          }
+        } else if let TypeNode::Module(ref name, ref exports) = left_type.node {
+          match exports.iter().find(|e| &e.0 == field) {
+            Some(e) => e.1.clone(),
+            None    => return fail!(
+              "E0303" => Wrong(format!("module `{}` has no `{}`", name, field)),
+              self.source.file,
+              expression.pos
+            )
+          }
+        } else if Self::is_parameter(&left_type.node) {
+          Type::from(left_type.node.clone())
+        } else {
+          return fail!(
+            "E0411" => Wrong(format!("can't access field `{}` on `{}`", field, left_type)),
+            self.source.file,
+            left.pos
+          )
+        }
+      },
 
       Binary(ref left, ref op, ref right) => {
         use self::Operator::*;
 
-        match (self.type_expression(left)?.node, op, self.type_expression(right)?.node) {
+        let is_nil_check = match *op {
+          Eq | NEq => [left, right].iter().any(|side| side.node == Nil),
+          _        => false,
+        };
+
+        // only optionals are compared to nil, everything else wants values that are there
+        if is_nil_check {
+          let other = if left.node == Nil { right } else { left };
+          let other_type = self.type_expression(other)?;
+
+          let may_be_nil = match other_type.mode {
+            TypeMode::Optional => true,
+            _                  => other_type.node == TypeNode::Nil,
+          };
+
+          if !may_be_nil {
+            return fail!(
+              "E0503" => Wrong(format!("`{}` is never nil, comparing it to `nil` is pointless", other_type)),
+              self.source.file,
+              other.pos
+            )
+          }
+
+          return Ok(Type::from(TypeNode::Bool))
+        }
+
+        match (self.present(left)?.node, op, self.present(right)?.node) {
           (ref a, ref op, ref b) => match **op {
-            Add | Sub | Mul | Div | Pow | Mod => if [a, b] != [&TypeNode::Nil, &TypeNode::Nil] { // real hack here
-              Type::from(if a != &TypeNode::Nil { a.to_owned() } else { b.to_owned() })
-            } else {
-              return Err(
-                response!(
-                  Wrong(format!("can't perform operation `{} {} {}`", a, op, b)),
+            // nothing is known of `any` to work with
+            _ if *a == TypeNode::Any || *b == TypeNode::Any => return self.operation_mismatch(expression, (left, a), (right, b), format!("can't perform operation `{} {} {}`", a, op, b)),
+
+            // a body is lowered once, it can't round down for `int` and not for `float`
+            Div | Pow if Self::is_parameter(a) || Self::is_parameter(b) => {
+              let parameter = if Self::is_parameter(a) { a } else { b };
+
+              return reject(
+                diagnostic!(
+                  "E0415" => Wrong(format!("can't perform operation `{} {} {}`, whether it rounds down depends on what `{}` is", a, op, b, parameter)),
                   self.source.file,
                   expression.pos
-                )
+                ).help("cast the operands with `as int` or `as float` first, or give the parameters concrete types")
               )
             },
 
+            // left to the instantiations, standing in for what they give meanwhile
+            Eq | Lt | Gt | NEq | LtEq | GtEq | And | Or if Self::is_parameter(a) || Self::is_parameter(b) => Type::from(TypeNode::Bool),
+            Concat                                      if Self::is_parameter(a) || Self::is_parameter(b) => Type::from(TypeNode::Str),
+
+            _ if Self::is_parameter(a) => Type::from(a.to_owned()),
+            _ if Self::is_parameter(b) => Type::from(b.to_owned()),
+
+            // an `int` with a `float` is a `float`, what the result rounds like depends on it
+            Add | Sub | Mul | Div | Pow | Mod => match (a, b) {
+              (&TypeNode::Int, &TypeNode::Int) => Type::from(TypeNode::Int),
+
+              (&TypeNode::Int, &TypeNode::Float) | (&TypeNode::Float, &TypeNode::Int) | (&TypeNode::Float, &TypeNode::Float) => Type::from(TypeNode::Float),
+
+              _ => return self.operation_mismatch(expression, (left, a), (right, b), format!("can't perform operation `{} {} {}`", a, op, b))
+            },
+
             Concat => if *a == TypeNode::Str {
               match *b {
-                TypeNode::Func(..) | TypeNode::Array(..) => return Err(
-                  response!(
-                    Wrong(format!("can't perform operation `{} {} {}`", a, op, b)),
-                    self.source.file,
-                    expression.pos
-                  )
-                ),
+                TypeNode::Func(..) | TypeNode::Array(..) => return self.operation_mismatch(expression, (left, a), (right, b), format!("can't perform operation `{} {} {}`", a, op, b)),
 
                 _ => Type::from(TypeNode::Str)
               }
             } else {
-              return Err(
-                response!(
-                  Wrong(format!("can't perform operation `{} {} {}`", a, op, b)),
-                  self.source.file,
-                  expression.pos
-                )
-              )
+              return self.operation_mismatch(expression, (left, a), (right, b), format!("can't perform operation `{} {} {}`", a, op, b))
             },
 
             Eq | Lt | Gt | NEq | LtEq | GtEq => if a == b {
               Type::from(TypeNode::Bool)
             } else {
-              return Err(
-                response!(
-                  Wrong(format!("can't perform operation `{} {} {}`", a, op, b)),
-                  self.source.file,
-                  expression.pos
-                )
-              )
+              return self.operation_mismatch(expression, (left, a), (right, b), format!("can't perform operation `{} {} {}`", a, op, b))
             },
 
-            _ => return Err(
-              response!(
-                Wrong(format!("can't perform operation `{} {} {}`", a, op, b)),
-                self.source.file,
-                expression.pos
-              )
-            )
+            And | Or => if *a == TypeNode::Bool && *b == TypeNode::Bool {
+              Type::from(TypeNode::Bool)
+            } else {
+              return self.operation_mismatch(expression, (left, a), (right, b), format!("can't perform operation `{} {} {}`, operands must be `bool`", a, op, b))
+            },
+
+            _ => return self.operation_mismatch(expression, (left, a), (right, b), format!("can't perform operation `{} {} {}`", a, op, b))
           },
         }
       },
 
-      Function(ref params, ref return_type, _, ref generics) => {
-        let mut param_types = Vec::new();
+      Unary(ref op, ref operand) => {
+        let operand_type = self.present(operand)?;
+
+        match (op, &operand_type.node) {
+          (&Operator::Not, &TypeNode::Bool)  => Type::from(TypeNode::Bool),
+          (&Operator::Neg, &TypeNode::Int)   => Type::from(TypeNode::Int),
+          (&Operator::Neg, &TypeNode::Float) => Type::from(TypeNode::Float),
 
-        for param in params {
-          param_types.push(param.1.clone())
+          (&Operator::Not, &TypeNode::Id(_)) => Type::from(TypeNode::Bool),
+          (&Operator::Neg, &TypeNode::Id(_)) => Type::from(operand_type.node.clone()),
+
+          _ => return fail!(
+            "E0407" => Wrong(format!("can't perform operation `{} {}`", op, operand_type)),
+            self.source.file,
+            expression.pos
+          )
         }
+      },
+
+      Function(ref params, ref return_type, _, ref generics) => {
+        let generics = generics.clone().unwrap_or(Vec::new());
+        let params   = params.iter().map(|param| param.1.clone()).collect::<Vec<_>>();
+
+        // the signature names its own type parameters, they stay as they are until a call
+        self.covers.push(Self::own_parameters(&generics));
 
-        Type::from(TypeNode::Func(param_types, Rc::new(return_type.clone()), generics.clone().unwrap_or(Vec::new()), Some(&expression.node)))
+        let resolved = self.resolve_types(&params, return_type, &expression.pos);
+
+        self.covers.pop();
+
+        let (param_types, return_type) = resolved?;
+
+        Type::from(TypeNode::Func(param_types, Rc::new(return_type), generics, Some(&expression.node)))
       },
 
       Block(ref statements) => {
@@ -950,12 +1537,17 @@ impl<'v> Visitor<'v> {
           self.flag = Some(FlagContext::Block(None))
         }
 
-        let block_type = if statements.len() > 0 {
+        let block_type = if !statements.is_empty() {
+          self.visit_expression(expression)?;
+
+          // everything below is typed in the scope the block ended with
+          self.tabs.push(self.tab_frames.last().unwrap().clone());
+
           for element in statements {
 
             match element.node {
               StatementNode::Expression(ref expression) => match expression.node {
-                Block(_) | If(..) => { self.type_expression(expression)?; },
+                Block(_) | If(..) | While(..) => { self.type_expression(expression)?; },
 
                 _ => (),
               },
@@ -964,22 +1556,20 @@ impl<'v> Visitor<'v> {
                 let flag = self.flag.clone();
 
                 if let Some(ref flag) = flag {
-                  if let &FlagContext::Block(ref consistent) = flag {
+                  if let FlagContext::Block(consistent) = flag {
 
                     let return_type = if let Some(ref return_type) = *return_type {
-                      self.type_expression(&return_type)?
+                      self.type_expression(return_type)?
                     } else {
                       Type::from(TypeNode::Nil)
                     };
 
                     if let Some(ref consistent) = *consistent {
                       if return_type != *consistent {
-                        return Err(
-                          response!(
-                            Wrong(format!("mismatched types, expected `{}` found `{}`", consistent, return_type)),
-                            self.source.file,
-                            expression.pos
-                          )
+                        return fail!(
+                          "E0401" => Wrong(format!("mismatched types, expected `{}` found `{}`", consistent, return_type)),
+                          self.source.file,
+                          expression.pos
                         )
                       }
                     } else {
@@ -993,10 +1583,6 @@ impl<'v> Visitor<'v> {
             }
           }
 
-          self.visit_expression(&expression)?;
-
-          self.tabs.push(self.tab_frames.last().unwrap().clone());
-
           let last          = statements.last().unwrap();
           let implicit_type = self.type_statement(last)?;
 
@@ -1006,12 +1592,10 @@ impl<'v> Visitor<'v> {
             if let FlagContext::Block(ref consistent) = flag {
               if let Some(ref consistent) = *consistent {
                 if implicit_type != *consistent {
-                  return Err(
-                    response!(
-                      Wrong(format!("mismatched types, expected `{}` found `{}`", consistent, implicit_type)),
-                      self.source.file,
-                      last.pos
-                    )
+                  return fail!(
+                    "E0401" => Wrong(format!("mismatched types, expected `{}` found `{}`", consistent, implicit_type)),
+                    self.source.file,
+                    last.pos
                   )
                 }
               } else {
@@ -1039,6 +1623,715 @@ impl<'v> Visitor<'v> {
 
 
 
+  // Checks a call against the called function, binding its type parameters by unifying
+  // parameter and argument types, and hands back the instantiated return type.
+  // Explicit type arguments, `foo<int>(x)`, are bound before any unification.
+  // With `check_body`, each new instantiation of a generic or splat function has its body checked.
+  fn type_call(&mut self, called: &'v Expression<'v>, args: &'v Vec<Expression<'v>>, types: &'v Vec<Type<'v>>, pos: &TokenElement<'v>, check_body: bool) -> Result<Type<'v>, ()> {
+    let called_type = self.present(called)?;
+
+    let (params, return_type, generics, func) = if let TypeNode::Func(ref params, ref return_type, ref generics, func) = called_type.node {
+      (params.clone(), (**return_type).clone(), generics.clone(), func)
+    } else if Self::is_parameter(&called_type.node) {
+      for arg in args {
+        self.type_expression(arg)?;
+      }
+
+      return Ok(Type::from(called_type.node.clone()))
+    } else {
+      return fail!(
+        "E0406" => Wrong(format!("expected function, found `{}`", called_type)),
+        self.source.file,
+        called.pos
+      )
+    };
+
+    // arguments with unwrapped splats spread out, the expression is kept for literals
+    let mut given: Vec<(Type<'v>, Option<&'v Expression<'v>>, TokenElement<'v>)> = Vec::new();
+
+    // a splat of unknown length may fill any number of parameters, so only its type is checked
+    let mut open = false;
+
+    for arg in args {
+      let arg_type = self.type_expression(arg)?;
+
+      match arg_type.mode {
+        TypeMode::Unwrap(Some(len)) => for _ in 0 .. len {
+          given.push((Type::from(arg_type.node.clone()), None, arg.pos.clone()))
+        },
+
+        TypeMode::Unwrap(None) => if !open {
+          open = true;
+
+          given.push((Type::from(arg_type.node.clone()), None, arg.pos.clone()))
+        },
+
+        _ => given.push((arg_type, Some(arg), arg.pos.clone())),
+      }
+    }
+
+    let is_splat = params.last().is_some_and(|param| matches!(param.mode, TypeMode::Splat(_)));
+    let fixed    = if is_splat { params.len() - 1 } else { params.len() };
+
+    let known = given.len() - open as usize;
+
+    if (known < fixed && !open) || (!is_splat && known > fixed) {
+      return fail!(
+        "E0405" => Wrong(format!("{} arguments, expected {} got {}", if given.len() < fixed { "too few" } else { "too many" }, fixed, given.len())),
+        self.source.file,
+        pos
+      )
+    }
+
+    let mut covers = if types.is_empty() {
+      HashMap::new()
+    } else {
+      let name = Self::path_of(called).unwrap_or_else(|| format!("{}", called_type));
+
+      self.type_arguments(&name, &generics, types, pos)?
+    };
+
+    for (i, (arg_type, arg, arg_pos)) in given.iter().enumerate() {
+      let param = match params.get(if i < fixed { i } else { fixed }) {
+        Some(param) => param,
+        None        => break, // an open splat with nothing left to fill
+      };
+
+      if let Some(arg) = *arg {
+        if Self::substitute(param, &covers).node.check_expression(&Parser::fold_expression(arg)?.node) {
+          continue
+        }
+      }
+
+      match (&param.mode, &arg_type.mode) {
+        (&TypeMode::Optional, _) if arg_type.node == TypeNode::Nil => continue,
+        (&TypeMode::Optional, _) | (_, &TypeMode::Optional) if !Self::fits(&Self::substitute(param, &covers), arg_type) && !Self::is_generic(param, &generics) => {
+          return fail!(
+            "E0404" => Wrong(format!("mismatched argument, expected `{}` got `{}`", Self::substitute(param, &covers), arg_type)),
+            self.source.file,
+            arg_pos
+          )
+        },
+
+        _ => (),
+      }
+
+      if let Err(message) = Self::unify(param, arg_type, &generics, &mut covers) {
+        return fail!(
+          "E0404" => Wrong(format!("mismatched argument, {}", message)),
+          self.source.file,
+          arg_pos
+        )
+      }
+    }
+
+    for name in &generics {
+      if !covers.contains_key(name) && Self::mentions(&return_type, name) {
+        return fail!(
+          "E0412" => Wrong(format!("can't infer type parameter `{}` of `{}`", name, called_type)),
+          self.source.file,
+          pos
+        )
+      }
+    }
+
+    if check_body && (!generics.is_empty() || is_splat) {
+      if let Some(ExpressionNode::Function(params, return_type, body, generics)) = func {
+        let splat_len = if open { None } else { Some(given.len() - fixed) };
+
+        let mut instance = covers.iter().map(|(name, t)| format!("{}={}", name, t)).collect::<Vec<_>>();
+
+        instance.sort();
+
+        let key = format!("{:p}<{}>..{:?}", body, instance.join(","), splat_len);
+
+        // inserted before checking, so recursion ends here
+        if !self.instances.contains(&format!("{:p}", body)) && self.instances.insert(key) {
+          self.visit_function(pos.clone(), params, return_type, body, generics, Some(covers.clone()), splat_len)?
+        }
+      }
+    }
+
+    Ok(Self::substitute(&return_type, &covers))
+  }
+
+
+
+  // binds type parameters in `param` to what they are in `arg`, telling which one doesn't fit
+  fn unify(param: &Type<'v>, arg: &Type<'v>, generics: &[String], covers: &mut HashMap<String, Type<'v>>) -> Result<(), String> {
+    use self::TypeNode::*;
+
+    match (&param.node, &arg.node) {
+      (Id(name), _) if generics.contains(name) => {
+        let arg = Type::from(arg.node.clone());
+
+        if let Some(cover) = covers.get(name) {
+          return if cover.node == arg.node {
+            Ok(())
+          } else {
+            Err(format!("type parameter `{}` is `{}` already, got `{}`", name, cover, arg))
+          }
+        }
+
+        covers.insert(name.clone(), arg);
+
+        Ok(())
+      },
+
+      (Any, _) => Ok(()),
+
+      // a type parameter of the body being checked by itself, known only in its instantiations
+      (_, Id(_)) | (Id(_), _) => Ok(()),
+
+      (Array(a, a_len), Array(b, b_len)) if a_len == b_len => Self::unify(a, b, generics, covers).map_err(|message| format!("{}, in `{}`", message, arg)),
+
+      (Func(a_params, a_return, ..), Func(b_params, b_return, ..)) if a_params.len() == b_params.len() => {
+        for (a, b) in a_params.iter().zip(b_params.iter()) {
+          Self::unify(a, b, generics, covers).map_err(|message| format!("{}, in `{}`", message, arg))?
+        }
+
+        Self::unify(a_return, b_return, generics, covers).map_err(|message| format!("{}, in `{}`", message, arg))
+      },
+
+      (a, b) => if a == b {
+        Ok(())
+      } else {
+        Err(format!("expected `{}` got `{}`", Self::substitute(param, covers).node, b))
+      }
+    }
+  }
+
+
+
+  // replaces covered type parameters wherever they are
+  fn substitute(t: &Type<'v>, covers: &HashMap<String, Type<'v>>) -> Type<'v> {
+    use self::TypeNode::*;
+
+    let node = match t.node {
+      Id(ref name) => match covers.get(name) {
+        Some(cover) => cover.node.clone(),
+        None        => t.node.clone(),
+      },
+
+      Array(ref content, len) => Array(Rc::new(Self::substitute(content, covers)), len),
+
+      Func(ref params, ref return_type, ref generics, func) => Func(
+        params.iter().map(|param| Self::substitute(param, covers)).collect(),
+        Rc::new(Self::substitute(return_type, covers)),
+        generics.clone(),
+        func
+      ),
+
+      _ => t.node.clone(),
+    };
+
+    Type::new(node, t.mode.clone())
+  }
+
+
+
+  fn is_generic(t: &Type<'v>, generics: &[String]) -> bool {
+    if let TypeNode::Id(ref name) = t.node {
+      generics.contains(name)
+    } else {
+      false
+    }
+  }
+
+
+
+  // whether a value of type `found` can be stored where `expected` is wanted
+  fn fits(expected: &Type<'v>, found: &Type<'v>) -> bool {
+    if expected.node == TypeNode::Any || Self::is_parameter(&expected.node) || Self::is_parameter(&found.node) {
+      return true
+    }
+
+    match (&expected.mode, &found.mode) {
+      (&TypeMode::Optional, _)  => found.node == TypeNode::Nil || expected.node == found.node,
+      (_, &TypeMode::Optional)  => false,
+      _                         => expected.node == found.node,
+    }
+  }
+
+
+
+  // what the elements of an array literal have in common, an `int` with a `float` is a `float` and a `nil` among them makes it optional
+  fn element_type(&mut self, content: &'v [Expression<'v>]) -> Result<Type<'v>, ()> {
+    use self::TypeNode::*;
+
+    let mut common: Option<Type<'v>> = None;
+    let mut optional = false;
+
+    for element in content {
+      let t = self.type_expression(element)?;
+
+      if t.node == Nil {
+        optional = true;
+
+        continue
+      }
+
+      optional |= t.mode == TypeMode::Optional;
+
+      let node = match common {
+        None                                           => t.node,
+        Some(ref c) if c.node == t.node                => t.node,
+        Some(ref c) if c.node == Any                   => Any,
+        Some(ref c) if Self::is_parameter(&c.node)     => c.node.clone(),
+        Some(ref c) if Self::is_parameter(&t.node)     => t.node,
+        Some(ref c) if [c.node.clone(), t.node.clone()].iter().all(|node| *node == Int || *node == Float) => Float,
+
+        Some(c) => if c.node.check_expression(&Parser::fold_expression(element)?.node) {
+          c.node
+        } else {
+          return fail!(
+            "E0401" => Wrong(format!("mismatched types in array, expected `{}` got `{}`", c, t)),
+            self.source.file,
+            element.pos
+          )
+        },
+      };
+
+      common = Some(Type::from(node))
+    }
+
+    let node = common.map_or(Nil, |common| common.node);
+
+    Ok(if optional && node != Nil { Type::new(node, TypeMode::Optional) } else { Type::from(node) })
+  }
+
+  // a value fits an annotation when its type does, or, for literals, when each part of it does, `[nil, 1]` fits `[int?; 2]`
+  fn fills(&mut self, expected: &Type<'v>, value: &'v Expression<'v>) -> Result<bool, ()> {
+    if let (TypeNode::Array(t, len), ExpressionNode::Array(content)) = (&expected.node, &value.node) {
+      if *len == content.len() && !content.is_empty() {
+        for element in content {
+          if !self.fills(t, element)? {
+            return Ok(false)
+          }
+        }
+
+        return Ok(true)
+      }
+    }
+
+    let found = self.type_expression(value)?;
+
+    Ok(expected.node.check_expression(&Parser::fold_expression(value)?.node) || Self::fits(expected, &found))
+  }
+
+  // a type parameter of a body checked by itself, what it allows is only known for each instantiation
+  fn is_parameter(node: &TypeNode<'v>) -> bool {
+    matches!(*node, TypeNode::Id(_))
+  }
+
+
+
+  // types an expression that is used as a value, which an optional can't be before it's unwrapped
+  fn present(&mut self, expression: &'v Expression<'v>) -> Result<Type<'v>, ()> {
+    let t = self.type_expression(expression)?;
+
+    if let TypeMode::Optional = t.mode {
+      reject(
+        diagnostic!(
+          "E0502" => Wrong(format!("`{}` may be nil", t)),
+          self.source.file,
+          expression.pos
+        ).help("unwrap it with `*` or give it a default with `else`")
+      )
+    } else {
+      Ok(t)
+    }
+  }
+
+
+
+  // points out the type of each operand, which is usually where the mistake is
+  fn operation_mismatch<T>(&self, expression: &Expression<'v>, left: (&Expression<'v>, &TypeNode), right: (&Expression<'v>, &TypeNode), message: String) -> Result<T, ()> {
+    reject(
+      diagnostic!(
+        "E0407" => Wrong(message),
+        self.source.file,
+        expression.pos
+      )
+      .label(&self.source.file, &left.0.pos, format!("this is `{}`", left.1))
+      .label(&self.source.file, &right.0.pos, format!("this is `{}`", right.1))
+    )
+  }
+
+
+
+  // the annotation of `name: type = value`, the variable's position starts right at it
+  fn annotation(pos: &TokenElement<'v>) -> TokenElement<'v> {
+    match *pos {
+      TokenElement::Pos(line, (start, end)) => {
+        // columns count characters
+        let text = line.1.chars().skip(start.saturating_sub(1)).take((end + 1).saturating_sub(start)).collect::<String>();
+        let len  = text.find(['=', ':']).map(|i| text[.. i].trim_end().chars().count()).unwrap_or(text.chars().count());
+
+        TokenElement::Pos(line, (start, start + len.max(1) - 1))
+      },
+
+      TokenElement::Span(line, start, ..) => Self::annotation(&TokenElement::Pos(line, (start, line.1.chars().count()))),
+
+      ref other => other.clone(),
+    }
+  }
+
+
+
+  // inside `if a != nil and b != nil { .. }`, `a` and `b` are known to be there
+  fn narrow(&mut self, condition: &'v Expression<'v>) -> Result<(), ()> {
+    use self::ExpressionNode::*;
+
+    match condition.node {
+      Binary(ref left, Operator::And, ref right) => {
+        self.narrow(left)?;
+        self.narrow(right)
+      },
+
+      Binary(ref left, Operator::NEq, ref right) => {
+        let name = match (&left.node, &right.node) {
+          (&Identifier(ref name), &Nil) | (&Nil, &Identifier(ref name)) => name,
+          _                                                             => return Ok(()),
+        };
+
+        if let Some((index, env_index)) = self.current_tab().0.get_name(name) {
+          let t = self.current_tab().1.get_type(index, env_index)?;
+
+          if let TypeMode::Optional = t.mode {
+            self.declare(name, Type::from(t.node))?;
+            self.current_tab().0.narrow(name);
+          }
+        }
+
+        Ok(())
+      },
+
+      _ => Ok(()),
+    }
+  }
+
+
+
+  fn mentions(t: &Type<'v>, name: &str) -> bool {
+    use self::TypeNode::*;
+
+    match t.node {
+      Id(ref id)                                 => id == name,
+      Array(ref content, _)                      => Self::mentions(content, name),
+      Func(ref params, ref return_type, ..)      => params.iter().any(|param| Self::mentions(param, name)) || Self::mentions(return_type, name),
+      _                                          => false,
+    }
+  }
+
+
+
+  fn declare(&mut self, name: &str, t: Type<'v>) -> Result<(), ()> {
+    let index = if let Some((index, 0)) = self.current_tab().0.get_name(name) {
+      index
+    } else {
+      self.current_tab().1.grow();
+      self.current_tab().0.add_name(name)
+    };
+
+    self.current_tab().1.set_type(index, 0, t)
+  }
+
+
+
+  // `extern math.tau: float` adds to `math`, or makes it a module of its own
+  fn extend_module(existing: Option<Type<'v>>, name: &str, path: &[String], member: Type<'v>) -> Type<'v> {
+    let mut exports = match existing {
+      Some(Type { node: TypeNode::Module(_, ref exports), .. }) => (**exports).clone(),
+      _                                                        => Vec::new(),
+    };
+
+    let position = exports.iter().position(|e| e.0 == path[0]);
+
+    let member = if path.len() == 1 {
+      member
+    } else {
+      Self::extend_module(position.map(|i| exports[i].1.clone()), &path[0], &path[1 ..], member)
+    };
+
+    match position {
+      Some(i) => exports[i].1 = member,
+      None    => exports.push((path[0].clone(), member)),
+    }
+
+    Type::from(TypeNode::Module(name.to_string(), Rc::new(exports)))
+  }
+
+
+
+  // `a.b.c` as written, the generator emits the same text for it
+  fn path_of(expression: &Expression) -> Option<String> {
+    match expression.node {
+      ExpressionNode::Identifier(ref name)   => Some(name.clone()),
+      ExpressionNode::Access(ref left, ref field) => Self::path_of(left).map(|left| format!("{}.{}", left, field)),
+      _                                      => None,
+    }
+  }
+
+
+
+  // checks the module a `import a.b` points at, relative to the importing file, once per compile
+  fn import(&mut self, path: &[String], pos: &TokenElement<'v>) -> Result<Type<'v>, ()> {
+    let directory = Path::new(&self.source.file.0).parent().unwrap_or(Path::new(""));
+    let file      = directory.join(format!("{}.wu", path.join("/")));
+    let key       = file.display().to_string();
+
+    let cached = self.modules.borrow().get(&key).cloned();
+
+    match cached {
+      Some(Some(module)) => return Ok(module),
+      Some(None)         => return fail!(
+        "E0305" => Wrong(format!("cyclic import of `{}`", path.join("."))),
+        self.source.file,
+        pos
+      ),
+
+      None => (),
+    }
+
+    let mut content = String::new();
+
+    if File::open(&file).and_then(|mut f| f.read_to_string(&mut content)).is_err() {
+      return fail!(
+        "E0304" => Wrong(format!("can't find module `{}` at `{}`", path.join("."), file.display())),
+        self.source.file,
+        pos
+      )
+    }
+
+    self.modules.borrow_mut().insert(key.clone(), None);
+
+    let exports = match Self::check_file(&key, &content, true, self.modules.clone()) {
+      Ok(exports) => exports,
+
+      // what's wrong with it is reported, the next import of it checks it again and reports the same
+      Err(()) => {
+        self.modules.borrow_mut().remove(&key);
+
+        return Err(())
+      },
+    };
+
+    let module = Type::from(TypeNode::Module(path.last().unwrap().clone(), Rc::new(exports)));
+
+    self.modules.borrow_mut().insert(key, Some(module.clone()));
+
+    Ok(module)
+  }
+
+
+
+  // reports `name` as not found, suggesting a visible name it might be a typo of
+  fn no_such_value<T>(&mut self, name: &str, pos: &TokenElement<'v>) -> Result<T, ()> {
+    let mut diagnostic = diagnostic!(
+      "E0301" => Wrong(format!("no such value `{}` in this scope", name)),
+      self.source.file,
+      pos
+    );
+
+    if let Some(similar) = similar(name, self.current_tab().0.visible_names().iter()) {
+      diagnostic = diagnostic.help(format!("did you mean `{}`?", similar))
+    }
+
+    reject(diagnostic)
+  }
+
+  // built-in types, type parameters in scope and visible structs
+  fn type_names(&mut self) -> Vec<String> {
+    let mut names = ["int", "float", "bool", "str", "char", "any"].iter().map(|name| name.to_string()).collect::<Vec<String>>();
+
+    for covers in &self.covers {
+      names.extend(covers.keys().cloned())
+    }
+
+    for name in self.current_tab().0.visible_names() {
+      if let Some(Type { node: TypeNode::StructDef(..), .. }) = self.lookup_type(&name) {
+        names.push(name)
+      }
+    }
+
+    names
+  }
+
+
+
+  fn lookup_type(&mut self, name: &str) -> Option<Type<'v>> {
+    use self::TypeNode::*;
+
+    let mut path = name.split('.');
+
+    let mut found = match self.current_tab().0.get_name(path.next().unwrap()) {
+      Some((index, env_index)) => self.current_tab().1.get_type(index, env_index).ok(),
+      None                     => None,
+    };
+
+    for segment in path {
+      found = match found {
+        Some(Type { node: Module(_, ref exports), .. }) => exports.iter().find(|e| e.0 == segment).map(|e| e.1.clone()),
+        _                                               => None,
+      }
+    }
+
+    found
+  }
+
+
+
+  fn resolve_types(&mut self, params: &[Type<'v>], return_type: &Type<'v>, pos: &TokenElement<'v>) -> Result<(Vec<Type<'v>>, Type<'v>), ()> {
+    let mut param_types = Vec::new();
+
+    for param in params {
+      param_types.push(self.resolve_type(param, pos)?)
+    }
+
+    Ok((param_types, self.resolve_type(return_type, pos)?))
+  }
+
+  // covers mapping each type parameter to itself
+  fn own_parameters(generics: &[String]) -> HashMap<String, Type<'v>> {
+    generics.iter().map(|name| (name.clone(), Type::id(name))).collect()
+  }
+
+  // turns annotated names of declared structs into the struct types they refer to
+  pub fn resolve_type(&mut self, t: &Type<'v>, pos: &TokenElement<'v>) -> Result<Type<'v>, ()> {
+    use self::TypeNode::*;
+
+    let node = match t.node {
+      Id(ref name) => if let Some(cover) = self.covers.iter().rev().filter_map(|covers| covers.get(name)).next() {
+        cover.node.clone()
+      } else {
+        match self.lookup_type(name) {
+          Some(Type { node: StructDef(ref name, ref fields), .. }) => Struct(name.clone(), fields.clone()),
+
+          Some(found) => return fail!(
+            "E0313" => Wrong(format!("expected type, found `{}` of type `{}`", name, found)),
+            self.source.file,
+            pos
+          ),
+
+          // from a module that couldn't be imported, which was reported already
+          None if name.contains('.') && self.current_tab().0.is_poisoned(name.split('.').next().unwrap()) => return Err(()),
+
+          None => {
+            let mut diagnostic = diagnostic!(
+              "E0312" => Wrong(format!("no such type `{}`", name)),
+              self.source.file,
+              pos
+            );
+
+            if let Some(similar) = similar(name, self.type_names().iter()) {
+              diagnostic = diagnostic.help(format!("did you mean `{}`?", similar))
+            }
+
+            return reject(diagnostic)
+          },
+        }
+      },
+
+      Generic(ref name, ref args) => {
+        let found = self.lookup_type(name);
+
+        let (params, return_type, generics, func) = match found {
+          Some(Type { node: Func(ref params, ref return_type, ref generics, func), .. }) if !generics.is_empty() => (params.clone(), return_type.clone(), generics.clone(), func),
+
+          Some(found) => return fail!(
+            "E0413" => Wrong(format!("`{}` of type `{}` takes no type arguments", name, found)),
+            self.source.file,
+            pos
+          ),
+
+          None => return fail!(
+            "E0306" => Wrong(format!("no such generic function `{}`", name)),
+            self.source.file,
+            pos
+          ),
+        };
+
+        let covers = self.type_arguments(name, &generics, args, pos)?;
+
+        Func(
+          params.iter().map(|param| Self::substitute(param, &covers)).collect(),
+          Rc::new(Self::substitute(&return_type, &covers)),
+          Vec::new(),
+          func
+        )
+      },
+
+      Array(ref content, len) => Array(Rc::new(self.resolve_type(content, pos)?), len),
+
+      Func(ref params, ref return_type, ref generics, func) => {
+        self.covers.push(Self::own_parameters(generics));
+
+        let resolved = self.resolve_types(params, return_type, pos);
+
+        self.covers.pop();
+
+        let (param_types, return_type) = resolved?;
+
+        Func(param_types, Rc::new(return_type), generics.clone(), func)
+      },
+
+      _ => t.node.clone(),
+    };
+
+    Ok(Type::new(node, t.mode.clone()))
+  }
+
+
+
+  // pairs explicit type arguments with the type parameters they are given for
+  fn type_arguments(&mut self, name: &str, generics: &[String], args: &[Type<'v>], pos: &TokenElement<'v>) -> Result<HashMap<String, Type<'v>>, ()> {
+    if generics.len() != args.len() {
+      return fail!(
+        "E0413" => Wrong(format!("`{}` takes {} type argument{}, got {}", name, generics.len(), if generics.len() == 1 { "" } else { "s" }, args.len())),
+        self.source.file,
+        pos
+      )
+    }
+
+    let mut covers = HashMap::new();
+
+    for (generic, arg) in generics.iter().zip(args.iter()) {
+      let arg = self.resolve_type(arg, pos)?;
+
+      covers.insert(generic.clone(), Type::from(arg.node));
+    }
+
+    Ok(covers)
+  }
+
+
+
+  // remembers where `name` is declared in the current scope
+  fn site(&mut self, name: &str, pos: &TokenElement<'v>) {
+    if let TokenElement::Pos(line, slice) = *pos {
+      self.current_tab().0.add_site(name, (line.0, slice))
+    }
+  }
+
+  // remembers what `name` at `pos` turned out to be
+  fn refer(&mut self, name: &str, pos: &TokenElement<'v>, kind: &Type<'v>) {
+    if let TokenElement::Pos(line, slice) = *pos {
+      let site = self.current_tab().0.get_site(name);
+
+      self.references.push(
+        Reference {
+          name: name.to_string(),
+          pos:  (line.0, slice),
+          kind: kind.clone(),
+          site,
+        }
+      )
+    }
+  }
+
+
+
   pub fn current_tab(&mut self) -> &mut (SymTab, TypeTab<'v>) {
     let len = self.tabs.len() - 1;
 
@@ -0,0 +1,212 @@
+extern crate tang;
+
+use std::fs;
+use std::process::Command;
+
+mod common;
+
+use common::*;
+
+use tang::wu::parser::*;
+use tang::wu::compiler::*;
+
+
+
+// what `int` operations give, whether folded while compiling or worked out by Lua
+const CASES: &[(i64, &str, i64, i64)] = &[
+  ( 7,  "/",  2,  3),
+  (-7,  "/",  2, -4),
+  ( 7,  "/", -2, -4),
+  (-7,  "/", -2,  3),
+  ( 6,  "/",  3,  2),
+  ( 7,  "%",  3,  1),
+  (-7,  "%",  3,  2),
+  ( 7,  "%", -3, -2),
+  (-7,  "%", -3, -1),
+  ( 6,  "%",  3,  0),
+  ( 2,  "^", 10,  1024),
+  (-3,  "^",  3, -27),
+  (-3,  "^",  2,  9),
+  (-2,  "^",  4,  16),
+  ( 5,  "^",  0,  1),
+];
+
+const TARGETS: &[Target] = &[Target::Lua51, Target::Lua54, Target::LuaJIT];
+
+
+
+// what `x` is set to in the generated Lua
+fn generated_x(lua: &str) -> String {
+  lua.lines().find_map(|line| line.strip_prefix("local x = ")).expect("no `x` in the output").to_string()
+}
+
+// `-3 ^ 2` is `-(3 ^ 2)`, in Wu as in Lua
+fn operand(n: i64) -> String {
+  if n < 0 { format!("({})", n) } else { n.to_string() }
+}
+
+// what `a op b` should become on `target`, `a` being whatever was written for it
+fn expected_lua(a: &str, op: &str, b: &str, target: Target) -> String {
+  match op {
+    "/" if target == Target::Lua54 => format!("({} // {})", a, b),
+    "/"                            => format!("math.floor({} / {})", a, b),
+    "^"                            => format!("math.floor({} ^ {})", a, b),
+    _                              => format!("({} {} {})", a, op, b),
+  }
+}
+
+// the first interpreter found for `target`, there may be none around
+fn interpreter(target: Target) -> Option<&'static str> {
+  let names: &[&'static str] = match target {
+    Target::Lua51  => &["lua5.1"],
+    Target::Lua54  => &["lua5.4", "lua5.3"],
+    Target::LuaJIT => &["luajit"],
+  };
+
+  names.iter().cloned().find(|name| Command::new(name).arg("-v").output().is_ok())
+}
+
+// what `x` comes out as when `lua` runs the module
+fn run_x(lua: &str, interpreter: &str) -> i64 {
+  let path = std::env::temp_dir().join(format!("tang-arithmetic-{}.lua", std::process::id()));
+
+  fs::write(&path, lua).unwrap();
+
+  let output = Command::new(interpreter).arg("-e").arg(format!("print(dofile({:?}).x)", path.display().to_string())).output().unwrap();
+
+  fs::remove_file(&path).unwrap();
+
+  let printed = String::from_utf8_lossy(&output.stdout);
+
+  printed.trim().parse::<f64>().unwrap_or_else(|_| panic!("{} printed {:?}: {}", interpreter, printed, String::from_utf8_lossy(&output.stderr))) as i64
+}
+
+
+
+#[test]
+fn folding() {
+  for &(a, op, b, expected) in CASES {
+    let content = format!("x :: {} {} {}\n", operand(a), op, b);
+    let source  = source(&content);
+    let tokens  = tokens(&content, &source);
+
+    let ast = Parser::new(tokens.iter().collect(), &source).parse();
+
+    let folded = match ast[0].node {
+      StatementNode::Variable(_, _, Some(ref right), _) => Parser::fold_expression(right).unwrap().node,
+      _                                                 => unreachable!(),
+    };
+
+    assert_eq!(folded, ExpressionNode::Int(expected), "{} {} {}", a, op, b)
+  }
+}
+
+#[test]
+fn folded_constants_are_inlined() {
+  for &(a, op, b, expected) in CASES {
+    let lua = compile_for(&format!("x :: {} {} {}\n", operand(a), op, b), Target::Lua54);
+
+    // negative ones in parentheses, they can end up as the left of `^`
+    let literal = if expected < 0 { format!("({})", expected) } else { expected.to_string() };
+
+    assert_eq!(generated_x(&lua), literal, "{} {} {}", a, op, b)
+  }
+}
+
+#[test]
+fn generated_matches_folding() {
+  for &target in TARGETS {
+    for &(a, op, b, _) in CASES {
+      let lua = compile_for(&format!("a := {}\nb := {}\nx := a {} b\n", a, b, op), target);
+
+      assert_eq!(generated_x(&lua), expected_lua("a", op, "b", target), "{} {} {} on {:?}", a, op, b, target);
+
+      // with `a` a constant, and so inlined as a literal
+      let lua = compile_for(&format!("a :: 0 - {}\nb := {}\nx := a {} b\n", -a, b, op), target);
+
+      assert_eq!(generated_x(&lua), expected_lua(&operand(a), op, "b", target), "inlined {} {} {} on {:?}", a, op, b, target)
+    }
+  }
+}
+
+#[test]
+fn generated_runs_as_folded() {
+  for &target in TARGETS {
+    let interpreter = match interpreter(target) {
+      Some(interpreter) => interpreter,
+      None              => continue,
+    };
+
+    for &(a, op, b, expected) in CASES {
+      let lua = compile_for(&format!("a := {}\nb := {}\nx := a {} b\n", a, b, op), target);
+
+      assert_eq!(run_x(&lua, interpreter), expected, "{} {} {} on {}", a, op, b, interpreter);
+
+      let lua = compile_for(&format!("a :: 0 - {}\nb := {}\nx := a {} b\n", -a, b, op), target);
+
+      assert_eq!(run_x(&lua, interpreter), expected, "inlined {} {} {} on {}", a, op, b, interpreter)
+    }
+  }
+}
+
+#[test]
+fn division_by_target() {
+  let lua = |target| generated_x(&compile_for("a := 7\nb := 2\nx := a / b\n", target));
+
+  assert_eq!(lua(Target::Lua54),  "(a // b)");
+  assert_eq!(lua(Target::Lua51),  "math.floor(a / b)");
+  assert_eq!(lua(Target::LuaJIT), "math.floor(a / b)");
+}
+
+#[test]
+fn floats_divide_as_they_are() {
+  for &target in TARGETS {
+    assert_eq!(generated_x(&compile_for("a := 7.0\nb := 2.0\nx := a / b\n", target)), "(a / b)")
+  }
+}
+
+#[test]
+fn inexact_folds_are_left_for_runtime() {
+  // past 2^53 floats, and so 5.1 and LuaJIT, lose precision
+  assert_eq!(generated_x(&compile_for("x :: 3 ^ 40\n", Target::Lua54)), "math.floor(3 ^ 40)");
+
+  // and dividing by zero is Lua's to report
+  assert_eq!(generated_x(&compile_for("x :: 1 / 0\n", Target::Lua54)), "(1 // 0)");
+}
+
+#[test]
+fn generic_bodies_are_lowered_once() {
+  // whether to round down can't depend on a type parameter, called or not
+  assert_eq!(codes("div: def<T>(a: T, b: T) -> T { a / b }\n"), ["E0415"]);
+  assert_eq!(codes("pow: def<T>(a: T, b: int) -> T { a ^ b }\n"), ["E0415"]);
+  assert_eq!(codes("half: def<T>(a: T) -> T { a / 2 }\nx := half(7)\ny := half(7.0)\n"), ["E0415"]);
+
+  // casts say which it is
+  let lua = compile("div: def<T>(a: T, b: T) -> float { (a as float) / (b as float) }\nx := div(7, 2)\ny := div(7.0, 2.0)\n");
+
+  assert!(lua.contains("return (a / b)"), "{}", lua);
+
+  // the rest is the same for both
+  let lua = compile("add: def<T>(a: T, b: T) -> T { a + b }\nx := add(7, 2)\ny := add(7.0, 2.0)\n");
+
+  assert!(lua.contains("return (a + b)"), "{}", lua)
+}
+
+#[test]
+fn mixed_operands_are_floats() {
+  assert!(checks("x := 7 / 2.0\ny: float = x\n"));
+  assert!(!checks("x := 7 / 2.0\ny: int = x\n"));
+  assert!(!checks("x: int = 2 ^ 0.5\n"));
+
+  // and so divide as floats later on
+  let lua = compile("a := 7\nb := a / 2.0\nx := b / 2\n");
+
+  assert_eq!(generated_x(&lua), "(b / 2)")
+}
+
+#[test]
+fn only_numbers_are_arithmetic() {
+  for content in &["x := \"a\" + 1\n", "x := true * 2\n", "x := [1] - [1]\n", "f: def() {}\nx := f() + 1\n"] {
+    assert!(!checks(content), "{}", content)
+  }
+}
@@ -0,0 +1,246 @@
+extern crate tang;
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+use tang::wu::lsp::Json;
+
+
+
+// a directory of its own for each test, holding `files`
+fn project(name: &str, files: &[(&str, &str)]) -> PathBuf {
+  let directory = std::env::temp_dir().join(format!("tang-cli-{}-{}", name, std::process::id()));
+
+  let _ = fs::remove_dir_all(&directory);
+
+  fs::create_dir_all(&directory).unwrap();
+
+  for &(file, content) in files {
+    fs::write(directory.join(file), content).unwrap()
+  }
+
+  directory
+}
+
+fn tang(args: &[&str]) -> i32 {
+  run(args).0
+}
+
+fn run(args: &[&str]) -> (i32, String) {
+  let output = Command::new(env!("CARGO_BIN_EXE_tang")).args(args).output().expect("failed to run tang");
+
+  (output.status.code().unwrap(), String::from_utf8(output.stdout).unwrap())
+}
+
+// what `--message-format=json` printed for `file`, one diagnostic to a line
+fn reported(file: &str) -> Vec<Json> {
+  let (_, output) = run(&["--message-format=json", file]);
+
+  output.lines().map(|line| Json::parse(line).unwrap_or_else(|| panic!("not JSON: {}", line))).collect()
+}
+
+fn codes(reported: &[Json]) -> Vec<&str> {
+  reported.iter().filter_map(|diagnostic| diagnostic.get("code").as_str()).collect()
+}
+
+
+
+#[test]
+fn errors_fail_the_run() {
+  let directory = project("errors", &[("bad.wu", "x: int = true\n")]);
+  let file      = directory.join("bad.wu").display().to_string();
+
+  assert_eq!(tang(&[&file]), 1);
+  assert_eq!(tang(&["--message-format=json", &file]), 1);
+
+  assert!(!directory.join("bad.lua").exists());
+
+  fs::remove_dir_all(directory).unwrap()
+}
+
+#[test]
+fn one_bad_file_fails_the_directory() {
+  let directory = project("directory", &[("good.wu", "x := 1\n"), ("bad.wu", "y: int = true\n")]);
+
+  assert_eq!(tang(&[&directory.display().to_string()]), 1);
+
+  // the rest is still written
+  assert!(directory.join("good.lua").exists());
+
+  fs::remove_dir_all(directory).unwrap()
+}
+
+#[test]
+fn fine_files_pass() {
+  let directory = project("fine", &[("good.wu", "x := 1\nprint(\"x is\", x)\n")]);
+
+  assert_eq!(tang(&[&directory.join("good.wu").display().to_string()]), 0);
+  assert_eq!(tang(&["fmt", "--check", &directory.join("good.wu").display().to_string()]), 0);
+
+  fs::remove_dir_all(directory).unwrap()
+}
+
+#[test]
+fn lexer_errors_leave_the_rest_checked() {
+  let directory = project("lexing", &[("bad.wu", "x := 1 $ 2\ny: int = true\n")]);
+  let file      = directory.join("bad.wu").display().to_string();
+
+  // what the weird character throws off on its own line isn't reported on top of it
+  assert_eq!(codes(&reported(&file)), ["E0101", "E0401"]);
+  assert_eq!(tang(&[&file]), 1);
+
+  fs::remove_dir_all(directory).unwrap()
+}
+
+#[test]
+fn broken_modules_are_not_cyclic() {
+  let directory = project("broken", &[
+    ("broken.wu", "x: int = true\n"),
+    ("other.wu",  "import broken\n"),
+    ("main.wu",   "import broken\nimport other\n"),
+  ]);
+
+  assert_eq!(codes(&reported(&directory.join("main.wu").display().to_string())), vec!["E0401"]);
+
+  fs::remove_dir_all(directory).unwrap()
+}
+
+#[test]
+fn tracebacks_leave_modules_of_that_name() {
+  let directory = project("traceback", &[("main.wu", "x := 1\n")]);
+  let path      = directory.display().to_string();
+
+  assert_eq!(tang(&["--traceback", &path]), 0);
+  assert_eq!(tang(&["--traceback", &path]), 0);
+
+  fs::write(directory.join("traceback.lua"), "return 1\n").unwrap();
+
+  assert_eq!(tang(&["--traceback", &path]), 1);
+  assert_eq!(fs::read_to_string(directory.join("traceback.lua")).unwrap(), "return 1\n");
+
+  fs::remove_dir_all(directory).unwrap()
+}
+
+#[test]
+fn json_diagnostics_have_every_field() {
+  let directory = project("json", &[("bad.wu", "x: int = true\n")]);
+  let file      = directory.join("bad.wu").display().to_string();
+
+  let reported   = reported(&file);
+  let diagnostic = &reported[0];
+
+  assert_eq!(reported.len(), 1);
+
+  assert_eq!(diagnostic.get("file").as_str(), Some(file.as_str()));
+  assert_eq!(diagnostic.get("severity").as_str(), Some("wrong"));
+  assert_eq!(diagnostic.get("code").as_str(), Some("E0401"));
+  assert_eq!(diagnostic.get("message").as_str(), Some("mismatched types, expected type `int` got `bool`"));
+
+  let position = ["line", "column_start", "line_end", "column_end"].iter().map(|key| diagnostic.get(key).as_usize()).collect::<Vec<_>>();
+
+  assert_eq!(position, [Some(1), Some(10), Some(1), Some(13)]);
+
+  let label = &diagnostic.get("labels").as_array().unwrap()[0];
+
+  assert_eq!(label.get("file").as_str(), Some(file.as_str()));
+  assert_eq!(label.get("message").as_str(), Some("expected `int` because of this annotation"));
+  assert_eq!((label.get("column_start").as_usize(), label.get("column_end").as_usize()), (Some(4), Some(6)));
+
+  assert_eq!(diagnostic.get("help").as_array().map(Vec::len), Some(0));
+
+  fs::remove_dir_all(directory).unwrap()
+}
+
+#[test]
+fn json_keeps_strings_intact() {
+  let directory = project("json-strings", &[("bad.wu", "wörld := \"a \\\"b\\\"\"\nprint(wörlb)\n")]);
+  let file      = directory.join("bad.wu").display().to_string();
+
+  // plain JSON lines, nothing rendered in between
+  let (_, output) = run(&["--message-format=json", &file]);
+
+  assert!(!output.contains("\x1b["));
+
+  let diagnostic = &reported(&file)[0];
+
+  assert_eq!(diagnostic.get("message").as_str(), Some("no such value `wörlb` in this scope"));
+  assert_eq!((diagnostic.get("column_start").as_usize(), diagnostic.get("column_end").as_usize()), (Some(7), Some(11)));
+
+  let help = diagnostic.get("help").as_array().unwrap().iter().filter_map(|help| help.as_str()).collect::<Vec<_>>();
+
+  assert_eq!(help, ["did you mean `wörld`?"]);
+
+  fs::remove_dir_all(directory).unwrap()
+}
+
+#[test]
+fn lint_levels_from_the_command_line() {
+  let directory = project("lints", &[("unused.wu", "f: def() {\n  c := 1\n}\n")]);
+  let file      = directory.join("unused.wu").display().to_string();
+
+  // a warning doesn't fail the run, a denied lint does
+  assert_eq!(codes(&reported(&file)), ["W0001"]);
+
+  // compiled files are skipped
+  fs::remove_file(directory.join("unused.lua")).unwrap();
+
+  assert_eq!(tang(&["--deny=unused_variables", &file]), 1);
+
+  assert_eq!(run(&["--allow=unused_variables", "--message-format=json", &file]), (0, String::new()));
+
+  assert_eq!(tang(&["--deny=unused_variable", &file]), 1);
+
+  fs::remove_dir_all(directory).unwrap()
+}
+
+#[test]
+fn source_maps_are_written_and_cleaned() {
+  let directory = project("maps", &[("main.wu", "x := 1\n\nprint(x)\n")]);
+  let path      = directory.display().to_string();
+
+  assert_eq!(tang(&["--source-map", &path]), 0);
+
+  assert_eq!(fs::read_to_string(directory.join("main.lua.map")).unwrap(), "main.wu\n1 1\n2 1\n3 3\n4 3\n");
+
+  assert_eq!(tang(&["clean", &path]), 0);
+
+  assert!(!directory.join("main.lua").exists());
+  assert!(!directory.join("main.lua.map").exists());
+
+  fs::remove_dir_all(directory).unwrap()
+}
+
+#[test]
+fn ir_is_printed_on_request() {
+  let directory = project("ir", &[("main.wu", "x := 1\n")]);
+  let file      = directory.join("main.wu").display().to_string();
+
+  let (code, output) = run(&["--emit-ir", &file]);
+
+  assert_eq!(code, 0);
+  assert!(output.contains("   1 | local x: int = 1\nexport x\n"), "{}", output);
+
+  fs::remove_dir_all(directory).unwrap()
+}
+
+#[test]
+fn targets_from_the_command_line() {
+  let directory = project("targets", &[("main.wu", "a := 7\nb := a / 2\n")]);
+  let file      = directory.join("main.wu").display().to_string();
+
+  assert_eq!(tang(&["--target=luajit", &file]), 0);
+  assert!(fs::read_to_string(directory.join("main.lua")).unwrap().contains("local b = math.floor(a / 2)"));
+
+  fs::remove_file(directory.join("main.lua")).unwrap();
+
+  assert_eq!(tang(&["--target=5.4", &file]), 0);
+  assert!(fs::read_to_string(directory.join("main.lua")).unwrap().contains("local b = (a // 2)"));
+
+  fs::remove_file(directory.join("main.lua")).unwrap();
+
+  assert_eq!(tang(&["--target=5.2", &file]), 1);
+  assert!(!directory.join("main.lua").exists());
+
+  fs::remove_dir_all(directory).unwrap()
+}
@@ -0,0 +1,123 @@
+// running source through the phases `tang` runs it through, each test file takes what it needs
+#![allow(dead_code)]
+
+use tang::wu::error::*;
+use tang::wu::source::*;
+use tang::wu::lexer::*;
+use tang::wu::parser::*;
+use tang::wu::visitor::*;
+use tang::wu::ir::*;
+use tang::wu::compiler::*;
+
+
+
+pub fn source(content: &str) -> Source {
+  Source::from("test.wu", content.lines().map(|line| line.into()).collect())
+}
+
+pub fn tokens<'t>(content: &str, source: &'t Source) -> Vec<Token<'t>> {
+  Lexer::default(content.chars().collect(), source).map(|token| token.expect("failed to lex")).collect()
+}
+
+pub fn checks(content: &str) -> bool {
+  let source = source(content);
+  let tokens = tokens(content, &source);
+
+  let mut parser = Parser::new(tokens.iter().collect(), &source);
+
+  let ast = parser.parse();
+
+  let checked = !parser.failed() && Visitor::new(&source, &ast).visit().is_ok();
+
+  // so they aren't taken for those of the next check
+  take_diagnostics();
+
+  checked
+}
+
+// what checking reports
+pub fn diagnostics(content: &str) -> Vec<Diagnostic> {
+  let source = source(content);
+  let tokens = tokens(content, &source);
+
+  let mut parser = Parser::new(tokens.iter().collect(), &source);
+
+  let ast = parser.parse();
+
+  let _ = Visitor::new(&source, &ast).visit();
+
+  take_diagnostics()
+}
+
+// what linting a program that checks reports, at `levels` from the command line
+pub fn lints(content: &str, levels: &LintLevels) -> Vec<Diagnostic> {
+  let source = source(content);
+  let tokens = tokens(content, &source);
+
+  let mut parser = Parser::new(tokens.iter().collect(), &source);
+
+  let ast = parser.parse();
+
+  assert!(!parser.failed() && Visitor::new(&source, &ast).visit().is_ok(), "failed to check:\n{}", content);
+
+  Linter::new(&source, &ast, levels).lint();
+
+  take_diagnostics()
+}
+
+// the codes of what checking reports, in order
+pub fn codes(content: &str) -> Vec<&'static str> {
+  diagnostics(content).iter().filter_map(|diagnostic| diagnostic.code).collect()
+}
+
+// the IR of a program that checks, pretty-printed
+pub fn lowered(content: &str) -> String {
+  let source = source(content);
+  let tokens = tokens(content, &source);
+
+  let mut parser = Parser::new(tokens.iter().collect(), &source);
+
+  let ast = parser.parse();
+
+  let mut visitor = Visitor::new(&source, &ast);
+
+  assert!(!parser.failed() && visitor.visit().is_ok(), "failed to check:\n{}", content);
+
+  Lowering::new(&visitor.types).lower(&ast).to_string()
+}
+
+pub fn compile(content: &str) -> String {
+  compile_for(content, Target::default())
+}
+
+pub fn compile_for(content: &str, target: Target) -> String {
+  generated(content, target).0
+}
+
+// the Lua with which Wu line each of its lines came from
+pub fn mapped(content: &str) -> (String, Vec<(usize, usize)>) {
+  generated(content, Target::default())
+}
+
+fn generated(content: &str, target: Target) -> (String, Vec<(usize, usize)>) {
+  let source = source(content);
+  let tokens = tokens(content, &source);
+
+  let mut parser = Parser::new(tokens.iter().collect(), &source);
+
+  let ast = parser.parse();
+
+  let mut visitor = Visitor::new(&source, &ast);
+
+  assert!(!parser.failed() && visitor.visit().is_ok(), "failed to check:\n{}", content);
+
+  let program = Lowering::new(&visitor.types).lower(&ast);
+
+  let mut generator = Generator::new();
+
+  generator.target = target;
+
+  let lua = generator.generate(&program);
+
+  (lua, generator.lines)
+}
@@ -0,0 +1,44 @@
+extern crate tang;
+
+mod common;
+
+use common::*;
+
+
+
+#[test]
+fn constants_are_assigned_once() {
+  assert_eq!(codes("a :: 1\na = 2\n"), ["E0507"]);
+  assert_eq!(codes("t: str : \"wu\"\nt = \"x\"\n"), ["E0507"]);
+  assert_eq!(codes("f :: def() {\n}\nf = f\n"), ["E0507"]);
+}
+
+#[test]
+fn constant_arrays_are_left_as_they_are() {
+  assert_eq!(codes("p :: [1, 2]\np[0] = 3\n"), ["E0507"]);
+}
+
+#[test]
+fn copies_of_constants_are_variables() {
+  assert!(checks("x :: 2\ny := x\ny = 3\n"));
+}
+
+#[test]
+fn constants_are_not_optional() {
+  assert_eq!(codes("o: int? = nil\nc :: o\n"), ["E0506"]);
+}
+
+#[test]
+fn constants_are_positioned_where_they_change() {
+  let diagnostic = &diagnostics("a :: 1\n\na = 2\n")[0];
+
+  assert_eq!((diagnostic.line(), diagnostic.column()), (3, 1))
+}
+
+#[test]
+fn folded_constants_are_inlined() {
+  let lua = compile("w :: 16\nh :: 9\narea :: w * h\nprint(area)\n");
+
+  assert!(lua.contains("local area = 144"), "{}", lua);
+  assert!(lua.contains("print(144)"), "{}", lua)
+}
@@ -0,0 +1,112 @@
+extern crate tang;
+
+mod common;
+
+use common::*;
+
+use tang::wu::error::*;
+
+
+
+#[test]
+fn broken_declarations_are_reported_once() {
+  assert_eq!(codes("x := undefined_thing\ny := x + 1\nz := x\n"), ["E0301"]);
+}
+
+#[test]
+fn poison_stays_in_its_scope() {
+  assert_eq!(codes("f: def() {\n  x := undefined_thing\n}\n\nh: def() -> int { x }\n"), ["E0301", "E0301"]);
+}
+
+fn suggestion(content: &str) -> Option<String> {
+  diagnostics(content).into_iter().flat_map(|diagnostic| diagnostic.help).next()
+}
+
+#[test]
+fn misspelled_names_get_a_suggestion() {
+  assert_eq!(suggestion("number := 1\nx := nubmer\n").as_deref(), Some("did you mean `number`?"));
+  assert_eq!(suggestion("count := 1\nx := cout\n").as_deref(), Some("did you mean `count`?"));
+}
+
+#[test]
+fn short_names_get_no_suggestion() {
+  assert_eq!(suggestion("a := 1\nx := b\n"), None);
+  assert_eq!(suggestion("ab := 1\nx := xy\n"), None);
+  assert_eq!(suggestion("ab := 1\nx := ac\n"), None);
+}
+
+#[test]
+fn far_names_get_no_suggestion() {
+  assert_eq!(suggestion("number := 1\nx := total\n"), None);
+}
+
+#[test]
+fn columns_count_characters() {
+  let diagnostic = &diagnostics("x := \"éééé\" + true\n")[0];
+
+  let columns = |span: &Span| (span.start.1, span.end.1);
+
+  assert_eq!(diagnostic.labels.iter().map(|label| columns(&label.span)).collect::<Vec<_>>(), [(6, 11), (15, 18)]);
+
+  // and the source is marked by them
+  assert!(diagnostic.to_string().contains("x := \"éééé\" + true"));
+}
+
+#[test]
+fn diagnostics_carry_what_went_wrong() {
+  let diagnostic = &diagnostics("x: int = true\n")[0];
+
+  assert_eq!(diagnostic.severity, Severity::Wrong);
+  assert_eq!(diagnostic.code, Some("E0401"));
+  assert_eq!(diagnostic.message, "mismatched types, expected type `int` got `bool`");
+  assert_eq!(diagnostic.file.as_deref(), Some("test.wu"));
+  assert_eq!((diagnostic.line(), diagnostic.column()), (1, 10));
+}
+
+#[test]
+fn annotations_are_labelled() {
+  let diagnostic = &diagnostics("x: int = true\n")[0];
+
+  assert_eq!(diagnostic.labels.len(), 1);
+  assert_eq!(diagnostic.labels[0].message, "expected `int` because of this annotation");
+  assert_eq!((diagnostic.labels[0].span.start, diagnostic.labels[0].span.end), ((1, 4), (1, 6)));
+}
+
+#[test]
+fn diagnostics_render_with_their_parts() {
+  let rendered = diagnostics("constant :: 1\nconstant = 2\n")[0].to_string();
+
+  for part in &["wrong[E0507]", "can't assign twice to constant `constant`", "test.wu:2:1", "constant = 2", "= help:"] {
+    assert!(rendered.contains(part), "no {} in:\n{}", part, rendered)
+  }
+}
+
+#[test]
+fn spans_cross_lines() {
+  let diagnostic = &diagnostics("x: int = [\n  1,\n  2,\n]\n")[0];
+  let span       = diagnostic.span.as_ref().unwrap();
+
+  assert_eq!(diagnostic.code, Some("E0401"));
+  assert_eq!((span.start, span.end), ((1, 10), (4, 1)));
+  assert_eq!((span.first.as_str(), span.last.as_str()), ("x: int = [", "]"));
+
+  assert!(diagnostic.to_json().contains("\"line\":1,\"column_start\":10,\"line_end\":4,\"column_end\":1"));
+}
+
+#[test]
+fn multiline_spans_run_down_a_gutter() {
+  let rendered = diagnostics("x: int = [\n  1,\n  2,\n]\n")[0].to_string();
+
+  let lines = rendered.lines().map(str::trim_end).collect::<Vec<_>>();
+
+  // opened under the first line, skipping the middle, closed under the last
+  let open  = lines.iter().position(|line| line.ends_with("╭──────────^")).expect(&rendered);
+  let close = lines.iter().position(|line| line.ends_with("╰─^")).expect(&rendered);
+
+  assert!(lines[open - 2].ends_with("x: int = ["));
+  assert!(lines[open + 1].ends_with("┆ │"));
+  assert!(lines[close - 1].ends_with("│ │ ]"));
+
+  // the label on the first line keeps its place above the gutter
+  assert!(lines[open - 1].ends_with("─── expected `int` because of this annotation"));
+}
@@ -0,0 +1,69 @@
+extern crate tang;
+
+use std::fs;
+
+use tang::wu::formatter::*;
+use tang::wu::lsp::*;
+
+
+
+fn formatted(content: &str) -> String {
+  format(content, "test.wu").unwrap_or_else(|| panic!("didn't parse:\n{}", content))
+}
+
+fn showcase() -> Vec<(String, String)> {
+  let mut paths = fs::read_dir("showcase").unwrap().map(|entry| entry.unwrap().path()).filter(|path| path.extension().is_some_and(|extension| extension == "wu")).collect::<Vec<_>>();
+
+  paths.sort();
+
+  paths.into_iter().map(|path| {
+    let content = fs::read_to_string(&path).unwrap();
+
+    (path.display().to_string(), content)
+  }).collect()
+}
+
+// what checking found, without where, as formatting moves things around
+fn findings(file: &str, content: &str) -> Vec<(Option<&'static str>, String)> {
+  Analysis::new(file, content).diagnostics.into_iter().map(|diagnostic| (diagnostic.code, diagnostic.message)).collect()
+}
+
+
+
+#[test]
+fn formatting_twice_changes_nothing() {
+  for (path, content) in showcase() {
+    let once  = format(&content, &path).unwrap_or_else(|| panic!("{} didn't parse", path));
+    let twice = format(&once, &path).unwrap_or_else(|| panic!("{} didn't parse once formatted", path));
+
+    assert_eq!(once, twice, "{} isn't stable", path)
+  }
+}
+
+#[test]
+fn formatting_keeps_meaning() {
+  for (path, content) in showcase() {
+    let once = format(&content, &path).unwrap();
+
+    assert_eq!(findings(&path, &content), findings(&path, &once), "{} checks differently once formatted:\n{}", path, once)
+  }
+}
+
+#[test]
+fn array_lengths_stay_as_written() {
+  assert_eq!(formatted("a: [int; 1 + 2] = [1, 2, 3]\n"), "a: [int; 1 + 2] = [1, 2, 3]\n");
+  assert_eq!(formatted("a: [[int; (1+2)*2]; -(-3)]\n"), "a: [[int; (1 + 2) * 2]; -(-3)]\n");
+  assert_eq!(formatted("f: def(a: [int; 2 ^ 2]) -> [int; 4] {\n  a\n}\n"), "f: def(a: [int; 2 ^ 2]) -> [int; 4] {\n  a\n}\n");
+}
+
+#[test]
+fn comments_after_braces_stay() {
+  for content in &[
+    "if true { -- why\n  print(1)\n}\n",
+    "f: def() { -- what\n  -- inside\n  print(2)\n}\n",
+    "point := { -- fields\n  x: float\n}\n",
+    "while true { -- never\n}\n",
+  ] {
+    assert_eq!(formatted(content), *content)
+  }
+}
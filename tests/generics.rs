@@ -0,0 +1,96 @@
+extern crate tang;
+
+mod common;
+
+use common::*;
+
+
+
+const ZERO: &str = "zero: def<T>() -> T {\n  0 as T\n}\n\nid: def<T>(a: T) -> T {\n  a\n}\n\n";
+
+fn with_zero(content: &str) -> String {
+  format!("{}{}", ZERO, content)
+}
+
+
+
+#[test]
+fn parameters_are_inferred_from_arguments() {
+  assert!(checks("id: def<T>(a: T) -> T { a }\na: int = id(10)\nb: str = id(\"wu\")\n"));
+  assert!(!checks("id: def<T>(a: T) -> T { a }\na: str = id(10)\n"));
+}
+
+#[test]
+fn one_parameter_is_one_type() {
+  assert!(checks("same: def<T>(a: T, b: T) -> bool { a == b }\nx := same(1, 2)\n"));
+  assert!(!checks("same: def<T>(a: T, b: T) -> bool { a == b }\nx := same(1, \"two\")\n"));
+}
+
+#[test]
+fn return_types_are_instantiated() {
+  assert!(checks("twice: def<T>(a: T) -> [T; 2] { [a, a] }\nb: [str; 2] = twice(\"wu\")\n"));
+  assert!(!checks("twice: def<T>(a: T) -> [T; 2] { [a, a] }\nb: [int; 2] = twice(\"wu\")\n"));
+}
+
+#[test]
+fn nested_parameters_are_inferred() {
+  let apply = "apply: def<T, K>(f: (T) -> K, a: T) -> K { f(a) }\npair: def(a: bool) -> [bool; 2] { [a, not a] }\n";
+
+  assert!(checks(&format!("{}c: [bool; 2] = apply(pair, true)\n", apply)));
+  assert!(!checks(&format!("{}c: [bool; 2] = apply(pair, 1)\n", apply)));
+}
+
+#[test]
+fn uncalled_bodies_are_checked() {
+  assert_eq!(codes("f: def<T>(a: T) -> T { undefined_name + \"x\" + 1 }\n"), ["E0301"]);
+  assert_eq!(codes("g: def(..a: int) { nope() }\n"), ["E0301"]);
+  assert_eq!(codes("h: def<T>(a: T) -> T {\n  b: int = \"no\"\n  a\n}\n"), ["E0401"]);
+}
+
+#[test]
+fn bodies_are_reported_once() {
+  assert_eq!(codes("f: def<T>(a: T) -> T { nope }\nx := f(1)\ny := f(2.0)\n"), ["E0301"]);
+}
+
+#[test]
+fn what_depends_on_parameters_is_left_to_calls() {
+  let bar = "bar: def<T, K>(a: T, b: K) -> bool { a < b }\n";
+
+  assert!(checks(bar));
+  assert!(checks(&format!("{}x := bar(1, 2)\n", bar)));
+  assert!(!checks(&format!("{}x := bar(1, \"two\")\n", bar)));
+}
+
+#[test]
+fn splats_of_unknown_length_are_spread() {
+  assert!(checks("h: def(..a: int) -> int { 1 }\ng: def(..a: int) -> int { h(*a) }\n"));
+  assert!(!checks("h: def(..a: str) -> int { 1 }\ng: def(..a: int) -> int { h(*a) }\n"));
+}
+
+#[test]
+fn parameters_only_returned_are_given_at_calls() {
+  assert!(checks(&with_zero("d := zero<float>()\nx: float = d\n")));
+
+  assert_eq!(codes(&with_zero("d := zero()\n")), ["E0412"]);
+}
+
+#[test]
+fn given_arguments_are_counted() {
+  assert_eq!(codes(&with_zero("d := zero<int, str>()\n")), ["E0413"]);
+  assert_eq!(codes(&with_zero("e: id<int, str> = id\n")), ["E0413"]);
+}
+
+#[test]
+fn given_arguments_are_what_calls_are_checked_with() {
+  assert!(checks(&with_zero("d: int = id<int>(1)\n")));
+
+  assert_eq!(codes(&with_zero("d: str = id<int>(1)\n")), ["E0401"]);
+  assert_eq!(codes(&with_zero("d := id<int>(\"s\")\n")), ["E0404"]);
+}
+
+#[test]
+fn annotations_instantiate() {
+  assert!(checks(&with_zero("e: id<str> = id\nf: str = e(\"s\")\n")));
+
+  assert_eq!(codes(&with_zero("e: id<str> = id\nf: int = e(\"s\")\n")), ["E0401"]);
+}
@@ -0,0 +1,127 @@
+extern crate tang;
+
+mod common;
+
+use common::*;
+
+use tang::wu::error::*;
+use tang::wu::visitor::*;
+
+
+
+fn warned(content: &str) -> Vec<(&'static str, Severity, String)> {
+  leveled(content, &LintLevels::default())
+}
+
+fn leveled(content: &str, levels: &LintLevels) -> Vec<(&'static str, Severity, String)> {
+  lints(content, levels).into_iter().map(|diagnostic| (diagnostic.code.unwrap_or(""), diagnostic.severity, diagnostic.message)).collect()
+}
+
+fn levels(level: Level, lint: &str) -> LintLevels {
+  let mut levels = LintLevels::default();
+
+  levels.set(lint, level).unwrap();
+
+  levels
+}
+
+
+
+#[test]
+fn unused_locals_and_parameters_are_weird() {
+  let warnings = warned("f: def(a: int, b: int) -> int {\n  c := 1\n  return b\n}\n");
+
+  assert_eq!(
+    warnings,
+    [
+      ("W0001", Severity::Weird, "unused parameter `a`".to_string()),
+      ("W0001", Severity::Weird, "unused variable `c`".to_string()),
+    ]
+  );
+}
+
+#[test]
+fn underscores_and_exports_are_left_alone() {
+  assert!(warned("unread := 1\nf: def(_a: int) {\n  _b := 1\n}\n").is_empty());
+}
+
+#[test]
+fn reads_anywhere_count() {
+  assert!(warned("f: def(a: int) -> int {\n  b := 2\n  if true {\n    return a + b\n  }\n  return 0\n}\n").is_empty());
+}
+
+#[test]
+fn statements_after_return_are_unreachable() {
+  // only the first of them is pointed out
+  let warnings = warned("f: def() -> int {\n  return 1\n  x := 2\n  x\n}\n");
+
+  assert_eq!(warnings, [("W0002", Severity::Weird, "unreachable statement".to_string())]);
+}
+
+#[test]
+fn inner_bindings_shadowing_outer_ones() {
+  let content  = "f: def(a: int) -> int {\n  if true {\n    a := 2\n    return a\n  }\n  return a\n}\n";
+  let warnings = lints(content, &LintLevels::default());
+
+  assert_eq!(warnings.len(), 1);
+  assert_eq!(warnings[0].code, Some("W0003"));
+  assert_eq!(warnings[0].message, "`a` shadows a binding from an enclosing scope");
+  assert_eq!(warnings[0].labels[0].message, "`a` is first bound here");
+}
+
+#[test]
+fn command_line_levels() {
+  let content = "f: def() {\n  c := 1\n}\n";
+
+  assert!(leveled(content, &levels(Level::Allow, "unused_variables")).is_empty());
+
+  let denied = lints(content, &levels(Level::Deny, "unused_variables"));
+
+  assert_eq!(denied[0].severity, Severity::Wrong);
+  assert!(denied[0].help.contains(&"`unused_variables` is denied".to_string()));
+
+  // other lints keep warning
+  assert_eq!(leveled(content, &levels(Level::Deny, "shadowing"))[0].1, Severity::Weird);
+}
+
+#[test]
+fn unknown_lints_are_refused() {
+  let mut levels = LintLevels::default();
+
+  assert_eq!(levels.set("shadowin", Level::Deny), Err("unknown lint `shadowin`, did you mean `shadowing`?".to_string()));
+  assert_eq!(levels.set("everything", Level::Deny), Err("unknown lint `everything`".to_string()));
+}
+
+#[test]
+fn file_attributes_cover_the_file() {
+  let content = "-- #![deny(unused_variables)]\nf: def() {\n  c := 1\n}\ng: def() {\n  d := 1\n}\n";
+
+  assert_eq!(leveled(content, &LintLevels::default()).iter().map(|warning| warning.1).collect::<Vec<_>>(), [Severity::Wrong, Severity::Wrong]);
+}
+
+#[test]
+fn statement_attributes_cover_the_statement() {
+  let content = "f: def() {\n  -- #[allow(unused_variables)]\n  c := 1\n  d := 1\n}\n";
+
+  assert_eq!(warned(content), [("W0001", Severity::Weird, "unused variable `d`".to_string())]);
+}
+
+#[test]
+fn statement_attributes_beat_the_file_and_command_line() {
+  let content = "-- #![deny(unused_variables)]\nf: def() {\n  -- #[warn(unused_variables)]\n  c := 1\n}\n";
+
+  assert_eq!(leveled(content, &levels(Level::Allow, "unused_variables"))[0].1, Severity::Weird);
+}
+
+#[test]
+fn bad_attributes_are_weird() {
+  let warnings = warned("-- #![forbid(shadowing)]\n-- #![deny(shadowin)]\nx := 1\n");
+
+  assert_eq!(
+    warnings.iter().map(|warning| (warning.0, warning.2.as_str())).collect::<Vec<_>>(),
+    [
+      ("W0000", "unknown lint level `forbid`, expected `allow`, `warn` or `deny`"),
+      ("W0000", "unknown lint `shadowin`, did you mean `shadowing`?"),
+    ]
+  );
+}
@@ -0,0 +1,48 @@
+extern crate tang;
+
+mod common;
+
+use common::*;
+
+
+
+#[test]
+fn loops_check() {
+  assert!(checks("i := 0\nwhile i < 3 {\n  i = i + 1\n}\n"));
+  assert!(checks("i := 0\nwhile i < 3 and i != 2 {\n  i = i + 1\n}\n"));
+}
+
+#[test]
+fn conditions_are_bool() {
+  assert_eq!(codes("while 1 {\n}\n"), ["E0402"]);
+  assert_eq!(codes("x := \"a\"\nwhile x {\n}\n"), ["E0402"]);
+}
+
+#[test]
+fn bodies_are_checked() {
+  assert_eq!(codes("while true {\n  x: int = false\n}\n"), ["E0401"]);
+}
+
+#[test]
+fn loops_are_lua_loops() {
+  let lua = compile("j := 0\nwhile j < 2 {\n  j = j + 1\n}\n");
+
+  assert!(lua.contains("while (j < 2) do\n  j = (j + 1)\nend"), "{}", lua)
+}
+
+#[test]
+fn loops_give_their_last_value() {
+  let lua = compile("i := 0\ni = while i < 3 {\n  i + 1\n}\n");
+
+  assert!(lua.contains("while (i < 3) do\n  i = (i + 1)\nend"), "{}", lua);
+
+  let lua = compile("k := 0\nx := while k < 3 {\n  k = k + 1\n  k * 2\n}\n");
+
+  assert!(lua.contains("local x\nwhile (k < 3) do\n  k = (k + 1)\n  x = (k * 2)\nend"), "{}", lua);
+}
+
+#[test]
+fn loop_values_are_typed_by_the_body() {
+  assert!(checks("i := 0\nx: int = while i < 3 {\n  i = i + 1\n  i\n}\n"));
+  assert_eq!(codes("i := 0\nx: str = while i < 3 {\n  i = i + 1\n  i\n}\n"), ["E0401"]);
+}
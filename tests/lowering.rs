@@ -0,0 +1,138 @@
+extern crate tang;
+
+mod common;
+
+use common::*;
+
+
+
+// where in the output each of `needles` first shows up, panicking on any that doesn't
+fn positions(lua: &str, needles: &[&str]) -> Vec<usize> {
+  needles.iter().map(|needle| lua.find(needle).unwrap_or_else(|| panic!("no `{}` in:\n{}", needle, lua))).collect()
+}
+
+
+
+#[test]
+fn hoisted_operands_run_in_order() {
+  let lua = compile("g: def() -> int { 1 }\nc := true\nr := g() + if c {\n  print(2)\n  2\n} else { 3 }\n");
+
+  let order = positions(&lua, &["g()", "print(2)"]);
+
+  assert!(order[0] < order[1], "{}", lua)
+}
+
+#[test]
+fn hoisted_arguments_run_in_order() {
+  let lua = compile("g: def() -> int { 1 }\nf: def(a: int, b: int) -> int { a + b }\nt := f(g(), {\n  print(2)\n  2\n})\n");
+
+  let order = positions(&lua, &["g()", "print(2)", "(__value"]);
+
+  assert!(order[0] < order[1] && order[1] < order[2], "{}", lua)
+}
+
+#[test]
+fn hoisted_elements_run_in_order() {
+  let lua = compile("g: def() -> int { 1 }\ns := [g(), {\n  print(3)\n  4\n}, 5]\n");
+
+  let order = positions(&lua, &["g()", "print(3)"]);
+
+  assert!(order[0] < order[1], "{}", lua)
+}
+
+#[test]
+fn nothing_is_spilled_without_hoisting() {
+  let lua = compile("g: def() -> int { 1 }\nr := g() + g()\n");
+
+  assert!(!lua.contains("__value"), "{}", lua)
+}
+
+#[test]
+fn splats_spread_without_being_called() {
+  let lua = compile("h: def(..a: int) -> int { 1 }\ng: def(..a: int) -> int { h(*a) }\n");
+
+  assert!(lua.contains("h(table.unpack(a, 1, a.n))"), "{}", lua)
+}
+
+#[test]
+fn shadowed_splats_are_not_spread() {
+  let lua = compile("g: def(..a: int) -> int {\n  f: def(a: int?) -> int { *a }\n  f(1)\n}\nx := g(1)\n");
+
+  assert!(lua.contains("return a\n"), "{}", lua)
+}
+
+#[test]
+fn temporaries_stay_clear_of_names() {
+  let lua = compile("__value0 := 5\nc := true\ng: def() -> int { 1 }\nr := __value0 + g() + if c {\n  print(3)\n  3\n} else { 4 }\n");
+
+  assert_eq!(lua.matches("local __value0").count(), 1, "{}", lua);
+
+  // a name that only looks like one is still spilled before the hoisted block runs
+  let order = positions(&lua, &["(__value0 + g())", "print(3)"]);
+
+  assert!(order[0] < order[1], "{}", lua)
+}
+
+#[test]
+fn every_value_is_typed() {
+  let ir = lowered("x := 1\ny := [x, 2]\nz := y[1] > 2 and x < 4\n");
+
+  assert_eq!(
+    ir,
+    concat!(
+      "   1 | local x: int = 1\n",
+      "   2 | local y: [int; 2] = [int; 2] { [0] = x: int, [1] = 2 }\n",
+      "   3 | local z: bool = ((y: [int; 2][1] > 2) and (x: int < 4))\n",
+      "export x, y, z\n",
+    )
+  );
+}
+
+#[test]
+fn blocks_are_indented_under_their_lines() {
+  let ir = lowered("f: def(a: int) -> int {\n  if a > 1 {\n    return a\n  }\n  a\n}\n");
+
+  assert_eq!(
+    ir,
+    concat!(
+      "   1 | local f: (int) -> int = fn(a: int) {\n",
+      "   2 |   if (a: int > 1) {\n",
+      "   3 |     return a: int\n",
+      "     |   }\n",
+      "   5 |   return a: int\n",
+      "     | }\n",
+      "export f\n",
+    )
+  );
+}
+
+#[test]
+fn if_expressions_become_control_flow() {
+  let ir = lowered("n := 1\nk := if n > 0 { 1 } else { 2 }\n");
+
+  for line in &["   2 | local k: int\n", "   2 | if (n: int > 0) {\n", "   2 |   k: int = 1\n", "     | } else {\n", "   2 |   k: int = 2\n"] {
+    assert!(ir.contains(line), "no `{}` in:\n{}", line.trim_end(), ir)
+  }
+}
+
+#[test]
+fn temporaries_are_explicit() {
+  let ir = lowered("n := 1\nz := n + (if true { 1 } else { 2 })\n");
+
+  // the left operand is kept before the right one runs
+  let order = positions(&ir, &["local __value1: int = n: int", "local __value0: int", "__value0: int = 1", "local z: int = (__value1: int + __value0: int)"]);
+
+  assert!(order.windows(2).all(|pair| pair[0] < pair[1]), "{}", ir)
+}
+
+#[test]
+fn calls_carry_their_instantiated_type() {
+  assert!(lowered("id: def<T>(a: T) -> T { a }\nn := id(1)\n").contains("   2 | local n: int = id: (T) -> T(1): int\n"));
+}
+
+#[test]
+fn loops_lower_to_while() {
+  let ir = lowered("x := 1\nwhile x < 3 {\n  x = x + 1\n}\n");
+
+  assert!(ir.contains("   2 | while (x: int < 3) {\n   3 |   x: int = (x: int + 1)\n     | }\n"), "{}", ir);
+}
@@ -0,0 +1,238 @@
+extern crate tang;
+
+use tang::wu::lsp::*;
+
+
+
+const URI: &str = "file:///tmp/main.wu";
+
+// frames each message the way a client sends it
+fn session(messages: &[Json]) -> (i32, Vec<Json>) {
+  let mut input = String::new();
+
+  for message in messages {
+    let content = message.to_string();
+
+    input.push_str(&format!("Content-Length: {}\r\n\r\n{}", content.len(), content))
+  }
+
+  let mut output = Vec::new();
+
+  let code = Server::new(input.as_bytes(), &mut output).run();
+
+  (code, replies(&String::from_utf8(output).unwrap()))
+}
+
+fn replies(mut output: &str) -> Vec<Json> {
+  let mut replies = Vec::new();
+
+  while let Some(header) = output.find("\r\n\r\n") {
+    let length = output[.. header].trim_start_matches("Content-Length: ").parse::<usize>().unwrap();
+    let start  = header + 4;
+
+    replies.push(Json::parse(&output[start .. start + length]).expect("server sent broken JSON"));
+
+    output = &output[start + length ..]
+  }
+
+  replies
+}
+
+fn request(id: usize, method: &str, params: Json) -> Json {
+  Json::object(vec!(
+    ("jsonrpc", Json::string("2.0")),
+    ("id",      Json::Number(id as f64)),
+    ("method",  Json::string(method)),
+    ("params",  params),
+  ))
+}
+
+fn notification(method: &str, params: Json) -> Json {
+  Json::object(vec!(
+    ("jsonrpc", Json::string("2.0")),
+    ("method",  Json::string(method)),
+    ("params",  params),
+  ))
+}
+
+fn open(text: &str) -> Json {
+  notification("textDocument/didOpen", Json::object(vec!(
+    ("textDocument", Json::object(vec!(
+      ("uri",        Json::string(URI)),
+      ("languageId", Json::string("wu")),
+      ("version",    Json::Number(1.0)),
+      ("text",       Json::string(text)),
+    ))),
+  )))
+}
+
+// a 0-based line and character in the open document
+fn at(line: usize, character: usize) -> Json {
+  Json::object(vec!(
+    ("textDocument", Json::object(vec!(("uri", Json::string(URI))))),
+    ("position",     Json::object(vec!(("line", Json::Number(line as f64)), ("character", Json::Number(character as f64))))),
+  ))
+}
+
+fn reply(replies: &[Json], id: usize) -> &Json {
+  replies.iter().find(|reply| reply.get("id").as_usize() == Some(id)).unwrap_or_else(|| panic!("no reply to {}", id))
+}
+
+fn diagnostics(replies: &[Json]) -> Vec<&Json> {
+  replies.iter().filter(|reply| reply.get("method").as_str() == Some("textDocument/publishDiagnostics")).collect()
+}
+
+
+
+#[test]
+fn session_from_start_to_exit() {
+  let (code, replies) = session(&[
+    request(1, "initialize", Json::object(Vec::new())),
+    notification("initialized", Json::object(Vec::new())),
+    open("number := 10\ntwice := number * 2\n"),
+    request(2, "textDocument/hover", at(1, 9)),
+    request(3, "textDocument/definition", at(1, 9)),
+    request(4, "textDocument/completion", at(1, 0)),
+    request(5, "shutdown", Json::Null),
+    notification("exit", Json::Null),
+  ]);
+
+  assert_eq!(code, 0);
+
+  let capabilities = reply(&replies, 1).get("result").get("capabilities");
+
+  assert!(!capabilities.get("hoverProvider").is_null() && !capabilities.get("completionProvider").is_null());
+
+  let published = diagnostics(&replies);
+
+  assert_eq!(published.len(), 1);
+  assert_eq!(published[0].get("params").get("diagnostics").as_array().map(Vec::len), Some(0));
+
+  let hover = reply(&replies, 2).get("result").get("contents").get("value").as_str().unwrap();
+
+  assert!(hover.contains("number: int"), "{}", hover);
+
+  let definition = reply(&replies, 3).get("result");
+
+  assert_eq!(definition.get("uri").as_str(), Some(URI));
+  assert_eq!(definition.get("range").get("start").get("line").as_usize(), Some(0));
+  assert_eq!(definition.get("range").get("start").get("character").as_usize(), Some(0));
+
+  let labels = reply(&replies, 4).get("result").as_array().unwrap().iter().filter_map(|item| item.get("label").as_str()).collect::<Vec<_>>();
+
+  assert!(labels.contains(&"number") && labels.contains(&"print"), "{:?}", labels);
+
+  assert!(reply(&replies, 5).get("result").is_null())
+}
+
+#[test]
+fn problems_are_published() {
+  let (_, replies) = session(&[open("x: int = true\n")]);
+
+  let published = diagnostics(&replies);
+  let problems  = published[0].get("params").get("diagnostics").as_array().unwrap();
+
+  assert_eq!(problems.len(), 1);
+  assert_eq!(problems[0].get("code").as_str(), Some("E0401"))
+}
+
+#[test]
+fn failed_imports_are_published_once() {
+  let (_, replies) = session(&[open("import nothere\n\na := nothere.x\nb: nothere.thing = nothere.make()\n")]);
+
+  let published = diagnostics(&replies);
+  let problems  = published[0].get("params").get("diagnostics").as_array().unwrap();
+
+  assert_eq!(problems.len(), 1);
+  assert_eq!(problems[0].get("code").as_str(), Some("E0304"))
+}
+
+#[test]
+fn indexing_what_isnt_an_array_is_a_problem() {
+  let (_, replies) = session(&[open("x := 1\ny := x[0]\n")]);
+
+  let published = diagnostics(&replies);
+  let problems  = published[0].get("params").get("diagnostics").as_array().unwrap();
+
+  assert_eq!(problems.len(), 1);
+  assert_eq!(problems[0].get("code").as_str(), Some("E0408"));
+  assert_eq!(problems[0].get("message").as_str(), Some("can't index `int`"))
+}
+
+#[test]
+fn empty_arrays_check() {
+  let (code, replies) = session(&[open("x := []\n"), request(1, "shutdown", Json::Null), notification("exit", Json::Null)]);
+
+  assert_eq!(code, 0);
+  assert!(reply(&replies, 1).get("result").is_null());
+  assert_eq!(diagnostics(&replies)[0].get("params").get("diagnostics").as_array().map(Vec::len), Some(0))
+}
+
+#[test]
+fn messages_without_a_length_are_skipped() {
+  let shutdown = request(1, "shutdown", Json::Null).to_string();
+  let exit     = notification("exit", Json::Null).to_string();
+
+  let input = format!(
+    "Content-Type: application/json\r\n\r\n{{}}Content-Length: {}\r\n\r\n{}Content-Length: {}\r\n\r\n{}",
+    shutdown.len(), shutdown, exit.len(), exit
+  );
+
+  let mut output = Vec::new();
+
+  let code = Server::new(input.as_bytes(), &mut output).run();
+
+  assert_eq!(code, 0);
+  assert!(reply(&replies(&String::from_utf8(output).unwrap()), 1).get("result").is_null())
+}
+
+#[test]
+fn characters_are_counted_in_utf16() {
+  let (_, replies) = session(&[
+    open("number := 10\nprint(\"😀\", number)\nx: int = \"😀\"\n"),
+    request(1, "textDocument/hover", at(1, 12)),
+  ]);
+
+  let range = reply(&replies, 1).get("result").get("range");
+
+  assert_eq!(range.get("start").get("character").as_usize(), Some(12));
+  assert_eq!(range.get("end").get("character").as_usize(), Some(18));
+
+  let problem = &diagnostics(&replies)[0].get("params").get("diagnostics").as_array().unwrap()[0];
+
+  assert_eq!(problem.get("range").get("start").get("character").as_usize(), Some(9));
+  assert_eq!(problem.get("range").get("end").get("character").as_usize(), Some(13))
+}
+
+#[test]
+fn definitions_are_where_names_are() {
+  let (_, replies) = session(&[
+    open("area := 1\nf: def(a: int,\n  b: int) -> int {\n  a + b\n}\n"),
+    request(1, "textDocument/definition", at(3, 2)),
+    request(2, "textDocument/definition", at(3, 6)),
+  ]);
+
+  let start = |id| {
+    let start = reply(&replies, id).get("result").get("range").get("start");
+
+    (start.get("line").as_usize(), start.get("character").as_usize())
+  };
+
+  assert_eq!(start(1), (Some(1), Some(7)));
+  assert_eq!(start(2), (Some(2), Some(2)))
+}
+
+#[test]
+fn exit_without_shutdown_fails() {
+  let (code, _) = session(&[notification("exit", Json::Null)]);
+
+  assert_eq!(code, 1)
+}
+
+#[test]
+fn escaped_characters_outside_the_basic_plane() {
+  assert_eq!(Json::parse(r#""\ud83d\ude00""#).as_ref().and_then(Json::as_str), Some("😀"));
+
+  // a first half followed by something that isn't a second half
+  assert!(Json::parse(r#""\ud800\u0041""#).is_none())
+}
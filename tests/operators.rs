@@ -0,0 +1,65 @@
+extern crate tang;
+
+mod common;
+
+use common::*;
+
+
+
+#[test]
+fn logic_is_on_bools() {
+  assert!(checks("a := true and false or not true\nb: bool = not a and a\n"));
+
+  assert_eq!(codes("a := 1 and true\n"), ["E0407"]);
+  assert_eq!(codes("a := true or \"s\"\n"), ["E0407"]);
+  assert_eq!(codes("a := not 1\n"), ["E0407"]);
+}
+
+#[test]
+fn logic_binds_looser_than_comparisons() {
+  let lua = compile("x := 1\na := x == 1 or x == 2 and x != 3\nb := not a == false\n");
+
+  assert!(lua.contains("local a = ((x == 1) or ((x == 2) and (x ~= 3)))"), "{}", lua);
+
+  // `not` binds tighter, as in Lua
+  assert!(lua.contains("local b = ((not a) == false)"), "{}", lua)
+}
+
+#[test]
+fn logic_short_circuits_in_lua() {
+  let lua = compile("f: def() -> bool {\n  true\n}\n\na := false and f()\nb := true or f()\n");
+
+  assert!(lua.contains("local a = (false and f())"), "{}", lua);
+  assert!(lua.contains("local b = (true or f())"), "{}", lua)
+}
+
+#[test]
+fn minus_is_on_numbers() {
+  assert!(checks("y := 2\nx: int = -y\nz: float = -1.5\n"));
+
+  assert_eq!(codes("a := -true\n"), ["E0407"]);
+  assert_eq!(codes("a := -\"s\"\n"), ["E0407"]);
+}
+
+#[test]
+fn minus_goes_where_values_do() {
+  let lua = compile("y := 2\nf: def(a: int) -> int {\n  a\n}\n\nd := f(-y)\ne := 2 * -y\n");
+
+  assert!(lua.contains("local d = f((- y))"), "{}", lua);
+  assert!(lua.contains("local e = (2 * (- y))"), "{}", lua)
+}
+
+#[test]
+fn minus_binds_looser_than_powers_on_their_left() {
+  let lua = compile("y := 2.0\na := -y ^ 2\nb := y ^ -y\n");
+
+  assert!(lua.contains("local a = (- (y ^ 2))"), "{}", lua);
+  assert!(lua.contains("local b = (y ^ (- y))"), "{}", lua)
+}
+
+#[test]
+fn minus_is_folded() {
+  let lua = compile("g :: -(-3)\nk :: -2 ^ 2\nh := g + k\n");
+
+  assert!(lua.contains("local h = (3 + (-4))"), "{}", lua)
+}
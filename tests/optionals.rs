@@ -0,0 +1,45 @@
+extern crate tang;
+
+mod common;
+
+use common::*;
+
+
+
+#[test]
+fn narrowed_names_are_read_without_their_optional() {
+  assert!(checks("w: int? = 1\n\nif w != nil {\n  x := w + 1\n}\n"));
+  assert!(!checks("w: int? = 1\n\nx := w + 1\n"));
+}
+
+#[test]
+fn narrowed_names_are_assigned_as_declared() {
+  assert!(checks("w: int? = 1\n\nif w != nil {\n  w = nil\n}\n"));
+  assert!(checks("w: int? = 1\n\nif w != nil {\n  w = 2\n  x := w + 1\n}\n"));
+  assert!(!checks("w: int? = 1\n\nif w != nil {\n  w = true\n}\n"));
+}
+
+#[test]
+fn narrowing_ends_at_nil() {
+  assert_eq!(codes("w: int? = 1\n\nif w != nil {\n  w = nil\n  x := w + 1\n}\n"), ["E0502"]);
+}
+
+#[test]
+fn arrays_of_optionals() {
+  assert!(checks("a: [int?; 2] = [nil, 1]\n"));
+  assert!(checks("a: [int?; 2] = [1, 2]\n"));
+  assert!(checks("a: [int?; 2] = [1, 2]\na = [nil, nil]\n"));
+  assert!(checks("a := [nil, 1]\nb: [int?; 2] = a\n"));
+  assert!(checks("x: int? = 1\na := [x, 2]\nb: [int?; 2] = a\n"));
+
+  assert!(!checks("a: [int?; 2] = [nil, \"a\"]\n"));
+  assert!(!checks("a: [int; 2] = [nil, 1]\n"));
+}
+
+#[test]
+fn array_elements_are_unified() {
+  assert!(checks("a: [float; 2] = [1, 2.5]\n"));
+  assert!(checks("a := [[1.0], [2]]\n"));
+
+  assert_eq!(codes("a := [1, true]\n"), ["E0401"]);
+}
@@ -0,0 +1,38 @@
+extern crate tang;
+
+mod common;
+
+use common::*;
+
+
+
+#[test]
+fn print_takes_anything() {
+  assert!(checks("print()\n"));
+  assert!(checks("print(\"a\", 1, 2.5, true, [1, 2])\n"));
+  assert!(checks("x: int? = nil\nprint(\"x is\", x)\n"));
+  assert!(checks("s := string.format(\"%s is %d\", \"x\", 10)\n"));
+}
+
+#[test]
+fn anything_is_only_passed_on() {
+  assert!(checks("x: any = 1\nprint(x)\n"));
+  assert!(!checks("x: any = 1\ny := x + 1\n"));
+  assert!(!checks("x: any = 1\ny: int = x\n"));
+}
+
+#[test]
+fn readme_taster_checks() {
+  let readme = include_str!("../README.md");
+  let start  = readme.find("```swift\n").expect("no taster in the README") + "```swift\n".len();
+  let taster = &readme[start .. start + readme[start ..].find("```").unwrap()];
+
+  assert!(checks(taster), "{}", taster)
+}
+
+#[test]
+fn tonumber_may_give_nil() {
+  assert!(!checks("x := tonumber(\"12\") + 1.0\n"));
+  assert!(checks("x := (tonumber(\"12\") else 0.0) + 1.0\n"));
+  assert!(checks("x := tonumber(\"twelve\")\ny := *x\n"));
+}
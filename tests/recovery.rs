@@ -0,0 +1,83 @@
+extern crate tang;
+
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+mod common;
+
+use common::*;
+
+use tang::wu::parser::*;
+use tang::wu::error::*;
+
+
+
+// parses on a thread of its own, so a parser stuck at the end fails the test instead of hanging it
+fn parsed(content: &'static str) -> (bool, usize, Vec<Diagnostic>) {
+  let (sender, receiver) = mpsc::channel();
+
+  thread::spawn(move || {
+    let content = format!("{}\n", content);
+
+    let source = source(&content);
+    let tokens = tokens(&content, &source);
+
+    let mut parser = Parser::new(tokens.iter().collect(), &source);
+
+    let ast = parser.parse();
+
+    sender.send((parser.failed(), ast.len(), take_diagnostics())).unwrap()
+  });
+
+  receiver.recv_timeout(Duration::from_secs(5)).unwrap_or_else(|_| panic!("no end to parsing:\n{}", content))
+}
+
+fn recovered(content: &'static str) -> (usize, Vec<Diagnostic>) {
+  let (failed, statements, diagnostics) = parsed(content);
+
+  assert!(failed && !diagnostics.is_empty(), "nothing reported for:\n{}", content);
+
+  (statements, diagnostics)
+}
+
+
+
+#[test]
+fn unfinished_calls() {
+  for content in &["f(1 +)", "f(1, 2 *)", "f(1 +", "f(g(1 +))"] {
+    recovered(content);
+  }
+}
+
+#[test]
+fn unfinished_arrays() {
+  for content in &["x := [1, 2 +]", "x := [1 +", "x := [[1 -], 2]"] {
+    recovered(content);
+  }
+}
+
+#[test]
+fn unfinished_struct_literals() {
+  for content in &["p := point { x: 1 + }", "p := point { x: 1, y: }", "p := point { 1 *"] {
+    recovered(content);
+  }
+}
+
+#[test]
+fn parsing_goes_on_after_the_broken_line() {
+  let (statements, diagnostics) = recovered("a := f(1 +)\nb := [2 *]\nc := 3\n");
+  let (_, expected, _)          = parsed("a := f(1)\nb := [2]\nc := 3\n");
+
+  assert_eq!(statements, expected);
+  assert_eq!(diagnostics.len(), 2)
+}
+
+#[test]
+fn integers_too_large_for_int() {
+  let (statements, diagnostics) = recovered("x := 99999999999999999999\ny := 9223372036854775807\n");
+  let (_, expected, _)          = parsed("x := 1\ny := 9223372036854775807\n");
+
+  assert_eq!(statements, expected);
+  assert_eq!(diagnostics.iter().map(|diagnostic| diagnostic.code).collect::<Vec<_>>(), vec![Some("E0207")])
+}
@@ -0,0 +1,55 @@
+extern crate tang;
+
+mod common;
+
+use common::*;
+
+
+
+// the Wu line behind each line of Lua containing `code`
+fn origin(content: &str, code: &str) -> usize {
+  let (lua, lines) = mapped(content);
+
+  let index = lua.lines().position(|line| line.contains(code)).unwrap_or_else(|| panic!("no `{}` in:\n{}", code, lua));
+
+  lines.iter().find(|&&(line, _)| line == index + 1).unwrap_or_else(|| panic!("`{}` isn't mapped in:\n{}", code, lua)).1
+}
+
+
+
+const PROGRAM: &str = "x := 1\n\ny := [\n  1,\n  2,\n]\nf: def(a: int) -> int {\n  b := a + 1\n  b\n}\nprint(f(x))\n";
+
+#[test]
+fn statements_map_to_their_lines() {
+  assert_eq!(origin(PROGRAM, "local x = 1"), 1);
+  assert_eq!(origin(PROGRAM, "local y = "), 3);
+  assert_eq!(origin(PROGRAM, "print(f(x))"), 11);
+}
+
+#[test]
+fn function_bodies_map_line_by_line() {
+  assert_eq!(origin(PROGRAM, "local f = function(a)"), 7);
+  assert_eq!(origin(PROGRAM, "local b = (a + 1)"), 8);
+  assert_eq!(origin(PROGRAM, "return b"), 9);
+}
+
+#[test]
+fn statements_over_several_lines_stay_with_their_first() {
+  assert_eq!(origin(PROGRAM, "[1] = 2"), 3);
+}
+
+#[test]
+fn exports_are_left_out() {
+  let (lua, lines) = mapped(PROGRAM);
+
+  let exports = lua.lines().position(|line| line == "return {").unwrap() + 1;
+
+  assert!(lines.iter().all(|&(line, _)| line < exports));
+}
+
+#[test]
+fn lua_lines_are_in_order() {
+  let (_, lines) = mapped(PROGRAM);
+
+  assert!(lines.windows(2).all(|pair| pair[0].0 + 1 == pair[1].0));
+}
@@ -0,0 +1,54 @@
+extern crate tang;
+
+mod common;
+
+use common::*;
+
+
+
+const POINT: &str = "p := {\n  x: float = 1\n  y: float\n}\n";
+
+fn with_point(content: &str) -> String {
+  format!("{}{}", POINT, content)
+}
+
+
+
+#[test]
+fn structs_are_built_by_position_and_by_name() {
+  assert!(checks(&with_point("a := p { 1, 2 }\nb := p { y: 3 }\nc := p { y: 3, x: 4 }\n")));
+}
+
+#[test]
+fn fields_are_accessed_and_assigned() {
+  assert!(checks(&with_point("a := p { 1, 2 }\nb: float = a.x\na.y = b\n")));
+
+  assert_eq!(codes(&with_point("a := p { 1, 2 }\nb := a.z\n")), ["E0302"]);
+  assert_eq!(codes(&with_point("a := p { 1, 2 }\na.x = \"s\"\n")), ["E0401"]);
+}
+
+#[test]
+fn fields_are_checked() {
+  assert_eq!(codes(&with_point("a := p { z: 1 }\n")), ["E0302"]);
+  assert_eq!(codes(&with_point("a := p { true, 2 }\n")), ["E0401"]);
+  assert_eq!(codes(&with_point("a := p { 1 }\n")), ["E0309"]);
+  assert_eq!(codes(&with_point("a := p { 1, 2, 3 }\n")), ["E0310"]);
+}
+
+#[test]
+fn structs_are_types() {
+  assert!(checks(&with_point("f: def(a: p) -> float {\n  a.x + a.y\n}\n\nz := f(p { y: 2 })\n")));
+  assert!(!checks(&with_point("f: def(a: p) -> float {\n  a.x\n}\n\nz := f(1)\n")));
+}
+
+#[test]
+fn structs_are_lua_tables() {
+  let lua = compile(&with_point("a := p { 1, 2 }\nb := p { y: 3 }\nb.x = a.y\n"));
+
+  assert!(lua.contains("local p = ({\n  x = 1\n})"), "{}", lua);
+  assert!(lua.contains("local a = ({\n  x = 1,\n  y = 2\n})"), "{}", lua);
+
+  // defaults are read off the declaration
+  assert!(lua.contains("local b = ({\n  x = p.x,\n  y = 3\n})"), "{}", lua);
+  assert!(lua.contains("b.x = a.y"), "{}", lua)
+}
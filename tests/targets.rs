@@ -0,0 +1,56 @@
+extern crate tang;
+
+mod common;
+
+use common::*;
+
+use tang::wu::compiler::*;
+
+
+
+const TARGETS: &[Target] = &[Target::Lua51, Target::Lua54, Target::LuaJIT];
+
+#[test]
+fn targets_by_name() {
+  assert_eq!(Target::from("5.1"),    Some(Target::Lua51));
+  assert_eq!(Target::from("5.3"),    Some(Target::Lua54));
+  assert_eq!(Target::from("5.4"),    Some(Target::Lua54));
+  assert_eq!(Target::from("luajit"), Some(Target::LuaJIT));
+  assert_eq!(Target::from("5.2"),    None);
+
+  assert_eq!(Target::default(), Target::Lua54);
+}
+
+#[test]
+fn splats_unpack_where_the_target_has_it() {
+  let content = "h: def(..a: int) -> int { 1 }\ng: def(..a: int) -> int { h(*a) }\n";
+
+  assert!(compile_for(content, Target::Lua54).contains("h(table.unpack(a, 1, a.n))"));
+  assert!(compile_for(content, Target::Lua51).contains("h(unpack(a, 1, a.n))"));
+  assert!(compile_for(content, Target::LuaJIT).contains("h(unpack(a, 1, a.n))"));
+}
+
+#[test]
+fn control_characters_are_escaped_for_the_target() {
+  let content = "x := \"a\u{7}b\u{1b}\"\n";
+
+  assert!(compile_for(content, Target::Lua54).contains("local x = \"a\\x07b\\x1b\""));
+  assert!(compile_for(content, Target::LuaJIT).contains("local x = \"a\\x07b\\x1b\""));
+
+  // decimal escapes take up to three digits, so they're padded to keep clear of what follows
+  assert!(compile_for("x := \"\u{7}1\"\n", Target::Lua51).contains("local x = \"\\0071\""));
+}
+
+#[test]
+fn common_escapes_are_the_same_everywhere() {
+  for &target in TARGETS {
+    assert!(compile_for("x := \"\\\"a\\\\\\n\\t\"\n", target).contains("local x = \"\\\"a\\\\\\n\\t\""), "{:?}", target)
+  }
+}
+
+#[test]
+fn float_division_is_the_same_everywhere() {
+  for &target in TARGETS {
+    assert!(compile_for("a := 1.0\nb := a / 2.0\n", target).contains("local b = (a / 2.0)"), "{:?}", target)
+  }
+}